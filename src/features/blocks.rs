@@ -0,0 +1,62 @@
+//! Lightweight scanning of the live document text. Used where the text being
+//! edited may not parse, so the cached AST alone cannot place the cursor.
+
+use tower_lsp::lsp_types::Position;
+
+/// Header of each block open at `position`, outermost first: the statement text
+/// before its `{`, e.g. `message User` or `option (my.config) =`. Comments and
+/// string literals are skipped.
+pub fn open_blocks(content: &str, position: Position) -> Vec<String> {
+    let mut stack = Vec::new();
+    let mut header = String::new();
+    let mut in_block_comment = false;
+
+    for (line_index, line) in content.lines().enumerate().take(position.line as usize + 1) {
+        let end = if line_index == position.line as usize {
+            (position.character as usize).min(line.len())
+        } else {
+            line.len()
+        };
+        let bytes = line.as_bytes();
+        let mut quote: Option<u8> = None;
+        let mut i = 0;
+        while i < end {
+            let b = bytes[i];
+            if in_block_comment {
+                if b == b'*' && bytes.get(i + 1) == Some(&b'/') {
+                    in_block_comment = false;
+                    i += 1;
+                }
+            } else if let Some(q) = quote {
+                if b == b'\\' {
+                    i += 1;
+                } else if b == q {
+                    quote = None;
+                }
+            } else {
+                match b {
+                    b'/' if bytes.get(i + 1) == Some(&b'/') => break,
+                    b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                        in_block_comment = true;
+                        i += 1;
+                    }
+                    b'"' | b'\'' => quote = Some(b),
+                    b'{' => {
+                        stack.push(header.trim().to_string());
+                        header.clear();
+                    }
+                    b'}' => {
+                        stack.pop();
+                        header.clear();
+                    }
+                    b';' => header.clear(),
+                    _ => header.push(b as char),
+                }
+            }
+            i += 1;
+        }
+        header.push(' ');
+    }
+
+    stack
+}
@@ -0,0 +1,340 @@
+//! Call hierarchy over types: a message "calls" the types of its fields, and
+//! is "called" by the messages embedding it and the RPCs taking or returning
+//! it. Following incoming calls up from a message answers which RPCs
+//! ultimately consume it.
+
+use crate::features::references::{for_each_type_reference, reference_target, ReferenceTarget};
+use crate::parser::proto::{EnumElement, MessageElement, MethodElement, ServiceElement, TypeReference, TypeReferenceKind};
+use crate::parser::ParsedProto;
+use crate::workspace::{TypeTable, WorkspaceManager};
+use serde::{Deserialize, Serialize};
+use tower_lsp::lsp_types::*;
+
+/// Identifies the message, enum or RPC behind a call hierarchy item, so that
+/// the incoming and outgoing call requests can find it again
+#[derive(Debug, Serialize, Deserialize)]
+struct CallItemData {
+    /// Fully-qualified name; `pkg.Service.Method` for RPCs
+    full_name: String,
+}
+
+/// Items for the symbol at the cursor: a message or enum (its declaration or
+/// any reference to it), or an RPC name
+pub async fn prepare_call_hierarchy(
+    params: CallHierarchyPrepareParams,
+    workspace: &WorkspaceManager,
+    content: Option<&str>,
+) -> Option<Vec<CallHierarchyItem>> {
+    let uri = &params.text_document_position_params.text_document.uri;
+    let position = params.text_document_position_params.position;
+    let content = content?;
+
+    let item = match reference_target(uri, workspace, content, position).await? {
+        ReferenceTarget::Type { full_name, uri, .. } => {
+            let file = workspace.get_file(&Url::parse(&uri).ok()?)?;
+            type_item(&file, &full_name)?
+        }
+        ReferenceTarget::Declaration { name, scope, .. } => {
+            let proto = workspace.get_file(uri)?;
+            let service = proto.services.iter().find(|s| s.full_name == scope)?;
+            let method = service.methods.iter().find(|m| m.name == name)?;
+            rpc_item(&proto, service, method)?
+        }
+    };
+    Some(vec![item])
+}
+
+/// Messages with a field of the item's type and RPCs taking or returning it,
+/// each with the ranges of the type names referring to it. RPCs have no
+/// incoming calls.
+pub async fn incoming_calls(
+    params: CallHierarchyIncomingCallsParams,
+    workspace: &WorkspaceManager,
+) -> Option<Vec<CallHierarchyIncomingCall>> {
+    let full_name = item_name(&params.item)?;
+    if params.item.kind == SymbolKind::METHOD {
+        return Some(Vec::new());
+    }
+
+    let mut references = Vec::new();
+    for_each_type_reference(&full_name, workspace, |location, _, type_ref, _| {
+        references.push((location, type_ref.clone()));
+    })
+    .await;
+
+    let mut calls: Vec<CallHierarchyIncomingCall> = Vec::new();
+    for (location, type_ref) in references {
+        let Some(proto) = workspace.get_file(&location.uri) else { continue };
+        let Some(from) = container_item(&proto, &type_ref) else { continue };
+        match calls.iter_mut().find(|call| same_item(&call.from, &from)) {
+            Some(call) => call.from_ranges.push(location.range),
+            None => calls.push(CallHierarchyIncomingCall { from, from_ranges: vec![location.range] }),
+        }
+    }
+    Some(calls)
+}
+
+/// Types of the item's fields, or the request and response of an RPC, each
+/// with the ranges of the type names in the item's file
+pub async fn outgoing_calls(
+    params: CallHierarchyOutgoingCallsParams,
+    workspace: &WorkspaceManager,
+) -> Option<Vec<CallHierarchyOutgoingCall>> {
+    let full_name = item_name(&params.item)?;
+    let uri = &params.item.uri;
+    let proto = workspace.get_file(uri)?;
+
+    let mut files = vec![proto.clone()];
+    files.extend(workspace.collect_all_imports_async(uri).await);
+    let table = TypeTable::from_files(files.iter().map(|f| f.as_ref()));
+
+    let mut calls: Vec<CallHierarchyOutgoingCall> = Vec::new();
+    for type_ref in &proto.type_refs {
+        let contained = match type_ref.kind {
+            TypeReferenceKind::Field | TypeReferenceKind::MapValue => type_ref.scope == full_name,
+            TypeReferenceKind::RpcInput | TypeReferenceKind::RpcOutput => {
+                rpc_at(&proto, type_ref.line).is_some_and(|(service, method)| {
+                    format!("{}.{}", service.full_name, method.name) == full_name
+                })
+            }
+            TypeReferenceKind::Extendee => false,
+        };
+        if !contained {
+            continue;
+        }
+        let Some(def) = table.resolve(&type_ref.name, &type_ref.scope) else { continue };
+        let Some(file) = Url::parse(&def.uri).ok().and_then(|uri| workspace.get_file(&uri)) else { continue };
+        let Some(to) = type_item(&file, &def.full_name) else { continue };
+
+        let range = name_range(type_ref.line, type_ref.character, type_ref.name.len());
+        match calls.iter_mut().find(|call| same_item(&call.to, &to)) {
+            Some(call) => call.from_ranges.push(range),
+            None => calls.push(CallHierarchyOutgoingCall { to, from_ranges: vec![range] }),
+        }
+    }
+    Some(calls)
+}
+
+/// Item of the message or RPC a type reference is written in. References in
+/// top-level `extend` blocks and extendees have none.
+fn container_item(proto: &ParsedProto, type_ref: &TypeReference) -> Option<CallHierarchyItem> {
+    match type_ref.kind {
+        TypeReferenceKind::Field | TypeReferenceKind::MapValue => type_item(proto, &type_ref.scope)
+            .filter(|item| item.kind == SymbolKind::CLASS),
+        TypeReferenceKind::RpcInput | TypeReferenceKind::RpcOutput => {
+            let (service, method) = rpc_at(proto, type_ref.line)?;
+            rpc_item(proto, service, method)
+        }
+        TypeReferenceKind::Extendee => None,
+    }
+}
+
+/// The RPC whose signature spans `line`
+pub fn rpc_at(proto: &ParsedProto, line: u32) -> Option<(&ServiceElement, &MethodElement)> {
+    let service = proto.services.iter().find(|s| (s.line..=s.end_line).contains(&line))?;
+    let method = service.methods.iter().rev().find(|m| m.line <= line)?;
+    Some((service, method))
+}
+
+fn type_item(proto: &ParsedProto, full_name: &str) -> Option<CallHierarchyItem> {
+    let (name, kind, range, selection_range) = type_declaration(proto, full_name)?;
+    Some(CallHierarchyItem {
+        name: name.to_string(),
+        kind,
+        tags: None,
+        detail: Some(full_name.to_string()),
+        uri: Url::parse(&proto.uri).ok()?,
+        range,
+        selection_range,
+        data: item_data(full_name),
+    })
+}
+
+/// The message or enum `full_name` declared in `proto`: its name, symbol kind,
+/// the range from the keyword to the end of the block, and the name's range
+pub fn type_declaration<'a>(proto: &'a ParsedProto, full_name: &str) -> Option<(&'a str, SymbolKind, Range, Range)> {
+    fn find<'a>(messages: &'a [MessageElement], full_name: &str) -> Option<Declared<'a>> {
+        messages.iter().find_map(|msg| {
+            if msg.full_name == full_name {
+                return Some(Declared::Message(msg));
+            }
+            msg.nested_enums
+                .iter()
+                .find(|e| e.full_name == full_name)
+                .map(Declared::Enum)
+                .or_else(|| find(&msg.nested_messages, full_name))
+        })
+    }
+    enum Declared<'a> {
+        Message(&'a MessageElement),
+        Enum(&'a EnumElement),
+    }
+
+    let declared = find(&proto.messages, full_name)
+        .or_else(|| proto.enums.iter().find(|e| e.full_name == full_name).map(Declared::Enum))?;
+    let (name, kind, keyword, line, end_line, character) = match declared {
+        Declared::Message(m) => (&m.name, SymbolKind::CLASS, "message ", m.line, m.end_line, m.character),
+        Declared::Enum(e) => (&e.name, SymbolKind::ENUM, "enum ", e.line, e.end_line, e.character),
+    };
+    let range = Range {
+        start: Position { line, character: character.saturating_sub(keyword.len() as u32) },
+        end: Position { line: end_line, character: 0 },
+    };
+    Some((name, kind, range, name_range(line, character, name.len())))
+}
+
+fn rpc_item(proto: &ParsedProto, service: &ServiceElement, method: &MethodElement) -> Option<CallHierarchyItem> {
+    let selection_range = name_range(method.line, method.character, method.name.len());
+    Some(CallHierarchyItem {
+        name: method.name.clone(),
+        kind: SymbolKind::METHOD,
+        tags: None,
+        detail: Some(format!("{}({}) returns ({})", service.name, method.input_type, method.output_type)),
+        uri: Url::parse(&proto.uri).ok()?,
+        range: Range {
+            start: Position {
+                line: method.line,
+                character: method.character.saturating_sub("rpc ".len() as u32),
+            },
+            end: selection_range.end,
+        },
+        selection_range,
+        data: item_data(&format!("{}.{}", service.full_name, method.name)),
+    })
+}
+
+fn item_data(full_name: &str) -> Option<serde_json::Value> {
+    serde_json::to_value(CallItemData { full_name: full_name.to_string() }).ok()
+}
+
+fn item_name(item: &CallHierarchyItem) -> Option<String> {
+    let data = serde_json::from_value::<CallItemData>(item.data.clone()?).ok()?;
+    Some(data.full_name)
+}
+
+fn same_item(a: &CallHierarchyItem, b: &CallHierarchyItem) -> bool {
+    a.uri == b.uri && a.data == b.data
+}
+
+pub fn name_range(line: u32, character: u32, len: usize) -> Range {
+    Range {
+        start: Position { line, character },
+        end: Position { line, character: character + len as u32 },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::testing::open_files;
+
+    fn names(items: impl IntoIterator<Item = (CallHierarchyItem, Vec<Range>)>) -> Vec<(String, Vec<u32>)> {
+        items
+            .into_iter()
+            .map(|(item, ranges)| (item.name, ranges.iter().map(|r| r.start.line).collect()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_call_hierarchy() {
+        let dir = tempfile::tempdir().unwrap();
+        let types = r#"syntax = "proto3";
+package shop;
+
+message Money {
+  int64 units = 1;
+}
+
+message Line {
+  Money price = 1;
+  map<string, Money> discounts = 2;
+  Status status = 3;
+  enum Status {
+    OPEN = 0;
+  }
+}
+"#;
+        let api = r#"syntax = "proto3";
+package shop.api;
+import "types.proto";
+
+message Order {
+  repeated shop.Line lines = 1;
+  shop.Money total = 2;
+}
+
+service Orders {
+  rpc Quote(shop.Line) returns (shop.Money);
+  rpc Place(Order)
+      returns (Order);
+}
+"#;
+        let workspace = WorkspaceManager::new();
+        let uris = open_files(&workspace, dir.path(), &[("types.proto", types), ("api.proto", api)]).await;
+        let (types_uri, api_uri) = (&uris[0], &uris[1]);
+
+        let prepare = |uri: &Url, content: &str, line: u32, character: u32| {
+            let params = CallHierarchyPrepareParams {
+                text_document_position_params: TextDocumentPositionParams {
+                    text_document: TextDocumentIdentifier { uri: uri.clone() },
+                    position: Position { line, character },
+                },
+                work_done_progress_params: Default::default(),
+            };
+            let workspace = &workspace;
+            let content = content.to_string();
+            async move { prepare_call_hierarchy(params, workspace, Some(&content)).await.unwrap().remove(0) }
+        };
+        let incoming = |item: CallHierarchyItem| async {
+            let params = CallHierarchyIncomingCallsParams {
+                item,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            };
+            let calls = incoming_calls(params, &workspace).await.unwrap();
+            names(calls.into_iter().map(|c| (c.from, c.from_ranges)))
+        };
+        let outgoing = |item: CallHierarchyItem| async {
+            let params = CallHierarchyOutgoingCallsParams {
+                item,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            };
+            let calls = outgoing_calls(params, &workspace).await.unwrap();
+            names(calls.into_iter().map(|c| (c.to, c.from_ranges)))
+        };
+
+        // Prepared from a reference in another file
+        let money = prepare(api_uri, api, 6, 9).await;
+        assert_eq!((money.name.as_str(), money.kind), ("Money", SymbolKind::CLASS));
+        assert_eq!(&money.uri, types_uri);
+        assert_eq!(money.selection_range.start, Position { line: 3, character: 8 });
+        assert_eq!(
+            incoming(money.clone()).await,
+            vec![
+                ("Order".to_string(), vec![6]),
+                ("Quote".to_string(), vec![10]),
+                ("Line".to_string(), vec![8, 9]),
+            ]
+        );
+        assert!(outgoing(money).await.is_empty());
+
+        let line = prepare(types_uri, types, 7, 9).await;
+        assert_eq!(
+            outgoing(line.clone()).await,
+            vec![("Money".to_string(), vec![8, 9]), ("Status".to_string(), vec![10])]
+        );
+        assert_eq!(
+            incoming(line).await,
+            vec![("Order".to_string(), vec![5]), ("Quote".to_string(), vec![10])]
+        );
+
+        // RPCs, including one whose signature spans lines
+        let place = prepare(api_uri, api, 11, 7).await;
+        assert_eq!((place.name.as_str(), place.kind), ("Place", SymbolKind::METHOD));
+        assert_eq!(outgoing(place.clone()).await, vec![("Order".to_string(), vec![11, 12])]);
+        assert!(incoming(place).await.is_empty());
+        let order = prepare(api_uri, api, 4, 9).await;
+        assert_eq!(incoming(order).await, vec![("Place".to_string(), vec![11, 12])]);
+    }
+}
@@ -1,3 +1,7 @@
+use crate::features::naming::to_upper_snake_case;
+use crate::features::suppressions::{parse_suppressions, IGNORE_DIRECTIVE, IGNORE_FILE_DIRECTIVE};
+use crate::parser::proto::{EnumElement, MessageElement};
+use crate::parser::ParsedProto;
 use crate::workspace::WorkspaceManager;
 use tower_lsp::lsp_types::*;
 
@@ -25,8 +29,39 @@ pub fn provide_code_actions(
                         actions.push(action);
                     }
                 }
+                NumberOrString::String(s) if s == "enum-value-scope-conflict" => {
+                    if let Some(action) = create_prefix_enum_values_action(uri, diag, workspace) {
+                        actions.push(action);
+                    }
+                }
+                NumberOrString::String(s) if s == "duplicate-enum-value-number" => {
+                    if let Some(action) =
+                        create_allow_alias_action(uri, diag, workspace, content)
+                    {
+                        actions.push(action);
+                    }
+                }
+                NumberOrString::String(s) if s == "unused-allow-alias" => {
+                    actions.push(create_remove_line_action(
+                        uri,
+                        diag,
+                        "Remove allow_alias option",
+                    ));
+                }
+                NumberOrString::String(s) if s == "unused-suppression" => {
+                    if let Some(action) = create_remove_suppression_action(uri, diag, content) {
+                        actions.push(action);
+                    }
+                }
                 _ => {}
             }
+
+            // Every other diagnostic of ours can be silenced with a comment
+            if let NumberOrString::String(s) = code {
+                if s != "unused-suppression" && diag.source.as_deref() == Some("protobuf-lsp") {
+                    actions.extend(create_suppress_actions(uri, diag, content, s));
+                }
+            }
         }
     }
 
@@ -152,6 +187,300 @@ fn create_fix_field_number_action(
     None
 }
 
+/// Create a code action that prefixes every value of the enum containing the
+/// diagnostic with the enum name in UPPER_SNAKE_CASE (e.g. `ACTIVE` in enum
+/// `Status` becomes `STATUS_ACTIVE`), the usual fix for C++ scoping conflicts.
+fn create_prefix_enum_values_action(
+    uri: &Url,
+    diag: &Diagnostic,
+    workspace: &WorkspaceManager,
+) -> Option<CodeActionOrCommand> {
+    let proto = workspace.get_file(uri)?;
+    let e = find_enum_with_value_at(&proto, diag.range.start)?;
+    let prefix = format!("{}_", to_upper_snake_case(&e.name));
+
+    let edits: Vec<TextEdit> = e
+        .values
+        .iter()
+        .filter(|v| !v.name.starts_with(&prefix))
+        .map(|v| TextEdit {
+            range: Range {
+                start: Position {
+                    line: v.line,
+                    character: v.character,
+                },
+                end: Position {
+                    line: v.line,
+                    character: v.character + v.name.len() as u32,
+                },
+            },
+            new_text: format!("{}{}", prefix, v.name),
+        })
+        .collect();
+
+    if edits.is_empty() {
+        return None;
+    }
+
+    let mut changes = std::collections::HashMap::new();
+    changes.insert(uri.clone(), edits);
+
+    Some(CodeActionOrCommand::CodeAction(CodeAction {
+        title: format!("Prefix values of enum '{}' with '{}'", e.name, prefix),
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: Some(vec![diag.clone()]),
+        edit: Some(WorkspaceEdit {
+            changes: Some(changes),
+            document_changes: None,
+            change_annotations: None,
+        }),
+        command: None,
+        is_preferred: Some(true),
+        disabled: None,
+        data: None,
+    }))
+}
+
+/// Create a code action that inserts `option allow_alias = true;` as the first
+/// statement of the enum containing the diagnostic.
+fn create_allow_alias_action(
+    uri: &Url,
+    diag: &Diagnostic,
+    workspace: &WorkspaceManager,
+    content: &str,
+) -> Option<CodeActionOrCommand> {
+    let proto = workspace.get_file(uri)?;
+    let e = find_enum_with_value_at(&proto, diag.range.start)?;
+
+    // Reuse the indentation of the value the diagnostic points at
+    let value_line = content.lines().nth(diag.range.start.line as usize)?;
+    let indent: String = value_line.chars().take_while(|c| c.is_whitespace()).collect();
+
+    let insert_at = Position {
+        line: e.line + 1,
+        character: 0,
+    };
+    let mut changes = std::collections::HashMap::new();
+    changes.insert(
+        uri.clone(),
+        vec![TextEdit {
+            range: Range {
+                start: insert_at,
+                end: insert_at,
+            },
+            new_text: format!("{}option allow_alias = true;\n", indent),
+        }],
+    );
+
+    Some(CodeActionOrCommand::CodeAction(CodeAction {
+        title: format!("Add 'option allow_alias = true;' to enum '{}'", e.name),
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: Some(vec![diag.clone()]),
+        edit: Some(WorkspaceEdit {
+            changes: Some(changes),
+            document_changes: None,
+            change_annotations: None,
+        }),
+        command: None,
+        is_preferred: Some(false),
+        disabled: None,
+        data: None,
+    }))
+}
+
+/// Create a code action that deletes the whole line the diagnostic is on.
+fn create_remove_line_action(uri: &Url, diag: &Diagnostic, title: &str) -> CodeActionOrCommand {
+    let line = diag.range.start.line;
+    let mut changes = std::collections::HashMap::new();
+    changes.insert(
+        uri.clone(),
+        vec![TextEdit {
+            range: Range {
+                start: Position { line, character: 0 },
+                end: Position {
+                    line: line + 1,
+                    character: 0,
+                },
+            },
+            new_text: String::new(),
+        }],
+    );
+
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title: title.to_string(),
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: Some(vec![diag.clone()]),
+        edit: Some(WorkspaceEdit {
+            changes: Some(changes),
+            document_changes: None,
+            change_annotations: None,
+        }),
+        command: None,
+        is_preferred: Some(true),
+        disabled: None,
+        data: None,
+    })
+}
+
+/// Create code actions inserting a `// protobuf-lsp:ignore` comment for the
+/// diagnostic's line, and a `// protobuf-lsp:ignore-file` comment for the file.
+/// Existing directives are extended rather than duplicated.
+fn create_suppress_actions(
+    uri: &Url,
+    diag: &Diagnostic,
+    content: &str,
+    code: &str,
+) -> Vec<CodeActionOrCommand> {
+    let line = diag.range.start.line;
+    let suppressions = parse_suppressions(content);
+
+    let line_edit = match suppressions
+        .iter()
+        .find(|s| s.target_line == Some(line) && !s.codes.is_empty())
+    {
+        Some(existing) => insert_edit(existing.line, existing.end_character, format!(" {}", code)),
+        None => {
+            let text = content.lines().nth(line as usize).unwrap_or("");
+            let indent = &text[..text.len() - text.trim_start().len()];
+            insert_edit(line, 0, format!("{}// {} {}\n", indent, IGNORE_DIRECTIVE, code))
+        }
+    };
+
+    let file_edit = match suppressions
+        .iter()
+        .find(|s| s.target_line.is_none() && !s.codes.is_empty())
+    {
+        Some(existing) => insert_edit(existing.line, existing.end_character, format!(" {}", code)),
+        None => insert_edit(0, 0, format!("// {} {}\n", IGNORE_FILE_DIRECTIVE, code)),
+    };
+
+    vec![
+        quick_fix(uri, diag, format!("Suppress '{}' on this line", code), vec![line_edit]),
+        quick_fix(uri, diag, format!("Suppress '{}' in this file", code), vec![file_edit]),
+    ]
+}
+
+/// Create a code action removing an unused suppression: just the unused code
+/// when the directive lists several, otherwise the whole comment.
+fn create_remove_suppression_action(
+    uri: &Url,
+    diag: &Diagnostic,
+    content: &str,
+) -> Option<CodeActionOrCommand> {
+    let line = diag.range.start.line;
+    let suppression = parse_suppressions(content).into_iter().find(|s| s.line == line)?;
+    let unused = diag
+        .data
+        .as_ref()
+        .and_then(|data| data.get("code"))
+        .and_then(|code| code.as_str());
+
+    let index = unused.and_then(|code| suppression.codes.iter().position(|c| c.code == code));
+    let range = match index {
+        Some(i) if suppression.codes.len() > 1 => {
+            let codes = &suppression.codes;
+            // Take the separator before the code, or after it for the first one
+            let (start, end) = if i > 0 {
+                (codes[i - 1].end, codes[i].end)
+            } else {
+                (codes[0].start, codes[1].start)
+            };
+            Range {
+                start: Position { line, character: start },
+                end: Position { line, character: end },
+            }
+        }
+        _ if suppression.own_line => Range {
+            start: Position { line, character: 0 },
+            end: Position {
+                line: line + 1,
+                character: 0,
+            },
+        },
+        _ => {
+            let text = content.lines().nth(line as usize)?;
+            let code_end = text[..suppression.character as usize].trim_end().len() as u32;
+            Range {
+                start: Position {
+                    line,
+                    character: code_end,
+                },
+                end: Position {
+                    line,
+                    character: suppression.end_character,
+                },
+            }
+        }
+    };
+
+    Some(quick_fix(
+        uri,
+        diag,
+        "Remove unused suppression".to_string(),
+        vec![TextEdit {
+            range,
+            new_text: String::new(),
+        }],
+    ))
+}
+
+fn insert_edit(line: u32, character: u32, new_text: String) -> TextEdit {
+    let position = Position { line, character };
+    TextEdit {
+        range: Range {
+            start: position,
+            end: position,
+        },
+        new_text,
+    }
+}
+
+/// Wrap edits to a single file in a quick fix for `diag`.
+fn quick_fix(uri: &Url, diag: &Diagnostic, title: String, edits: Vec<TextEdit>) -> CodeActionOrCommand {
+    let mut changes = std::collections::HashMap::new();
+    changes.insert(uri.clone(), edits);
+
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title,
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: Some(vec![diag.clone()]),
+        edit: Some(WorkspaceEdit {
+            changes: Some(changes),
+            document_changes: None,
+            change_annotations: None,
+        }),
+        command: None,
+        is_preferred: Some(false),
+        disabled: None,
+        data: None,
+    })
+}
+
+/// Find the enum (top-level or nested) that declares a value at `position`.
+fn find_enum_with_value_at(proto: &ParsedProto, position: Position) -> Option<&EnumElement> {
+    fn in_enums(enums: &[EnumElement], position: Position) -> Option<&EnumElement> {
+        enums.iter().find(|e| {
+            e.values
+                .iter()
+                .any(|v| v.line == position.line && v.character == position.character)
+        })
+    }
+
+    fn in_messages(messages: &[MessageElement], position: Position) -> Option<&EnumElement> {
+        for msg in messages {
+            if let Some(e) = in_enums(&msg.nested_enums, position) {
+                return Some(e);
+            }
+            if let Some(e) = in_messages(&msg.nested_messages, position) {
+                return Some(e);
+            }
+        }
+        None
+    }
+
+    in_enums(&proto.enums, position).or_else(|| in_messages(&proto.messages, position))
+}
+
 /// Check if the file has import statements.
 fn has_imports(content: &str) -> bool {
     content
@@ -254,6 +583,47 @@ message Foo {}
         assert!(action.is_some());
     }
 
+    #[tokio::test]
+    async fn test_prefix_enum_values_action() {
+        let content = r#"syntax = "proto3";
+package test;
+
+enum Color {
+    UNKNOWN = 0;
+    RED = 1;
+}
+
+enum Size {
+    UNKNOWN = 0;
+    SIZE_LARGE = 1;
+}
+"#;
+        let workspace = WorkspaceManager::new();
+        let uri = Url::parse("file:///test.proto").unwrap();
+        workspace.open_file(&uri, content).await.unwrap();
+
+        let diag = Diagnostic {
+            range: Range {
+                start: Position { line: 9, character: 4 },
+                end: Position { line: 9, character: 11 },
+            },
+            code: Some(NumberOrString::String("enum-value-scope-conflict".to_string())),
+            ..Default::default()
+        };
+        let action = create_prefix_enum_values_action(&uri, &diag, &workspace);
+        let Some(CodeActionOrCommand::CodeAction(action)) = action else {
+            panic!("expected a code action");
+        };
+        assert_eq!(action.title, "Prefix values of enum 'Size' with 'SIZE_'");
+
+        let changes = action.edit.unwrap().changes.unwrap();
+        let edits = &changes[&uri];
+        // SIZE_LARGE is already prefixed and left alone
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].new_text, "SIZE_UNKNOWN");
+        assert_eq!(edits[0].range.start, Position { line: 9, character: 4 });
+    }
+
     #[test]
     fn test_already_sorted_no_action() {
         let content = r#"syntax = "proto3";
@@ -268,4 +638,68 @@ message Foo {}
         let action = create_sort_imports_action(&uri, content);
         assert!(action.is_none());
     }
+
+    fn edits(action: &CodeActionOrCommand, uri: &Url) -> Vec<TextEdit> {
+        let CodeActionOrCommand::CodeAction(action) = action else {
+            panic!("expected a code action");
+        };
+        action.edit.as_ref().unwrap().changes.as_ref().unwrap()[uri].clone()
+    }
+
+    #[test]
+    fn test_suppress_actions() {
+        let content = "syntax = \"proto3\";\nmessage A {\n  Missing m = 1;\n  // protobuf-lsp:ignore other-rule\n  Missing n = 2;\n}\n";
+        let uri = Url::parse("file:///test.proto").unwrap();
+        let diag = |line| Diagnostic {
+            range: Range {
+                start: Position { line, character: 2 },
+                end: Position { line, character: 9 },
+            },
+            code: Some(NumberOrString::String("unresolved-type".to_string())),
+            source: Some("protobuf-lsp".to_string()),
+            ..Default::default()
+        };
+
+        let actions = create_suppress_actions(&uri, &diag(2), content, "unresolved-type");
+        assert_eq!(actions.len(), 2);
+        let line_edits = edits(&actions[0], &uri);
+        assert_eq!(line_edits[0].new_text, "  // protobuf-lsp:ignore unresolved-type\n");
+        assert_eq!(line_edits[0].range.start, Position { line: 2, character: 0 });
+        let file_edits = edits(&actions[1], &uri);
+        assert_eq!(file_edits[0].new_text, "// protobuf-lsp:ignore-file unresolved-type\n");
+
+        // An existing directive above the line is extended instead
+        let actions = create_suppress_actions(&uri, &diag(4), content, "unresolved-type");
+        let line_edits = edits(&actions[0], &uri);
+        assert_eq!(line_edits[0].new_text, " unresolved-type");
+        assert_eq!(line_edits[0].range.start, Position { line: 3, character: 35 });
+    }
+
+    #[test]
+    fn test_remove_unused_suppression_action() {
+        let content = "message A {\n  // protobuf-lsp:ignore a-rule, b-rule\n  string s = 1; // protobuf-lsp:ignore\n}\n";
+        let uri = Url::parse("file:///test.proto").unwrap();
+        let unused = |line, data| Diagnostic {
+            range: Range {
+                start: Position { line, character: 2 },
+                end: Position { line, character: 2 },
+            },
+            code: Some(NumberOrString::String("unused-suppression".to_string())),
+            data,
+            ..Default::default()
+        };
+
+        // Only the unused code is dropped, with its separator
+        let diag = unused(1, Some(serde_json::json!({ "code": "b-rule" })));
+        let action = create_remove_suppression_action(&uri, &diag, content).unwrap();
+        let edit = &edits(&action, &uri)[0];
+        assert_eq!(edit.range.start, Position { line: 1, character: 31 });
+        assert_eq!(edit.range.end, Position { line: 1, character: 39 });
+
+        // A trailing directive without codes is removed along with its padding
+        let action = create_remove_suppression_action(&uri, &unused(2, None), content).unwrap();
+        let edit = &edits(&action, &uri)[0];
+        assert_eq!(edit.range.start, Position { line: 2, character: 15 });
+        assert_eq!(edit.range.end, Position { line: 2, character: 38 });
+    }
 }
@@ -0,0 +1,268 @@
+use crate::features::call_hierarchy::rpc_at;
+use crate::features::references::for_each_reference;
+use crate::parser::proto::{MessageElement, MethodElement, TypeReferenceKind};
+use crate::parser::ParsedProto;
+use crate::workspace::{TypeTable, WorkspaceManager};
+use std::collections::{BTreeSet, HashMap};
+use tower_lsp::lsp_types::*;
+
+/// Server command of the lenses that only display information
+pub const NO_OP_COMMAND: &str = "protobuf-lsp.noop";
+
+/// Commands to advertise in `executeCommandProvider`
+pub const COMMANDS: &[&str] = &[NO_OP_COMMAND];
+
+/// Code lenses for the current file:
+/// - above each message and enum, its reference count and, for types taken or
+///   returned by RPCs, how many RPCs use it
+/// - above each service, its number of RPCs
+/// - above each RPC, its streaming kind and links to its request and response
+///   types
+///
+/// Counts come from resolving every type reference in the workspace against
+/// its file's imports, the same as find references. Clicking a counting lens
+/// runs the client command set as `codeLens.showReferencesCommand` (VS Code's
+/// `editor.action.showReferences` by default) with the document URI, the lens
+/// position and the locations it counts.
+pub async fn provide_code_lenses(params: CodeLensParams, workspace: &WorkspaceManager) -> Option<Vec<CodeLens>> {
+    let uri = &params.text_document.uri;
+    let proto = workspace.get_file(uri)?;
+    let command = workspace.settings().show_references_command;
+    let show_locations = |position, title, locations| show_locations(&command, uri, position, title, locations);
+
+    let mut types = Vec::new();
+    collect_types(&proto.messages, &mut types);
+    types.extend(proto.enums.iter().map(|e| (e.full_name.as_str(), e.line, e.character)));
+
+    let mut references: HashMap<String, Vec<Location>> = HashMap::new();
+    let mut rpc_uses: HashMap<String, (BTreeSet<String>, Vec<Location>)> = HashMap::new();
+    for_each_reference(workspace, |full_name, location, file, type_ref| {
+        if !types.iter().any(|(name, _, _)| *name == full_name) {
+            return;
+        }
+        // The whole written name, not a qualifier of it, is what the RPC takes
+        let whole = location.range.end.character == type_ref.character + type_ref.name.len() as u32;
+        if whole && matches!(type_ref.kind, TypeReferenceKind::RpcInput | TypeReferenceKind::RpcOutput) {
+            if let Some((service, method)) = rpc_at(file, type_ref.line) {
+                let (rpcs, locations) = rpc_uses.entry(full_name.to_string()).or_default();
+                rpcs.insert(format!("{}.{}", service.full_name, method.name));
+                locations.push(location.clone());
+            }
+        }
+        references.entry(full_name.to_string()).or_default().push(location);
+    })
+    .await;
+
+    let mut lenses = Vec::new();
+    for (full_name, line, character) in &types {
+        let position = Position { line: *line, character: *character };
+        let locations = references.remove(*full_name).unwrap_or_default();
+        lenses.push(show_locations(position, count(locations.len(), "reference"), locations));
+        if let Some((rpcs, locations)) = rpc_uses.remove(*full_name) {
+            let title = format!("used by {}", count(rpcs.len(), "RPC"));
+            lenses.push(show_locations(position, title, locations));
+        }
+    }
+
+    let mut files = vec![proto.clone()];
+    files.extend(workspace.collect_all_imports_async(uri).await);
+    let table = TypeTable::from_files(files.iter().map(|f| f.as_ref()));
+    for service in &proto.services {
+        let position = Position { line: service.line, character: service.character };
+        let methods = service
+            .methods
+            .iter()
+            .map(|m| name_location(uri, m.line, m.character, &m.name))
+            .collect::<Vec<_>>();
+        lenses.push(show_locations(position, count(methods.len(), "RPC"), methods));
+
+        for method in &service.methods {
+            lenses.extend(rpc_lenses(&proto, &table, method, show_locations));
+        }
+    }
+
+    lenses.sort_by_key(|lens| (lens.range.start.line, lens.range.start.character));
+    Some(lenses)
+}
+
+/// The streaming kind of an RPC, and a lens showing each of its request and
+/// response types that resolves
+fn rpc_lenses(
+    proto: &ParsedProto,
+    table: &TypeTable,
+    method: &MethodElement,
+    show_locations: impl Fn(Position, String, Vec<Location>) -> CodeLens,
+) -> Vec<CodeLens> {
+    let position = Position { line: method.line, character: method.character };
+    let kind = match (method.client_streaming, method.server_streaming) {
+        (false, false) => "unary",
+        (true, false) => "client streaming",
+        (false, true) => "server streaming",
+        (true, true) => "bidi streaming",
+    };
+    let mut lenses = vec![CodeLens {
+        range: Range { start: position, end: position },
+        command: Some(Command { title: kind.to_string(), command: NO_OP_COMMAND.to_string(), arguments: None }),
+        data: None,
+    }];
+
+    for (label, kind) in [("request", TypeReferenceKind::RpcInput), ("response", TypeReferenceKind::RpcOutput)] {
+        let Some(type_ref) = proto.type_refs.iter().find(|r| {
+            r.kind == kind && rpc_at(proto, r.line).is_some_and(|(_, m)| std::ptr::eq(m, method))
+        }) else {
+            continue;
+        };
+        let Some(def) = table.resolve(&type_ref.name, &type_ref.scope) else { continue };
+        let Ok(def_uri) = Url::parse(&def.uri) else { continue };
+        let declaration = name_location(&def_uri, def.line, def.character, &def.name);
+        lenses.push(show_locations(position, format!("{}: {}", label, def.name), vec![declaration]));
+    }
+    lenses
+}
+
+/// Messages and the enums nested in them, with the position of their names
+fn collect_types<'a>(messages: &'a [MessageElement], out: &mut Vec<(&'a str, u32, u32)>) {
+    for msg in messages {
+        out.push((&msg.full_name, msg.line, msg.character));
+        out.extend(msg.nested_enums.iter().map(|e| (e.full_name.as_str(), e.line, e.character)));
+        collect_types(&msg.nested_messages, out);
+    }
+}
+
+/// A lens running the client's show references `command` with the arguments
+/// of VS Code's `editor.action.showReferences`: URI, position and locations
+fn show_locations(command: &str, uri: &Url, position: Position, title: String, locations: Vec<Location>) -> CodeLens {
+    let arguments = [serde_json::to_value(uri), serde_json::to_value(position), serde_json::to_value(locations)]
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .ok();
+    CodeLens {
+        range: Range { start: position, end: position },
+        command: Some(Command { title, command: command.to_string(), arguments }),
+        data: None,
+    }
+}
+
+fn name_location(uri: &Url, line: u32, character: u32, name: &str) -> Location {
+    Location {
+        uri: uri.clone(),
+        range: Range {
+            start: Position { line, character },
+            end: Position { line, character: character + name.len() as u32 },
+        },
+    }
+}
+
+fn count(n: usize, noun: &str) -> String {
+    format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::testing::open_files;
+
+    #[tokio::test]
+    async fn test_code_lenses() {
+        let dir = tempfile::tempdir().unwrap();
+        let types = r#"syntax = "proto3";
+package shop;
+
+message Money {
+  int64 units = 1;
+}
+
+message Order {
+  Money total = 1;
+  enum Status {
+    OPEN = 0;
+  }
+  Status status = 2;
+}
+"#;
+        let api = r#"syntax = "proto3";
+package shop.api;
+import "types.proto";
+
+message GetOrder {
+  string id = 1;
+}
+
+service Orders {
+  rpc Get(GetOrder) returns (shop.Order);
+  rpc Watch(GetOrder) returns (stream shop.Order);
+  rpc Total(stream shop.Order.Status) returns (shop.Money);
+}
+"#;
+        // Does not import `types.proto`, so `Money` does not resolve here
+        let stray = "syntax = \"proto3\";\npackage shop;\n\nmessage Stray {\n  Money money = 1;\n}\n";
+        let workspace = WorkspaceManager::new();
+        let files = [("types.proto", types), ("api.proto", api), ("stray.proto", stray)];
+        let uris = open_files(&workspace, dir.path(), &files).await;
+
+        let lenses = |uri: &Url| {
+            let params = CodeLensParams {
+                text_document: TextDocumentIdentifier { uri: uri.clone() },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            };
+            let workspace = &workspace;
+            async move {
+                provide_code_lenses(params, workspace)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|lens| (lens.range.start.line, lens.command.unwrap().title))
+                    .collect::<Vec<_>>()
+            }
+        };
+        let titles = |lenses: &[(u32, String)], line: u32| {
+            lenses.iter().filter(|(l, _)| *l == line).map(|(_, t)| t.as_str()).collect::<Vec<_>>().join(" | ")
+        };
+
+        let type_lenses = lenses(&uris[0]).await;
+        assert_eq!(titles(&type_lenses, 3), "2 references | used by 1 RPC");
+        // `shop.Order.Status` also refers to `shop.Order`, but only as a qualifier
+        assert_eq!(titles(&type_lenses, 7), "3 references | used by 2 RPCs");
+        assert_eq!(titles(&type_lenses, 9), "2 references | used by 1 RPC");
+
+        let api_lenses = lenses(&uris[1]).await;
+        assert_eq!(titles(&api_lenses, 4), "2 references | used by 2 RPCs");
+        assert_eq!(titles(&api_lenses, 8), "3 RPCs");
+        assert_eq!(titles(&api_lenses, 9), "unary | request: GetOrder | response: Order");
+        assert_eq!(titles(&api_lenses, 10), "server streaming | request: GetOrder | response: Order");
+        assert_eq!(titles(&api_lenses, 11), "client streaming | request: Status | response: Money");
+
+        // Clicking shows the counted locations
+        let params = CodeLensParams {
+            text_document: TextDocumentIdentifier { uri: uris[0].clone() },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let lens = provide_code_lenses(params.clone(), &workspace).await.unwrap().remove(0);
+        let command = lens.command.unwrap();
+        assert_eq!(command.command, "editor.action.showReferences");
+        let arguments = command.arguments.unwrap();
+        assert_eq!(serde_json::from_value::<Url>(arguments[0].clone()).unwrap(), uris[0]);
+        assert_eq!(serde_json::from_value::<Position>(arguments[1].clone()).unwrap(), lens.range.start);
+        let locations: Vec<Location> = serde_json::from_value(arguments[2].clone()).unwrap();
+        assert_eq!(locations.len(), 2);
+        assert_eq!(locations[0].uri, uris[1]);
+
+        // Clients name their own command
+        workspace.update_settings(&serde_json::json!({ "codeLens": { "showReferencesCommand": "myEditor.peek" } }));
+        let lens = provide_code_lenses(params, &workspace).await.unwrap().remove(0);
+        assert_eq!(lens.command.unwrap().command, "myEditor.peek");
+
+        // Streaming kinds are informational only
+        let params = CodeLensParams {
+            text_document: TextDocumentIdentifier { uri: uris[1].clone() },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let lenses = provide_code_lenses(params, &workspace).await.unwrap();
+        let unary = lenses.iter().filter_map(|l| l.command.as_ref()).find(|c| c.title == "unary").unwrap();
+        assert_eq!(unary.command, NO_OP_COMMAND);
+        assert!(COMMANDS.contains(&unary.command.as_str()));
+    }
+}
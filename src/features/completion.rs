@@ -1,8 +1,20 @@
-use crate::parser::ParsedProto;
-use crate::workspace::{WorkspaceManager, SymbolKind};
+use crate::features::blocks::open_blocks;
+use crate::features::hover::{format_enum_hover, format_message_hover};
+use crate::features::options::provide_option_completions;
+use crate::features::snippets::snippet_completions;
+use crate::features::fuzzy::fuzzy_score;
+use crate::features::naming::{field_name_suggestions, field_type_before_name, to_upper_snake_case};
+use crate::features::numbering::{enclosing_numbered_block, is_number_assignment, EditedLine, NumberedBlock};
+use crate::parser::proto::EnumElement;
+use crate::parser::{MessageElement, ParsedProto};
+use crate::workspace::{SymbolKind, TypeTable, WorkspaceManager};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use tower_lsp::lsp_types::{
-    CompletionItem, CompletionItemKind, CompletionParams, CompletionResponse, Documentation,
-    MarkupContent, MarkupKind, Position, Url,
+    CompletionItem, CompletionItemKind, CompletionItemTag, CompletionList, CompletionParams, CompletionResponse,
+    CompletionTextEdit, Documentation, InsertTextFormat,
+    MarkupContent, MarkupKind, Position, Range, TextEdit, Url,
 };
 
 const PROTO_KEYWORDS: &[&str] = &[
@@ -24,6 +36,9 @@ const PROTO_KEYWORDS: &[&str] = &[
     "map",
 ];
 
+/// Most items returned at once; longer lists are cut and marked incomplete
+const MAX_COMPLETION_ITEMS: usize = 200;
+
 const PROTO_TYPES: &[&str] = &[
     "double", "float", "int32", "int64", "uint32", "uint64", "sint32", "sint64", "fixed32",
     "fixed64", "sfixed32", "sfixed64", "bool", "string", "bytes",
@@ -39,20 +54,46 @@ pub async fn provide_completion(
 
     let proto = workspace.get_file(&uri)?;
 
+    let content = document_content?;
+
+    // Inside an option only option names and values make sense
+    if let Some(items) = provide_option_completions(&uri, workspace, content, position).await {
+        return Some(CompletionResponse::Array(items));
+    }
+
     // Get context from cursor position
-    let context = document_content.map(|content| get_completion_context(content, position, &proto))?;
+    let context = get_completion_context(content, position, &proto);
+
+    // `=` only triggers completion for field and enum value numbers, `"` and `/`
+    // only for import paths
+    let trigger = params.context.as_ref().and_then(|c| c.trigger_character.as_deref());
+    match trigger {
+        Some("=") if context.next_numbers.is_empty() => return None,
+        Some("\"" | "/") if context.import_path.is_none() => return None,
+        _ => {}
+    }
 
     
     // Add completion items based on context
     let mut items = Vec::new();
 
     // Add items with appropriate priority based on context
-    add_contextual_completions(&context, &proto, workspace, &uri, &mut items).await;
+    add_contextual_completions(&context, &proto, workspace, &uri, content, &mut items).await;
 
     
     // Sort items by priority (lower sort_text = higher priority)
     items.sort_by(|a, b| a.sort_text.as_ref().unwrap_or(&"0".to_string()).cmp(b.sort_text.as_ref().unwrap_or(&"0".to_string())));
 
+    // Large workspaces: send the best items and have the client ask again as
+    // more is typed
+    if items.len() > MAX_COMPLETION_ITEMS {
+        items.truncate(MAX_COMPLETION_ITEMS);
+        return Some(CompletionResponse::List(CompletionList {
+            is_incomplete: true,
+            items,
+        }));
+    }
+
     Some(CompletionResponse::Array(items))
 }
 
@@ -79,6 +120,29 @@ struct CompletionContext {
     typing_package_name: bool,
     /// The partial package name being typed
     partial_package: Option<String>,
+    /// Free numbers to offer after `name =` in a message or enum, with a description
+    next_numbers: Vec<(i32, String)>,
+    /// Inside `import "…`: position after the opening quote and the path typed so far
+    import_path: Option<(Position, String)>,
+    /// Cursor position
+    position: Position,
+    /// Scope names resolve from: the package followed by the enclosing messages
+    scope: String,
+    /// Field names to suggest after the type of a field declaration
+    field_names: Vec<String>,
+    /// Inside an enum where a value can start
+    enum_value: Option<EnumValueContext>,
+    /// Word typed before the cursor, after any `.`
+    typed: String,
+}
+
+#[derive(Debug, Clone)]
+struct EnumValueContext {
+    /// The enum name in UPPER_SNAKE_CASE plus `_`, e.g. `STATUS_`
+    prefix: String,
+    /// Whether the enum still lacks its zero value
+    needs_zero: bool,
+    next_number: Option<i32>,
 }
 
 /// Gets the completion context based on cursor position
@@ -166,8 +230,7 @@ fn get_completion_context(content: &str, position: Position, proto: &ParsedProto
     // Analyze the identifier to determine context
     let (package_prefix, typing_package_name, partial_package) = if identifier.contains('.') {
         // Has dots - check if it ends with a dot (package prefix)
-        if identifier.ends_with('.') {
-            let pkg_name = &identifier[..identifier.len() - 1];
+        if let Some(pkg_name) = identifier.strip_suffix('.') {
             if pkg_name.chars().all(|c| c.is_lowercase() || c.is_ascii_digit() || c == '_') {
                 (Some(identifier.to_string()), false, None)
             } else {
@@ -203,6 +266,58 @@ fn get_completion_context(content: &str, position: Position, proto: &ParsedProto
         }
     };
 
+    let block = enclosing_numbered_block(proto, content, position);
+    let edited = EditedLine { line: position.line, text: &current_line };
+    let next_numbers = match block {
+        Some(block) if is_number_assignment(&prefix, &block) => {
+            let what = match block {
+                NumberedBlock::Message(_) => "field number",
+                NumberedBlock::Enum(_) => "enum value number",
+            };
+            let mut numbers = Vec::new();
+            // The line being typed may still be in the last good parse
+            let next = block.next_number(Some(edited));
+            if let Some(next) = next {
+                numbers.push((next, format!("Next free {}", what)));
+            }
+            if let Some(lowest) = block.lowest_free_number(Some(edited)) {
+                if next.is_some_and(|next| lowest < next) {
+                    numbers.push((lowest, format!("Lowest unused {}", what)));
+                }
+            }
+            numbers
+        }
+        _ => Vec::new(),
+    };
+
+    // Names already taken in the message are not suggested again
+    let field_names = match (block, field_type_before_name(&prefix)) {
+        (Some(NumberedBlock::Message(msg)), Some((type_name, repeated))) => {
+            field_name_suggestions(&type_name, repeated)
+                .into_iter()
+                .filter(|name| !msg.fields.iter().any(|f| &f.name == name && !edited.declares(f.line, &f.name)))
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+
+    let enum_value = match block {
+        Some(NumberedBlock::Enum(e)) if prefix.trim_start().chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => {
+            Some(EnumValueContext {
+                prefix: format!("{}_", to_upper_snake_case(&e.name)),
+                needs_zero: !e.values.iter().any(|v| v.number == 0 && !edited.declares(v.line, &v.name)),
+                next_number: NumberedBlock::Enum(e).next_number(Some(edited)),
+            })
+        }
+        _ => None,
+    };
+
+    let import_path = import_path_prefix(&prefix).map(|(character, typed)| {
+        (Position { line: position.line, character }, typed)
+    });
+
+    let typed = identifier.rsplit('.').next().unwrap_or("").to_string();
+
     CompletionContext {
         _current_line: current_line,
         _prefix: prefix,
@@ -214,7 +329,140 @@ fn get_completion_context(content: &str, position: Position, proto: &ParsedProto
         package_prefix,
         typing_package_name,
         partial_package,
+        next_numbers,
+        import_path,
+        position,
+        scope: cursor_scope(content, position, proto.package.as_deref()),
+        field_names,
+        enum_value,
+        typed,
+    }
+}
+
+/// Fully-qualified name of the innermost message enclosing `position`, or the
+/// package at top level and in services
+fn cursor_scope(content: &str, position: Position, package: Option<&str>) -> String {
+    let mut scope: Vec<&str> = package.into_iter().collect();
+    let blocks = open_blocks(content, position);
+    for header in &blocks {
+        let mut words = header.split_whitespace();
+        if let (Some("message"), Some(name)) = (words.next(), words.next()) {
+            scope.push(name);
+        }
     }
+    scope.join(".")
+}
+
+/// If `prefix` ends inside the string of an import statement, returns the column
+/// after the opening quote and the path typed so far
+fn import_path_prefix(prefix: &str) -> Option<(u32, String)> {
+    let rest = prefix.trim_start().strip_prefix("import")?;
+    let rest = rest.trim_start();
+    let rest = rest
+        .strip_prefix("public")
+        .or_else(|| rest.strip_prefix("weak"))
+        .unwrap_or(rest)
+        .trim_start();
+    let typed = rest.strip_prefix(['"', '\''])?;
+    if typed.contains(['"', '\'']) {
+        return None;
+    }
+    Some(((prefix.len() - typed.len()) as u32, typed.to_string()))
+}
+
+/// Identifies the message or enum behind a completion item, so that
+/// `completionItem/resolve` can find it again
+#[derive(Debug, Serialize, Deserialize)]
+struct TypeItemData {
+    /// File defining the type
+    uri: String,
+    full_name: String,
+    /// File completion was requested in
+    from: String,
+    /// Import added when the item is accepted
+    import: Option<String>,
+}
+
+impl TypeItemData {
+    fn to_data(uri: &str, full_name: &str, from: &Url, import: Option<&str>) -> Option<serde_json::Value> {
+        serde_json::to_value(TypeItemData {
+            uri: uri.to_string(),
+            full_name: full_name.to_string(),
+            from: from.to_string(),
+            import: import.map(str::to_string),
+        })
+        .ok()
+    }
+}
+
+/// Fills in the documentation of a message or enum completion item: its leading
+/// comment, a summary of its fields or values, the defining file and the import
+/// accepting it adds. Other items are returned unchanged.
+pub fn resolve_completion_item(mut item: CompletionItem, workspace: &WorkspaceManager) -> CompletionItem {
+    let Some(data) = item
+        .data
+        .clone()
+        .and_then(|data| serde_json::from_value::<TypeItemData>(data).ok())
+    else {
+        return item;
+    };
+    let Some(uri) = Url::parse(&data.uri).ok() else {
+        return item;
+    };
+    let Some(file) = workspace.get_file(&uri) else {
+        return item;
+    };
+
+    let mut messages = Vec::new();
+    collect_messages(&file.messages, &mut messages);
+    let message = messages.into_iter().find(|m| m.full_name == data.full_name);
+    let enumeration = file
+        .enums
+        .iter()
+        .find(|e| e.full_name == data.full_name)
+        .or_else(|| file.messages.iter().find_map(|m| find_enum(m, &data.full_name)));
+    let (comment, summary) = match (message, enumeration) {
+        (Some(msg), _) => (msg.comment.clone(), format_message_hover(msg)),
+        (None, Some(e)) => (e.comment.clone(), format_enum_hover(e)),
+        (None, None) => return item,
+    };
+
+    let mut value = String::new();
+    if let Some(comment) = comment {
+        value.push_str(&comment);
+        value.push_str("\n\n");
+    }
+    value.push_str(&summary);
+
+    let defined_in = data
+        .import
+        .clone()
+        .or_else(|| Url::parse(&data.from).ok().and_then(|from| workspace.import_path_for(&from, &uri)))
+        .unwrap_or_else(|| uri.path().rsplit('/').next().unwrap_or_default().to_string());
+    value.push_str(&format!("\n\nDefined in `{}`", defined_in));
+    if let Some(import) = &data.import {
+        value.push_str(&format!("\n\nAdds `import \"{}\";`", import));
+    }
+
+    item.documentation = Some(Documentation::MarkupContent(MarkupContent {
+        kind: MarkupKind::Markdown,
+        value,
+    }));
+    item
+}
+
+fn collect_messages<'a>(messages: &'a [MessageElement], out: &mut Vec<&'a MessageElement>) {
+    for msg in messages {
+        out.push(msg);
+        collect_messages(&msg.nested_messages, out);
+    }
+}
+
+fn find_enum<'a>(msg: &'a MessageElement, full_name: &str) -> Option<&'a EnumElement> {
+    msg.nested_enums
+        .iter()
+        .find(|e| e.full_name == full_name)
+        .or_else(|| msg.nested_messages.iter().find_map(|m| find_enum(m, full_name)))
 }
 
 /// Adds completion items based on context
@@ -223,8 +471,74 @@ async fn add_contextual_completions(
     proto: &ParsedProto,
     workspace: &WorkspaceManager,
     uri: &Url,
+    content: &str,
     items: &mut Vec<CompletionItem>,
 ) {
+    // Inside `import "…"` only paths make sense
+    if let Some((start, typed)) = &context.import_path {
+        add_import_path_completions(workspace, uri, proto, *start, typed, items);
+        return;
+    }
+
+    // After `name =` in a message or enum only a number fits
+    if !context.next_numbers.is_empty() {
+        for (i, (number, detail)) in context.next_numbers.iter().enumerate() {
+            items.push(CompletionItem {
+                label: number.to_string(),
+                kind: Some(CompletionItemKind::VALUE),
+                detail: Some(detail.clone()),
+                sort_text: Some(format!("0{}", i)),
+                preselect: Some(i == 0),
+                ..Default::default()
+            });
+        }
+        return;
+    }
+
+    // After the type of a field only its name is left to write
+    if !context.field_names.is_empty() {
+        for (i, name) in context.field_names.iter().enumerate() {
+            items.push(CompletionItem {
+                label: name.clone(),
+                kind: Some(CompletionItemKind::FIELD),
+                detail: Some("Field name".to_string()),
+                sort_text: Some(format!("0{}", i)),
+                preselect: Some(i == 0),
+                ..Default::default()
+            });
+        }
+        return;
+    }
+
+    // Enum values are prefixed with the enum name, starting with the zero value
+    if let Some(enum_value) = &context.enum_value {
+        let value_prefix = &enum_value.prefix;
+        if enum_value.needs_zero {
+            let name = format!("{}UNSPECIFIED", value_prefix);
+            items.push(CompletionItem {
+                label: name.clone(),
+                kind: Some(CompletionItemKind::ENUM_MEMBER),
+                detail: Some("Zero value".to_string()),
+                sort_text: Some("00".to_string()),
+                insert_text: Some(format!("{} = 0;", name)),
+                preselect: Some(true),
+                ..Default::default()
+            });
+        } else if let Some(next) = enum_value.next_number {
+            items.push(CompletionItem {
+                label: format!("{}…", value_prefix),
+                kind: Some(CompletionItemKind::ENUM_MEMBER),
+                detail: Some("Enum value".to_string()),
+                sort_text: Some("00".to_string()),
+                filter_text: Some(value_prefix.clone()),
+                insert_text: Some(format!("{}${{1:VALUE}} = {};$0", value_prefix, next)),
+                insert_text_format: Some(InsertTextFormat::SNIPPET),
+                preselect: Some(true),
+                ..Default::default()
+            });
+        }
+    }
+
     // If we're typing a package name (without dot), suggest available packages.
     // At top level this is the only useful completion, so we return early. Inside
     // a message/service body the user may also want built-in types, keywords and
@@ -313,6 +627,7 @@ async fn add_contextual_completions(
                     kind: Some(kind),
                     detail: Some(format!("{}: {}", format!("{:?}", symbol.kind).to_lowercase(), symbol.full_name)),
                     sort_text: Some(format!("0{}", symbol.name)), // High priority for package symbols
+                    tags: deprecated_tags(symbol.deprecated),
                     ..Default::default()
                 });
             }
@@ -437,23 +752,26 @@ async fn add_contextual_completions(
         }
     }
 
-    // Add messages with priority based on package context
-    add_messages_with_priority(&proto, items, context, priority_base);
-
-    // Add enums with priority
-    add_enums_with_priority(&proto, items, context, priority_base);
-
-    // Add services with priority
-    add_services_with_priority(&proto, items, context, priority_base);
-
     // Add items from imported files with lower priority. Use the async recursive
     // collector so transitive imports are loaded from disk on demand (not just
     // files the editor has did_open-ed).
     let all_imports = workspace.collect_all_imports_async(uri).await;
+    let types = TypeTable::from_files(std::iter::once(proto).chain(all_imports.iter().map(|p| p.as_ref())));
+
+    // Messages and enums, named as they resolve from the cursor
+    add_types_with_priority(proto, &types, uri, items, context, priority_base);
+
+    // Add services with priority
+    add_services_with_priority(proto, items, context, priority_base);
+
     for imported in &all_imports {
-        add_messages_with_priority(&imported, items, context, "5"); // Lowest priority
-        add_enums_with_priority(&imported, items, context, "5");
-        add_services_with_priority(&imported, items, context, "5");
+        add_types_with_priority(imported, &types, uri, items, context, "5"); // Lowest priority
+        add_services_with_priority(imported, items, context, "5");
+    }
+
+    // Messages and enums from the rest of the workspace, imported on accept
+    if context.in_message || context.in_service {
+        add_auto_import_completions(proto, &all_imports, &types, workspace, uri, content, items);
     }
 
     // Add remaining keywords with lowest priority (except extend which gets medium-low priority)
@@ -500,91 +818,312 @@ async fn add_contextual_completions(
             });
         }
     }
+
+    // Block templates for the constructs valid at the cursor
+    let settings = workspace.settings();
+    items.extend(snippet_completions(proto, content, context.position, &settings, priority_base));
+
+    rank_completions(items, context, proto, &types, workspace, uri);
 }
 
-/// Adds messages to completion with appropriate priority
-fn add_messages_with_priority(proto: &ParsedProto, items: &mut Vec<CompletionItem>, context: &CompletionContext, priority_base: &str) {
-    for msg in &proto.messages {
-        // Higher priority for messages in the same package
-        let priority = if let (Some(current_pkg), Some(msg_pkg)) = (&context.current_package, msg.full_name.split('.').nth(0)) {
-            if current_pkg == msg_pkg {
-                format!("{}{}", priority_base, "0")
-            } else {
-                format!("{}{}", priority_base, "1")
+/// Orders the items by how well they match the typed word (see `fuzzy_score`),
+/// their context priority, proximity of the defining file (same file, same
+/// package, direct import, transitive import, not imported) and how often the
+/// type is already used in this file. Items that do not match are kept, after
+/// the matches: the client filters the list itself as typing continues, and
+/// must still see them when characters are deleted.
+fn rank_completions(
+    items: &mut Vec<CompletionItem>,
+    context: &CompletionContext,
+    proto: &ParsedProto,
+    types: &TypeTable,
+    workspace: &WorkspaceManager,
+    uri: &Url,
+) {
+    let mut usage: HashMap<&str, i32> = HashMap::new();
+    for type_ref in &proto.type_refs {
+        if let Some(def) = types.resolve(&type_ref.name, &type_ref.scope) {
+            *usage.entry(def.full_name.as_str()).or_default() += 1;
+        }
+    }
+    let direct_imports: HashSet<String> = proto
+        .imports
+        .iter()
+        .filter_map(|import| workspace.resolve_import(uri, &import.path))
+        .filter_map(|path| Url::from_file_path(path).ok())
+        .map(|url| url.to_string())
+        .collect();
+
+    let mut scored: Vec<(bool, i32, CompletionItem)> = items
+        .drain(..)
+        .map(|item| {
+            let text = item.filter_text.as_deref().unwrap_or(&item.label);
+            let matched = fuzzy_score(&context.typed, text);
+            let sort_text = item.sort_text.clone().unwrap_or_default();
+            // Context priority: the leading digit, lower is better
+            let priority = sort_text.chars().next().and_then(|c| c.to_digit(10)).unwrap_or(9) as i32;
+
+            let mut proximity = 0;
+            let mut uses = 0;
+            if let Some(data) = item.data.clone().and_then(|d| serde_json::from_value::<TypeItemData>(d).ok()) {
+                proximity = if data.import.is_some() {
+                    0
+                } else if data.uri == proto.uri {
+                    30
+                } else if direct_imports.contains(&data.uri) {
+                    15
+                } else {
+                    5
+                };
+                let same_package = Url::parse(&data.uri)
+                    .ok()
+                    .and_then(|file| workspace.get_file(&file))
+                    .is_some_and(|file| file.package == proto.package);
+                if same_package {
+                    proximity += 10;
+                }
+                uses = usage.get(data.full_name.as_str()).copied().unwrap_or(0);
             }
-        } else {
-            format!("{}{}", priority_base, "2")
+
+            // Preselected items are the best guess for this position
+            let preselected = if item.preselect == Some(true) { 100 } else { 0 };
+            let score = matched.unwrap_or(0) * 2 + (9 - priority) * 5 + proximity + (uses * 6).min(30) + preselected;
+            (matched.is_some(), score, item)
+        })
+        .collect();
+
+    scored.sort_by(|(a_matched, a_score, a), (b_matched, b_score, b)| {
+        b_matched
+            .cmp(a_matched)
+            .then_with(|| b_score.cmp(a_score))
+            .then_with(|| a.sort_text.cmp(&b.sort_text))
+            .then_with(|| a.label.cmp(&b.label))
+    });
+    for (rank, (_, _, mut item)) in scored.into_iter().enumerate() {
+        item.sort_text = Some(format!("{:05}", rank));
+        items.push(item);
+    }
+}
+
+/// Messages and enums of workspace files that are neither this file nor one of
+/// its imports. Accepting an item inserts the type, qualified for the current
+/// package, and adds the import in sorted position.
+fn add_auto_import_completions(
+    proto: &ParsedProto,
+    imports: &[Arc<ParsedProto>],
+    visible_types: &TypeTable,
+    workspace: &WorkspaceManager,
+    uri: &Url,
+    content: &str,
+    items: &mut Vec<CompletionItem>,
+) {
+    let visible: HashSet<&str> = std::iter::once(proto.uri.as_str())
+        .chain(imports.iter().map(|p| p.uri.as_str()))
+        .collect();
+
+    for (file_uri, file) in workspace.get_all_files() {
+        if visible.contains(file_uri.as_str()) {
+            continue;
+        }
+        let Some(import_path) = Url::parse(&file_uri)
+            .ok()
+            .and_then(|target| workspace.import_path_for(uri, &target))
+        else {
+            continue;
         };
+        let import_edits = import_insertion_edits(content, &[&import_path]);
 
-        items.push(CompletionItem {
-            label: msg.name.clone(),
-            kind: Some(CompletionItemKind::CLASS),
-            detail: Some(format!("Message: {}", msg.full_name)),
-            documentation: Some(Documentation::MarkupContent(MarkupContent {
-                kind: MarkupKind::Markdown,
-                value: format!("```protobuf\nmessage {}\n```", msg.name),
-            })),
-            sort_text: Some(priority),
-            ..Default::default()
-        });
+        let mut types = Vec::new();
+        collect_importable_types(&file.messages, &file.enums, &mut types);
+        for (name, full_name, kind, deprecated) in types {
+            // Already reachable under the same name through another file
+            if visible_types.resolve(&format!(".{}", full_name), "").is_some() {
+                continue;
+            }
+            let insert_text = match (&file.package, &proto.package) {
+                (Some(theirs), Some(ours)) if theirs == ours => {
+                    full_name[theirs.len() + 1..].to_string()
+                }
+                _ => full_name.to_string(),
+            };
+            items.push(CompletionItem {
+                label: name.to_string(),
+                kind: Some(kind),
+                detail: Some(format!("{} (import \"{}\")", full_name, import_path)),
+                sort_text: Some(format!("5~{}", name)),
+                filter_text: Some(name.to_string()),
+                insert_text: Some(insert_text),
+                additional_text_edits: Some(import_edits.clone()),
+                tags: deprecated_tags(deprecated),
+                data: TypeItemData::to_data(&file_uri, full_name, uri, Some(&import_path)),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+/// Name, full name, kind and deprecation of every message and enum, including nested ones
+fn collect_importable_types<'a>(
+    messages: &'a [MessageElement],
+    enums: &'a [EnumElement],
+    out: &mut Vec<(&'a str, &'a str, CompletionItemKind, bool)>,
+) {
+    for msg in messages {
+        out.push((&msg.name, &msg.full_name, CompletionItemKind::CLASS, msg.deprecated));
+        collect_importable_types(&msg.nested_messages, &msg.nested_enums, out);
+    }
+    for e in enums {
+        out.push((&e.name, &e.full_name, CompletionItemKind::ENUM, e.deprecated));
+    }
+}
+
+/// Edits adding `import "<path>";` for each of `paths`: before the first import
+/// that sorts after it, or after the last import. When the file has no imports
+/// yet, a single block goes after the package (or syntax) statement.
+pub fn import_insertion_edits(content: &str, paths: &[&str]) -> Vec<TextEdit> {
+    let mut paths = paths.to_vec();
+    paths.sort_unstable();
+    let statement = |path: &str| format!("import \"{}\";\n", path);
 
-        // Add nested messages
-        add_nested_messages_with_priority(msg, items, context, &format!("{}{}", priority_base, "1"));
+    let mut imports: Vec<(u32, &str)> = Vec::new();
+    let mut header = None;
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("import") {
+            imports.push((i as u32, rest.split('"').nth(1).unwrap_or("")));
+        } else if trimmed.starts_with("package") || (header.is_none() && (trimmed.starts_with("syntax") || trimmed.starts_with("edition"))) {
+            header = Some(i as u32);
+        }
     }
+
+    let Some(&(last_import, _)) = imports.last() else {
+        let block: String = paths.iter().map(|p| statement(p)).collect();
+        return vec![match header {
+            Some(line) => insert_at(line + 1, format!("\n{}", block)),
+            None => insert_at(0, format!("{}\n", block)),
+        }];
+    };
+    paths
+        .iter()
+        .map(|path| {
+            let line = imports
+                .iter()
+                .find(|(_, existing)| existing > path)
+                .map_or(last_import + 1, |(line, _)| *line);
+            insert_at(line, statement(path))
+        })
+        .collect()
 }
 
-/// Adds nested messages with priority
-fn add_nested_messages_with_priority(
-    msg: &crate::parser::proto::MessageElement,
+fn insert_at(line: u32, new_text: String) -> TextEdit {
+    let position = Position { line, character: 0 };
+    TextEdit {
+        range: Range {
+            start: position,
+            end: position,
+        },
+        new_text,
+    }
+}
+
+/// Files and directories reachable from the import search roots, minus the
+/// files already imported. Replaces the whole path typed so far.
+fn add_import_path_completions(
+    workspace: &WorkspaceManager,
+    uri: &Url,
+    proto: &ParsedProto,
+    start: Position,
+    typed: &str,
     items: &mut Vec<CompletionItem>,
-    context: &CompletionContext,
-    priority_base: &str,
 ) {
-    for nested in &msg.nested_messages {
+    let line = start.line;
+    let range = Range {
+        start,
+        end: Position {
+            line,
+            character: start.character + typed.len() as u32,
+        },
+    };
+    for candidate in workspace.list_import_candidates(uri, typed) {
+        // The import on the cursor line is the one being edited
+        if proto.imports.iter().any(|i| i.path == candidate.path && i.line != line) {
+            continue;
+        }
+        let (kind, sort_prefix) = if candidate.is_dir {
+            (CompletionItemKind::FOLDER, "1")
+        } else {
+            (CompletionItemKind::FILE, "0")
+        };
         items.push(CompletionItem {
-            label: nested.name.clone(),
-            kind: Some(CompletionItemKind::CLASS),
-            detail: Some(format!("Nested message: {}", nested.full_name)),
-            sort_text: Some(format!("{}{}", priority_base, "1")),
+            label: candidate.path.clone(),
+            kind: Some(kind),
+            sort_text: Some(format!("{}{}", sort_prefix, candidate.path)),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                range,
+                new_text: candidate.path,
+            })),
             ..Default::default()
         });
-        add_nested_messages_with_priority(nested, items, context, priority_base);
     }
 }
 
-/// Adds enums to completion with appropriate priority
-fn add_enums_with_priority(proto: &ParsedProto, items: &mut Vec<CompletionItem>, context: &CompletionContext, priority_base: &str) {
-    for e in &proto.enums {
-        // Higher priority for enums in the same package
-        let priority = if let (Some(current_pkg), Some(enum_pkg)) = (&context.current_package, e.full_name.split('.').nth(0)) {
-            if current_pkg == enum_pkg {
-                format!("{}{}", priority_base, "0")
-            } else {
-                format!("{}{}", priority_base, "1")
-            }
+/// Completion item tags for a symbol, striking it through when deprecated
+fn deprecated_tags(deprecated: bool) -> Option<Vec<CompletionItemTag>> {
+    deprecated.then(|| vec![CompletionItemTag::DEPRECATED])
+}
+
+/// Adds the messages and enums of `proto`, nested ones included, under the
+/// shortest name that resolves to them from the cursor. Types declared in the
+/// cursor's message come first, then those of enclosing scopes.
+fn add_types_with_priority(
+    proto: &ParsedProto,
+    types: &TypeTable,
+    uri: &Url,
+    items: &mut Vec<CompletionItem>,
+    context: &CompletionContext,
+    priority_base: &str,
+) {
+    let mut found = Vec::new();
+    collect_importable_types(&proto.messages, &proto.enums, &mut found);
+    for (_, full_name, kind, deprecated) in found {
+        let name = types.shortest_name(full_name, &context.scope);
+        let parent = full_name.rfind('.').map_or("", |i| &full_name[..i]);
+        let rank = if parent == context.scope {
+            '0'
+        } else if parent.is_empty() || context.scope.starts_with(&format!("{}.", parent)) {
+            '1'
+        } else if proto.package == context.current_package {
+            '2'
         } else {
-            format!("{}{}", priority_base, "2")
+            '3'
+        };
+        let what = match kind {
+            CompletionItemKind::ENUM => "Enum",
+            _ => "Message",
         };
 
+        // Documentation is filled in by `resolve_completion_item`
         items.push(CompletionItem {
-            label: e.name.clone(),
-            kind: Some(CompletionItemKind::ENUM),
-            detail: Some(format!("Enum: {}", e.full_name)),
-            documentation: Some(Documentation::MarkupContent(MarkupContent {
-                kind: MarkupKind::Markdown,
-                value: format!("```protobuf\nenum {}\n```", e.name),
-            })),
-            sort_text: Some(priority),
+            label: name.clone(),
+            kind: Some(kind),
+            detail: Some(format!("{}: {}", what, full_name)),
+            sort_text: Some(format!("{}{}{}", priority_base, rank, name)),
+            filter_text: Some(name),
+            tags: deprecated_tags(deprecated),
+            data: TypeItemData::to_data(&proto.uri, full_name, uri, None),
             ..Default::default()
         });
+    }
 
-        // Add enum values
+    // Enum values
+    for e in &proto.enums {
         for value in &e.values {
             items.push(CompletionItem {
                 label: value.name.clone(),
                 kind: Some(CompletionItemKind::ENUM_MEMBER),
                 detail: Some(format!("Enum value: {} = {}", value.name, value.number)),
                 sort_text: Some(format!("{}{}", priority_base, "2")),
+                tags: deprecated_tags(value.deprecated),
                 ..Default::default()
             });
         }
@@ -595,7 +1134,7 @@ fn add_enums_with_priority(proto: &ParsedProto, items: &mut Vec<CompletionItem>,
 fn add_services_with_priority(proto: &ParsedProto, items: &mut Vec<CompletionItem>, context: &CompletionContext, priority_base: &str) {
     for svc in &proto.services {
         // Higher priority for services in the same package
-        let priority = if let (Some(current_pkg), Some(svc_pkg)) = (&context.current_package, svc.full_name.split('.').nth(0)) {
+        let priority = if let (Some(current_pkg), Some(svc_pkg)) = (&context.current_package, svc.full_name.split('.').next()) {
             if current_pkg == svc_pkg {
                 format!("{}{}", priority_base, "0")
             } else {
@@ -623,6 +1162,7 @@ fn add_services_with_priority(proto: &ParsedProto, items: &mut Vec<CompletionIte
                     method.name, method.input_type, method.output_type
                 )),
                 sort_text: Some(format!("{}{}", priority_base, "1")),
+                tags: deprecated_tags(method.deprecated),
                 ..Default::default()
             });
         }
@@ -1,3 +1,4 @@
+use crate::features::references::{reference_target, word_at, ReferenceTarget};
 use crate::workspace::WorkspaceManager;
 use tower_lsp::lsp_types::{
     GotoDefinitionParams, GotoDefinitionResponse, Location, Position, Range, Url,
@@ -455,6 +456,21 @@ pub async fn provide_definition_async(
         }
     }
 
+    // Messages and enums resolve semantically, as find references and document
+    // highlight do, so a reference lands on the exact type it denotes. While
+    // the live text does not parse, the positions of the last good parse may
+    // have shifted, so the target must still be the word under the cursor.
+    let word = content
+        .lines()
+        .nth(position.line as usize)
+        .and_then(|line| word_at(line, position.character as usize))
+        .map(|(_, word)| word);
+    if let Some(target @ ReferenceTarget::Type { .. }) = reference_target(&uri, workspace, content, position).await {
+        if word == Some(target.name()) {
+            return Some(GotoDefinitionResponse::Scalar(target.declaration().clone()));
+        }
+    }
+
     // Extract the word at the cursor position
     let symbol_name = if let Some(word) = extract_word_at_position(content, position) {
         tracing::debug!(
@@ -902,5 +918,34 @@ message User {
             target_line
         );
     }
+
+    // ---------------------------------------------------------------
+    // Test: while the live text does not parse, type references of the last
+    // good parse may sit on other lines; the word under the cursor decides
+    // ---------------------------------------------------------------
+    #[tokio::test]
+    async fn test_goto_definition_with_unparsed_edit_above() {
+        let ws = WorkspaceManager::new();
+
+        let uri = Url::parse("file:///project/test.proto").unwrap();
+        let content = r#"syntax = "proto3";
+package test;
+
+message Alpha {}
+message Beta {}
+
+message Holder {
+    Alpha a = 1;
+    Beta b = 2;
 }
+"#;
+        ws.open_file(&uri, content).await.unwrap();
+        let edited = content.replace("    Alpha a = 1;\n", "    string x =\n    Alpha a = 1;\n");
+        let _ = ws.open_file(&uri, &edited).await;
 
+        // Cursor on "Alpha" in line 8, where the last good parse has "Beta"
+        let params = make_params(&uri, 8, 6);
+        let loc = unwrap_location(provide_definition_async(params, &ws, Some(&edited)).await);
+        assert_eq!(loc.range.start.line, 3, "Should jump to 'message Alpha'");
+    }
+}
@@ -1,7 +1,19 @@
-use crate::workspace::WorkspaceManager;
+use crate::features::options::validate_option_values;
+use crate::features::suppressions::apply_suppressions;
+use crate::parser::proto::{EnumElement, EnumValueElement, FieldElement, MessageElement};
+use crate::parser::ParsedProto;
+use crate::workspace::{TypeKind, TypeTable, WorkspaceManager};
 use anyhow::Result;
+use dashmap::DashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use tower_lsp::lsp_types::{
-    Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range, Url,
+    Diagnostic, DiagnosticSeverity, DiagnosticTag, DocumentDiagnosticParams, DocumentDiagnosticReport,
+    DocumentDiagnosticReportResult, FullDocumentDiagnosticReport, NumberOrString, Position,
+    Range, RelatedFullDocumentDiagnosticReport, RelatedUnchangedDocumentDiagnosticReport,
+    UnchangedDocumentDiagnosticReport, Url, WorkspaceDiagnosticParams, WorkspaceDiagnosticReport,
+    WorkspaceDiagnosticReportResult, WorkspaceDocumentDiagnosticReport,
+    WorkspaceFullDocumentDiagnosticReport, WorkspaceUnchangedDocumentDiagnosticReport,
 };
 use tower_lsp::Client;
 use tracing::{debug, info};
@@ -29,6 +41,14 @@ pub async fn publish_diagnostics(
 pub async fn validate_proto_file(uri: &Url, workspace: &WorkspaceManager, client: &Client) -> Result<()> {
     debug!("Validating proto file: {}", uri);
 
+    let diagnostics = compute_diagnostics(uri, workspace).await;
+    publish_diagnostics(uri, diagnostics, client).await;
+    Ok(())
+}
+
+/// Runs every diagnostic rule against the cached state of a file. Shared by the
+/// push (`publish_diagnostics`) and pull (`textDocument/diagnostic`) models.
+pub async fn compute_diagnostics(uri: &Url, workspace: &WorkspaceManager) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
 
     // Get the parsed proto file
@@ -38,6 +58,17 @@ pub async fn validate_proto_file(uri: &Url, workspace: &WorkspaceManager, client
 
         // Check for semantic issues
         diagnostics.extend(validate_semantics(&proto));
+
+        // Check enum value numbers, allow_alias and C++ scoping of value names
+        diagnostics.extend(validate_enums(&proto));
+
+        // Check that referenced types exist in this file or its imports
+        diagnostics.extend(validate_type_references(uri, &proto, workspace).await);
+
+        // Check aggregate option values against the option's message type
+        if let Some(content) = workspace.get_content(uri) {
+            diagnostics.extend(validate_option_values(uri, workspace, &content).await);
+        }
     }
 
     // Add parse errors from the most recent parse attempt (may come from a failed
@@ -70,8 +101,169 @@ pub async fn validate_proto_file(uri: &Url, workspace: &WorkspaceManager, client
         });
     }
 
-    publish_diagnostics(uri, diagnostics, client).await;
-    Ok(())
+    // Honour `// protobuf-lsp:ignore` comments and flag the ones that match nothing
+    match workspace.get_content(uri) {
+        Some(content) => apply_suppressions(&content, diagnostics),
+        None => diagnostics,
+    }
+}
+
+/// Diagnostics last computed for a file, keyed by the result ID handed to the client
+struct CachedReport {
+    result_id: String,
+    items: Vec<Diagnostic>,
+}
+
+/// Cache of pulled diagnostic reports. A report is reused as long as neither the
+/// file nor any of its transitive imports has been re-parsed since.
+#[derive(Default)]
+pub struct DiagnosticsCache {
+    reports: DashMap<Url, CachedReport>,
+}
+
+impl DiagnosticsCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops the cached report for a file
+    pub fn invalidate(&self, uri: &Url) {
+        self.reports.remove(uri);
+    }
+}
+
+/// Outcome of a pull request for a single file
+enum PullReport {
+    Full {
+        result_id: Option<String>,
+        items: Vec<Diagnostic>,
+    },
+    Unchanged {
+        result_id: String,
+    },
+}
+
+/// Derives a result ID from the parse generations of a file and everything it
+/// imports, so edits to a dependency also invalidate the file's report.
+async fn compute_result_id(uri: &Url, workspace: &WorkspaceManager) -> Option<String> {
+    let generation = workspace.get_generation(uri)?;
+
+    let mut hasher = DefaultHasher::new();
+    uri.as_str().hash(&mut hasher);
+    generation.hash(&mut hasher);
+
+    let mut imports: Vec<(Url, u64)> = Vec::new();
+    for imported in workspace.collect_all_imports_async(uri).await {
+        if let Ok(import_uri) = Url::parse(&imported.uri) {
+            let import_generation = workspace.get_generation(&import_uri).unwrap_or(0);
+            imports.push((import_uri, import_generation));
+        }
+    }
+    imports.sort();
+    for (import_uri, import_generation) in &imports {
+        import_uri.as_str().hash(&mut hasher);
+        import_generation.hash(&mut hasher);
+    }
+
+    Some(format!("{:016x}", hasher.finish()))
+}
+
+async fn pull_diagnostics(
+    uri: &Url,
+    previous_result_id: Option<&str>,
+    workspace: &WorkspaceManager,
+    cache: &DiagnosticsCache,
+) -> PullReport {
+    let Some(result_id) = compute_result_id(uri, workspace).await else {
+        // Not tracked by the workspace: nothing to report
+        return PullReport::Full { result_id: None, items: Vec::new() };
+    };
+
+    if previous_result_id == Some(result_id.as_str()) {
+        debug!("Diagnostics unchanged for {} ({})", uri, result_id);
+        return PullReport::Unchanged { result_id };
+    }
+
+    if let Some(cached) = cache.reports.get(uri) {
+        if cached.result_id == result_id {
+            return PullReport::Full { result_id: Some(result_id), items: cached.items.clone() };
+        }
+    }
+
+    let items = compute_diagnostics(uri, workspace).await;
+    cache.reports.insert(
+        uri.clone(),
+        CachedReport { result_id: result_id.clone(), items: items.clone() },
+    );
+    PullReport::Full { result_id: Some(result_id), items }
+}
+
+/// Handle `textDocument/diagnostic` (LSP 3.17 pull model).
+pub async fn provide_document_diagnostics(
+    params: DocumentDiagnosticParams,
+    workspace: &WorkspaceManager,
+    cache: &DiagnosticsCache,
+) -> DocumentDiagnosticReportResult {
+    let uri = &params.text_document.uri;
+    let report = pull_diagnostics(uri, params.previous_result_id.as_deref(), workspace, cache).await;
+
+    let report = match report {
+        PullReport::Full { result_id, items } => {
+            DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+                related_documents: None,
+                full_document_diagnostic_report: FullDocumentDiagnosticReport { result_id, items },
+            })
+        }
+        PullReport::Unchanged { result_id } => {
+            DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+                related_documents: None,
+                unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport { result_id },
+            })
+        }
+    };
+    DocumentDiagnosticReportResult::Report(report)
+}
+
+/// Handle `workspace/diagnostic`: reports on every file the workspace has indexed,
+/// including files that were never opened in the editor.
+pub async fn provide_workspace_diagnostics(
+    params: WorkspaceDiagnosticParams,
+    workspace: &WorkspaceManager,
+    cache: &DiagnosticsCache,
+) -> WorkspaceDiagnosticReportResult {
+    let previous: std::collections::HashMap<Url, String> = params
+        .previous_result_ids
+        .into_iter()
+        .map(|p| (p.uri, p.value))
+        .collect();
+
+    let mut uris = workspace.get_tracked_uris();
+    uris.sort();
+
+    let mut items = Vec::with_capacity(uris.len());
+    for uri in uris {
+        let report =
+            pull_diagnostics(&uri, previous.get(&uri).map(|s| s.as_str()), workspace, cache).await;
+        items.push(match report {
+            PullReport::Full { result_id, items } => {
+                WorkspaceDocumentDiagnosticReport::Full(WorkspaceFullDocumentDiagnosticReport {
+                    uri,
+                    version: None,
+                    full_document_diagnostic_report: FullDocumentDiagnosticReport { result_id, items },
+                })
+            }
+            PullReport::Unchanged { result_id } => {
+                WorkspaceDocumentDiagnosticReport::Unchanged(WorkspaceUnchangedDocumentDiagnosticReport {
+                    uri,
+                    version: None,
+                    unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport { result_id },
+                })
+            }
+        });
+    }
+
+    info!("Workspace diagnostics: {} files", items.len());
+    WorkspaceDiagnosticReportResult::Report(WorkspaceDiagnosticReport { items })
 }
 
 fn validate_syntax(proto: &crate::parser::ParsedProto) -> Vec<Diagnostic> {
@@ -228,6 +420,325 @@ fn validate_semantics(proto: &crate::parser::ParsedProto) -> Vec<Diagnostic> {
     diagnostics
 }
 
+/// Check field, RPC and extend types against the types visible from the file.
+/// Unknown types are errors; uses of deprecated types and enum values (in field
+/// defaults) are hints tagged `DEPRECATED`. Unknown types are not reported when
+/// an import cannot be found, since every type it provides would be flagged.
+async fn validate_type_references(
+    uri: &Url,
+    proto: &ParsedProto,
+    workspace: &WorkspaceManager,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    if proto.type_refs.is_empty() {
+        return diagnostics;
+    }
+
+    let imports = workspace.collect_all_imports_async(uri).await;
+    let mut files: Vec<(Url, &ParsedProto)> = vec![(uri.clone(), proto)];
+    for imported in &imports {
+        if let Ok(import_uri) = Url::parse(&imported.uri) {
+            files.push((import_uri, imported));
+        }
+    }
+    let mut imports_complete = true;
+    for (file_uri, file) in &files {
+        if let Some(missing) = file
+            .imports
+            .iter()
+            .find(|imp| workspace.resolve_import(file_uri, &imp.path).is_none())
+        {
+            debug!("Skipping unknown type checks for {}: import '{}' not found", uri, missing.path);
+            imports_complete = false;
+            break;
+        }
+    }
+
+    let table = TypeTable::from_files(files.iter().map(|(_, file)| *file));
+    for type_ref in &proto.type_refs {
+        let range = Range {
+            start: Position {
+                line: type_ref.line,
+                character: type_ref.character,
+            },
+            end: Position {
+                line: type_ref.line,
+                character: type_ref.character + type_ref.name.len() as u32,
+            },
+        };
+        match table.resolve(&type_ref.name, &type_ref.scope) {
+            Some(def) if def.deprecated => {
+                let kind = match def.kind {
+                    TypeKind::Message => "Message",
+                    TypeKind::Enum => "Enum",
+                };
+                diagnostics.push(deprecated_usage_diagnostic(
+                    range,
+                    format!("{} '{}' is deprecated", kind, def.full_name),
+                ));
+            }
+            Some(_) => {}
+            None if imports_complete => diagnostics.push(Diagnostic {
+                range,
+                severity: Some(DiagnosticSeverity::ERROR),
+                code: Some(NumberOrString::String("unresolved-type".to_string())),
+                source: Some("protobuf-lsp".to_string()),
+                message: format!("Unknown type '{}'", type_ref.name),
+                related_information: None,
+                tags: None,
+                code_description: None,
+                data: None,
+            }),
+            None => {}
+        }
+    }
+
+    // Enum values used as proto2 field defaults, e.g. `[default = OLD_VALUE]`
+    let mut fields: Vec<(&FieldElement, &str)> = Vec::new();
+    let package_scope = proto.package.as_deref().unwrap_or("");
+    for msg in &proto.messages {
+        collect_message_fields(msg, &mut fields);
+    }
+    for ext in &proto.extends {
+        fields.extend(ext.fields.iter().map(|f| (f, package_scope)));
+    }
+    for (field, scope) in fields {
+        let (Some(type_name), Some(default)) =
+            (&field.type_name, field.options.iter().find(|o| o.name == "default"))
+        else {
+            continue;
+        };
+        let Some(def) = table.resolve(type_name, scope) else { continue };
+        if def.deprecated_values.contains(&default.value) {
+            let range = Range {
+                start: Position {
+                    line: default.value_line,
+                    character: default.value_character,
+                },
+                end: Position {
+                    line: default.value_line,
+                    character: default.value_character + default.value.len() as u32,
+                },
+            };
+            diagnostics.push(deprecated_usage_diagnostic(
+                range,
+                format!("Enum value '{}.{}' is deprecated", def.full_name, default.value),
+            ));
+        }
+    }
+
+    diagnostics
+}
+
+/// Collect the fields of a message and its nested messages, paired with the
+/// scope their types are resolved from.
+fn collect_message_fields<'a>(msg: &'a MessageElement, out: &mut Vec<(&'a FieldElement, &'a str)>) {
+    out.extend(msg.fields.iter().map(|f| (f, msg.full_name.as_str())));
+    for nested in &msg.nested_messages {
+        collect_message_fields(nested, out);
+    }
+}
+
+fn deprecated_usage_diagnostic(range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::HINT),
+        code: Some(NumberOrString::String("deprecated-usage".to_string())),
+        source: Some("protobuf-lsp".to_string()),
+        message,
+        related_information: None,
+        tags: Some(vec![DiagnosticTag::DEPRECATED]),
+        code_description: None,
+        data: None,
+    }
+}
+
+/// Republish diagnostics for every file in `open` that imports one of `changed`,
+/// directly or transitively. Returns the files that were revalidated.
+pub async fn validate_dependents(
+    changed: &[Url],
+    open: &[Url],
+    workspace: &WorkspaceManager,
+    client: &Client,
+) -> Vec<Url> {
+    let dependents = workspace.find_dependents(open, changed).await;
+    for uri in &dependents {
+        debug!("Revalidating dependent file: {}", uri);
+        if let Err(e) = validate_proto_file(uri, workspace, client).await {
+            tracing::error!("Failed to validate {}: {}", uri, e);
+        }
+    }
+    dependents
+}
+
+/// A symbol that lives in an enum value's scope. Enum values follow C++ scoping
+/// rules: they are siblings of their enum type, not children of it, so they share
+/// a namespace with every other type, field and enum value declared in that scope.
+struct ScopeSymbol<'a> {
+    name: &'a str,
+    /// Human-readable description used in the conflict message
+    kind: String,
+}
+
+fn validate_enums(proto: &ParsedProto) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    // File (package) scope: top-level messages, enums and services
+    let mut scope: Vec<ScopeSymbol> = Vec::new();
+    for msg in &proto.messages {
+        scope.push(ScopeSymbol { name: &msg.name, kind: format!("message '{}'", msg.name) });
+    }
+    for e in &proto.enums {
+        scope.push(ScopeSymbol { name: &e.name, kind: format!("enum '{}'", e.name) });
+    }
+    for svc in &proto.services {
+        scope.push(ScopeSymbol { name: &svc.name, kind: format!("service '{}'", svc.name) });
+    }
+    let scope_name = proto.package.clone().unwrap_or_else(|| "this file".to_string());
+    validate_enum_scope(&proto.enums, scope, &scope_name, &mut diagnostics);
+
+    for msg in &proto.messages {
+        validate_message_enums(msg, &mut diagnostics);
+    }
+
+    diagnostics
+}
+
+fn validate_message_enums(msg: &MessageElement, diagnostics: &mut Vec<Diagnostic>) {
+    let mut scope: Vec<ScopeSymbol> = Vec::new();
+    for nested in &msg.nested_messages {
+        scope.push(ScopeSymbol { name: &nested.name, kind: format!("message '{}'", nested.name) });
+    }
+    for e in &msg.nested_enums {
+        scope.push(ScopeSymbol { name: &e.name, kind: format!("enum '{}'", e.name) });
+    }
+    for field in &msg.fields {
+        scope.push(ScopeSymbol { name: &field.name, kind: format!("field '{}'", field.name) });
+    }
+    validate_enum_scope(&msg.nested_enums, scope, &msg.full_name, diagnostics);
+
+    for nested in &msg.nested_messages {
+        validate_message_enums(nested, diagnostics);
+    }
+}
+
+/// Validate the enums declared directly in one scope. `scope` holds the other
+/// symbols of that scope; enum values are added to it as they are visited so
+/// values of sibling enums are checked against each other.
+fn validate_enum_scope<'a>(
+    enums: &'a [EnumElement],
+    mut scope: Vec<ScopeSymbol<'a>>,
+    scope_name: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for e in enums {
+        let allow_alias = e.options.iter().find(|o| o.name == "allow_alias");
+        let aliasing_allowed = allow_alias.is_some_and(|o| o.is_true());
+
+        // Duplicate numbers within the enum
+        let mut numbers: std::collections::HashMap<i32, &str> = std::collections::HashMap::new();
+        let mut has_alias = false;
+        for value in &e.values {
+            if let Some(first) = numbers.get(&value.number) {
+                has_alias = true;
+                if !aliasing_allowed {
+                    diagnostics.push(enum_value_diagnostic(
+                        value,
+                        DiagnosticSeverity::ERROR,
+                        "duplicate-enum-value-number",
+                        format!(
+                            "Enum value number {} is already used by '{}' in enum '{}'. \
+                             Set 'option allow_alias = true;' to allow aliases.",
+                            value.number, first, e.name
+                        ),
+                    ));
+                }
+            } else {
+                numbers.insert(value.number, &value.name);
+            }
+        }
+
+        if let Some(opt) = allow_alias {
+            if opt.is_true() && !has_alias {
+                diagnostics.push(Diagnostic {
+                    range: Range {
+                        start: Position {
+                            line: opt.line,
+                            character: opt.character,
+                        },
+                        end: Position {
+                            line: opt.line,
+                            character: opt.character + ("option ".len() + opt.name.len()) as u32,
+                        },
+                    },
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    code: Some(NumberOrString::String("unused-allow-alias".to_string())),
+                    source: Some("protobuf-lsp".to_string()),
+                    message: format!(
+                        "Enum '{}' has allow_alias set to true but no values are aliased. \
+                         Remove the allow_alias option.",
+                        e.name
+                    ),
+                    related_information: None,
+                    tags: None,
+                    code_description: None,
+                    data: None,
+                });
+            }
+        }
+
+        // Value names share the enclosing scope with sibling enums' values
+        for value in &e.values {
+            if let Some(existing) = scope.iter().find(|s| s.name == value.name) {
+                diagnostics.push(enum_value_diagnostic(
+                    value,
+                    DiagnosticSeverity::ERROR,
+                    "enum-value-scope-conflict",
+                    format!(
+                        "'{}' is already defined as {} in '{}'. Enum values use C++ scoping \
+                         rules: they are siblings of their enum type, so '{}' must be unique \
+                         within '{}', not just within '{}'.",
+                        value.name, existing.kind, scope_name, value.name, scope_name, e.name
+                    ),
+                ));
+            } else {
+                scope.push(ScopeSymbol {
+                    name: &value.name,
+                    kind: format!("a value of enum '{}'", e.name),
+                });
+            }
+        }
+    }
+}
+
+fn enum_value_diagnostic(
+    value: &EnumValueElement,
+    severity: DiagnosticSeverity,
+    code: &str,
+    message: String,
+) -> Diagnostic {
+    Diagnostic {
+        range: Range {
+            start: Position {
+                line: value.line,
+                character: value.character,
+            },
+            end: Position {
+                line: value.line,
+                character: value.character + value.name.len() as u32,
+            },
+        },
+        severity: Some(severity),
+        code: Some(NumberOrString::String(code.to_string())),
+        source: Some("protobuf-lsp".to_string()),
+        message,
+        related_information: None,
+        tags: None,
+        code_description: None,
+        data: None,
+    }
+}
+
 fn get_file_content(uri: &str) -> Option<String> {
     use std::fs;
     use std::path::Path;
@@ -243,4 +754,335 @@ fn get_file_content(uri: &str) -> Option<String> {
     } else {
         None
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<String> {
+        diagnostics
+            .iter()
+            .filter_map(|d| match &d.code {
+                Some(NumberOrString::String(s)) => Some(s.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_enum_number_without_allow_alias() {
+        let content = r#"syntax = "proto3";
+package test;
+
+enum Status {
+    STATUS_UNKNOWN = 0;
+    STATUS_ACTIVE = 1;
+    STATUS_ENABLED = 1;
+}
+"#;
+        let proto = ParsedProto::parse("test.proto".to_string(), content).await.unwrap();
+        let diagnostics = validate_enums(&proto);
+        assert_eq!(codes(&diagnostics), vec!["duplicate-enum-value-number"]);
+        assert_eq!(diagnostics[0].range.start.line, 6);
+        assert!(diagnostics[0].message.contains("STATUS_ACTIVE"));
+    }
+
+    #[tokio::test]
+    async fn test_allow_alias_permits_duplicate_numbers() {
+        let content = r#"syntax = "proto3";
+package test;
+
+enum Status {
+    option allow_alias = true;
+    STATUS_UNKNOWN = 0;
+    STATUS_ACTIVE = 1;
+    STATUS_ENABLED = 1;
+}
+"#;
+        let proto = ParsedProto::parse("test.proto".to_string(), content).await.unwrap();
+        assert!(validate_enums(&proto).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_allow_alias_without_aliases() {
+        let content = r#"syntax = "proto3";
+package test;
+
+enum Status {
+    option allow_alias = true;
+    STATUS_UNKNOWN = 0;
+    STATUS_ACTIVE = 1;
+}
+"#;
+        let proto = ParsedProto::parse("test.proto".to_string(), content).await.unwrap();
+        let diagnostics = validate_enums(&proto);
+        assert_eq!(codes(&diagnostics), vec!["unused-allow-alias"]);
+        assert_eq!(diagnostics[0].range.start.line, 4);
+    }
+
+    #[tokio::test]
+    async fn test_sibling_enum_value_conflict() {
+        let content = r#"syntax = "proto3";
+package test;
+
+enum Color {
+    UNKNOWN = 0;
+    RED = 1;
+}
+
+enum Size {
+    UNKNOWN = 0;
+    LARGE = 1;
+}
+"#;
+        let proto = ParsedProto::parse("test.proto".to_string(), content).await.unwrap();
+        let diagnostics = validate_enums(&proto);
+        assert_eq!(codes(&diagnostics), vec!["enum-value-scope-conflict"]);
+        assert_eq!(diagnostics[0].range.start.line, 9);
+        assert!(diagnostics[0].message.contains("enum 'Color'"));
+    }
+
+    #[tokio::test]
+    async fn test_nested_enum_value_conflicts_with_sibling_type() {
+        let content = r#"syntax = "proto3";
+package test;
+
+message Outer {
+    message DONE {}
+    enum State {
+        PENDING = 0;
+        DONE = 1;
+    }
+}
+
+enum State {
+    PENDING = 0;
+}
+"#;
+        let proto = ParsedProto::parse("test.proto".to_string(), content).await.unwrap();
+        let diagnostics = validate_enums(&proto);
+        // Only the nested value clashes; the top-level PENDING lives in another scope.
+        assert_eq!(codes(&diagnostics), vec!["enum-value-scope-conflict"]);
+        assert_eq!(diagnostics[0].range.start.line, 7);
+        assert!(diagnostics[0].message.contains("test.Outer"));
+    }
+
+    fn document_params(uri: &Url, previous_result_id: Option<String>) -> DocumentDiagnosticParams {
+        DocumentDiagnosticParams {
+            text_document: tower_lsp::lsp_types::TextDocumentIdentifier { uri: uri.clone() },
+            identifier: None,
+            previous_result_id,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    fn full_report(result: DocumentDiagnosticReportResult) -> FullDocumentDiagnosticReport {
+        match result {
+            DocumentDiagnosticReportResult::Report(DocumentDiagnosticReport::Full(r)) => {
+                r.full_document_diagnostic_report
+            }
+            other => panic!("expected a full report, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pull_diagnostics_result_ids() {
+        let workspace = WorkspaceManager::new();
+        let cache = DiagnosticsCache::new();
+        let uri = Url::parse("file:///test/pull.proto").unwrap();
+        let content = "syntax = \"proto3\";\nmessage A {}\nmessage A {}\n";
+        workspace.open_file(&uri, content).await.unwrap();
+
+        let report = full_report(
+            provide_document_diagnostics(document_params(&uri, None), &workspace, &cache).await,
+        );
+        assert_eq!(codes(&report.items), vec!["duplicate-message"]);
+        let result_id = report.result_id.unwrap();
+
+        // Same result ID back from the client: nothing changed
+        let result =
+            provide_document_diagnostics(document_params(&uri, Some(result_id.clone())), &workspace, &cache)
+                .await;
+        assert!(matches!(
+            result,
+            DocumentDiagnosticReportResult::Report(DocumentDiagnosticReport::Unchanged(_))
+        ));
+
+        // An edit produces a new result ID and a fresh report
+        workspace.open_file(&uri, "syntax = \"proto3\";\nmessage A {}\n").await.unwrap();
+        let report = full_report(
+            provide_document_diagnostics(document_params(&uri, Some(result_id.clone())), &workspace, &cache)
+                .await,
+        );
+        assert!(report.items.is_empty());
+        assert_ne!(report.result_id.unwrap(), result_id);
+    }
+
+    #[tokio::test]
+    async fn test_workspace_diagnostics_cover_unopened_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("good.proto"), "syntax = \"proto3\";\nmessage A {}\n").unwrap();
+        std::fs::write(
+            dir.path().join("dup.proto"),
+            "syntax = \"proto3\";\nmessage B {}\nmessage B {}\n",
+        )
+        .unwrap();
+
+        let workspace = WorkspaceManager::new();
+        let cache = DiagnosticsCache::new();
+        workspace.add_workspace_root(dir.path().to_path_buf());
+        workspace.index_workspace().await;
+
+        let params = WorkspaceDiagnosticParams {
+            identifier: None,
+            previous_result_ids: Vec::new(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let WorkspaceDiagnosticReportResult::Report(report) =
+            provide_workspace_diagnostics(params, &workspace, &cache).await
+        else {
+            panic!("expected a full workspace report");
+        };
+        assert_eq!(report.items.len(), 2);
+
+        let dup_uri = Url::from_file_path(dir.path().join("dup.proto")).unwrap();
+        let mut previous_result_ids = Vec::new();
+        for item in &report.items {
+            let WorkspaceDocumentDiagnosticReport::Full(full) = item else {
+                panic!("expected full reports on the first pull");
+            };
+            let report = &full.full_document_diagnostic_report;
+            if full.uri == dup_uri {
+                assert_eq!(codes(&report.items), vec!["duplicate-message"]);
+            } else {
+                assert!(report.items.is_empty());
+            }
+            previous_result_ids.push(tower_lsp::lsp_types::PreviousResultId {
+                uri: full.uri.clone(),
+                value: report.result_id.clone().unwrap(),
+            });
+        }
+
+        // Second pull with the previous IDs: every file is unchanged
+        let params = WorkspaceDiagnosticParams {
+            identifier: None,
+            previous_result_ids,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let WorkspaceDiagnosticReportResult::Report(report) =
+            provide_workspace_diagnostics(params, &workspace, &cache).await
+        else {
+            panic!("expected a full workspace report");
+        };
+        assert!(report
+            .items
+            .iter()
+            .all(|item| matches!(item, WorkspaceDocumentDiagnosticReport::Unchanged(_))));
+    }
+
+    #[tokio::test]
+    async fn test_unresolved_type_follows_imported_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let common_path = dir.path().join("common.proto");
+        std::fs::write(&common_path, "syntax = \"proto3\";\npackage common;\nmessage Id {}\n").unwrap();
+        let common = Url::from_file_path(&common_path).unwrap();
+        let user = Url::from_file_path(dir.path().join("user.proto")).unwrap();
+
+        let workspace = WorkspaceManager::new();
+        workspace
+            .open_file(&common, "syntax = \"proto3\";\npackage common;\nmessage Id {}\n")
+            .await
+            .unwrap();
+        let user_content = "syntax = \"proto3\";\npackage user;\nimport \"common.proto\";\n\nmessage User {\n  common.Id id = 1;\n  Missing other = 2;\n}\n";
+        workspace.open_file(&user, user_content).await.unwrap();
+
+        let diagnostics = compute_diagnostics(&user, &workspace).await;
+        assert_eq!(codes(&diagnostics), vec!["unresolved-type"]);
+        assert_eq!(diagnostics[0].range.start, Position { line: 6, character: 2 });
+
+        // Renaming the message in the imported file breaks the reference
+        workspace
+            .open_file(&common, "syntax = \"proto3\";\npackage common;\nmessage Identifier {}\n")
+            .await
+            .unwrap();
+        let diagnostics = compute_diagnostics(&user, &workspace).await;
+        assert_eq!(codes(&diagnostics), vec!["unresolved-type", "unresolved-type"]);
+        assert!(diagnostics[0].message.contains("common.Id"));
+    }
+
+    #[tokio::test]
+    async fn test_unresolved_type_skipped_with_missing_import() {
+        let workspace = WorkspaceManager::new();
+        let uri = Url::parse("file:///nonexistent/dir/a.proto").unwrap();
+        let content = "syntax = \"proto3\";\nimport \"missing.proto\";\nmessage A { Missing m = 1; }\n";
+        workspace.open_file(&uri, content).await.unwrap();
+        assert!(compute_diagnostics(&uri, &workspace).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deprecated_usage_hints() {
+        let workspace = WorkspaceManager::new();
+        let uri = Url::parse("file:///test/deprecated.proto").unwrap();
+        let content = r#"syntax = "proto2";
+package test;
+
+message Old {
+  option deprecated = true;
+}
+
+enum Color {
+  COLOR_RED = 0 [deprecated = true];
+  COLOR_BLUE = 1;
+}
+
+message User {
+  optional Old old = 1;
+  optional Color color = 2 [default = COLOR_RED];
+  optional Color other = 3 [default = COLOR_BLUE];
+}
+"#;
+        workspace.open_file(&uri, content).await.unwrap();
+        let diagnostics = compute_diagnostics(&uri, &workspace).await;
+        assert_eq!(codes(&diagnostics), vec!["deprecated-usage", "deprecated-usage"]);
+        for d in &diagnostics {
+            assert_eq!(d.severity, Some(DiagnosticSeverity::HINT));
+            assert_eq!(d.tags, Some(vec![DiagnosticTag::DEPRECATED]));
+        }
+        assert_eq!(diagnostics[0].range.start, Position { line: 13, character: 11 });
+        assert!(diagnostics[1].message.contains("test.Color.COLOR_RED"));
+        assert_eq!(diagnostics[1].range.start, Position { line: 14, character: 38 });
+    }
+
+    #[tokio::test]
+    async fn test_suppression_comments() {
+        let workspace = WorkspaceManager::new();
+        let uri = Url::parse("file:///test/suppressed.proto").unwrap();
+        let content = r#"syntax = "proto3";
+// protobuf-lsp:ignore-file DUPLICATE_MESSAGE
+package test;
+
+message A {}
+message A {}
+
+message User {
+  // protobuf-lsp:ignore UNRESOLVED_TYPE
+  Missing one = 1;
+  Missing two = 2; // protobuf-lsp:ignore duplicate-field-number
+  Missing three = 2;
+}
+"#;
+        workspace.open_file(&uri, content).await.unwrap();
+        let diagnostics = compute_diagnostics(&uri, &workspace).await;
+        assert_eq!(
+            codes(&diagnostics),
+            vec!["duplicate-field-number", "unresolved-type", "unresolved-type", "unused-suppression"]
+        );
+        assert_eq!(diagnostics[0].range.start.line, 11);
+        assert_eq!(diagnostics[1].range.start.line, 10);
+        assert_eq!(diagnostics[2].range.start.line, 11);
+        assert_eq!(diagnostics[3].range.start, Position { line: 10, character: 42 });
+    }
+}
@@ -54,7 +54,7 @@ pub fn provide_document_links(
 
 /// Extract the import path and its column range from an import line.
 /// Returns (path, start_column, end_column) where columns include the quotes.
-fn extract_import_path(line: &str) -> Option<(String, usize, usize)> {
+pub fn extract_import_path(line: &str) -> Option<(String, usize, usize)> {
     // Look for both double-quoted and single-quoted paths
     let quote_start = line.find('"').or_else(|| line.find('\''))?;
     let quote_char = line.as_bytes()[quote_start] as char;
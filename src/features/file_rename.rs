@@ -0,0 +1,304 @@
+//! `workspace/willRenameFiles`: keep imports working when `.proto` files or
+//! directories are moved, and offer to move the package along with them.
+
+use crate::features::document_link::extract_import_path;
+use crate::features::rename::{package_move_edits, workspace_edit, EditSupport, RenameEdit};
+use crate::workspace::WorkspaceManager;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::*;
+
+/// Change annotation of the edits to import paths
+const IMPORTS_ANNOTATION: &str = "update-imports";
+/// Change annotation of the edits moving a package to its new directory
+const PACKAGE_ANNOTATION: &str = "update-package";
+
+/// Edits to apply before the files in `params` are renamed: every import of a
+/// moved file, and every import made by a moved file, is rewritten to the path
+/// that resolves to the new location from the same roots `ImportResolver`
+/// searches. When a package mirrors the directory its files are moved out of,
+/// moving them to the package of the new directory is offered as an edit that
+/// needs confirmation: the whole package is renamed when all of its files move,
+/// otherwise only the moved files change package. Clients without change
+/// annotations only get the import edits.
+pub async fn will_rename_files(
+    params: RenameFilesParams,
+    workspace: &WorkspaceManager,
+    support: &EditSupport,
+) -> Option<WorkspaceEdit> {
+    let moves: Vec<(PathBuf, PathBuf)> = params
+        .files
+        .iter()
+        .filter_map(|file| {
+            let old = Url::parse(&file.old_uri).ok()?.to_file_path().ok()?;
+            let new = Url::parse(&file.new_uri).ok()?.to_file_path().ok()?;
+            Some((old, new))
+        })
+        .collect();
+    if moves.is_empty() {
+        return None;
+    }
+    tracing::debug!("Will rename files: {:?}", moves);
+
+    let moved = |path: &Path| relocate(path, moves.iter().map(|(old, new)| (old, new)));
+    let original = |path: &Path| relocate(path, moves.iter().map(|(old, new)| (new, old)));
+    // Whether a file will be at `path` once the renames are done
+    let exists = |path: &Path| {
+        original(path).is_some_and(|from| from.exists()) || (moved(path).is_none() && path.exists())
+    };
+
+    let mut files = workspace.get_all_files();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut edits: Vec<RenameEdit> = Vec::new();
+    let mut importers = HashSet::new();
+    for (uri, _) in &files {
+        let Ok(uri) = Url::parse(uri) else { continue };
+        let Ok(path) = uri.to_file_path() else { continue };
+        let Some(content) = workspace.get_content(&uri) else { continue };
+        let new_path = moved(&path);
+
+        for (line_index, line) in content.lines().enumerate() {
+            if !line.trim_start().starts_with("import ") {
+                continue;
+            }
+            let Some((import_path, start, end)) = extract_import_path(line) else { continue };
+            let Some(target) = workspace.resolve_import(&uri, &import_path) else { continue };
+            let new_target = moved(&target);
+            if new_path.is_none() && new_target.is_none() {
+                continue;
+            }
+
+            let new_path = new_path.as_deref().unwrap_or(&path);
+            let new_target = new_target.unwrap_or(target);
+            if workspace.resolve_planned_import(new_path, &import_path, exists).as_ref() == Some(&new_target) {
+                continue;
+            }
+            let Some(new_import) = workspace.planned_import_path(new_path, &new_target, exists) else {
+                tracing::warn!("No import path reaches {} from {}", new_target.display(), new_path.display());
+                continue;
+            };
+            let range = Range {
+                start: Position { line: line_index as u32, character: start as u32 + 1 },
+                end: Position { line: line_index as u32, character: end as u32 - 1 },
+            };
+            importers.insert(uri.clone());
+            edits.push((Location { uri: uri.clone(), range }, new_import, IMPORTS_ANNOTATION));
+        }
+    }
+
+    let mut change_annotations = HashMap::new();
+    if !edits.is_empty() {
+        change_annotations.insert(
+            IMPORTS_ANNOTATION.to_string(),
+            ChangeAnnotation {
+                label: "Update imports".to_string(),
+                needs_confirmation: Some(false),
+                description: Some(format!(
+                    "{} import{} in {} file{}",
+                    edits.len(),
+                    if edits.len() == 1 { "" } else { "s" },
+                    importers.len(),
+                    if importers.len() == 1 { "" } else { "s" }
+                )),
+            },
+        );
+    }
+
+    // Packages that mirror the directory of the moved files, offered only when
+    // the client can ask for confirmation
+    let mut packages = Vec::new();
+    let mut seen = HashSet::new();
+    for (uri, proto) in files.iter().filter(|_| support.annotated) {
+        let Some(package) = proto.package.as_deref() else { continue };
+        let Ok(uri) = Url::parse(uri) else { continue };
+        let Ok(path) = uri.to_file_path() else { continue };
+        let Some(new_path) = moved(&path) else { continue };
+        if !seen.insert(package) {
+            continue;
+        }
+
+        let old_import = workspace.import_path_for(&uri, &uri);
+        let new_import = workspace.planned_import_path(&new_path, &new_path, exists);
+        if old_import.as_deref().and_then(directory_package).as_deref() != Some(package) {
+            continue;
+        }
+        let Some(new_package) = new_import.as_deref().and_then(directory_package) else { continue };
+        if new_package == package {
+            continue;
+        }
+        // Files left behind stay in the old package; only the moved ones change
+        let is_moved = |uri: &Url| uri.to_file_path().is_ok_and(|path| moved(&path).is_some());
+        let stays = files.iter().any(|(uri, proto)| {
+            proto.package.as_deref() == Some(package) && Url::parse(uri).is_ok_and(|uri| !is_moved(&uri))
+        });
+
+        match package_move_edits(package, &new_package, is_moved, workspace).await {
+            Ok((package_edits, _)) if !package_edits.is_empty() => {
+                edits.extend(package_edits.into_iter().map(|(location, text, _)| (location, text, PACKAGE_ANNOTATION)));
+                if stays {
+                    packages.push(format!("'{}' to '{}' in the moved files", package, new_package));
+                } else {
+                    packages.push(format!("'{}' to '{}'", package, new_package));
+                }
+            }
+            Ok(_) => {}
+            Err(reason) => tracing::debug!("Not offering package rename: {}", reason),
+        }
+    }
+    if !packages.is_empty() {
+        change_annotations.insert(
+            PACKAGE_ANNOTATION.to_string(),
+            ChangeAnnotation {
+                label: format!(
+                    "Update package{} {} to match the new director{}",
+                    if packages.len() == 1 { "" } else { "s" },
+                    packages.join(", "),
+                    if packages.len() == 1 { "y" } else { "ies" }
+                ),
+                needs_confirmation: Some(true),
+                description: None,
+            },
+        );
+    }
+
+    if edits.is_empty() {
+        return None;
+    }
+    Some(workspace_edit(edits, change_annotations, support))
+}
+
+/// Where `path` ends up under the first move whose source holds it, whether
+/// the move is of the file itself or of a directory above it
+fn relocate<'a>(path: &Path, mut moves: impl Iterator<Item = (&'a PathBuf, &'a PathBuf)>) -> Option<PathBuf> {
+    moves.find_map(|(from, to)| {
+        let rest = path.strip_prefix(from).ok()?;
+        Some(if rest.as_os_str().is_empty() { to.clone() } else { to.join(rest) })
+    })
+}
+
+/// The package spelled by the directory of an import path: `a/b/c.proto` is
+/// `a.b`. None for files at a root or directories that are not identifiers.
+fn directory_package(import_path: &str) -> Option<String> {
+    let (directory, _) = import_path.rsplit_once('/')?;
+    let valid = directory.split('/').all(|part| {
+        part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    });
+    valid.then(|| directory.replace('/', "."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::testing::open_files;
+
+    /// New texts of the edits to `uri`, with their annotation ids
+    fn edits_to(edit: &WorkspaceEdit, uri: &Url) -> Vec<(u32, String, String)> {
+        let Some(DocumentChanges::Edits(documents)) = &edit.document_changes else { return Vec::new() };
+        documents
+            .iter()
+            .filter(|document| &document.text_document.uri == uri)
+            .flat_map(|document| &document.edits)
+            .map(|edit| match edit {
+                OneOf::Left(edit) => (edit.range.start.line, edit.new_text.clone(), String::new()),
+                OneOf::Right(edit) => {
+                    (edit.text_edit.range.start.line, edit.text_edit.new_text.clone(), edit.annotation_id.clone())
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_directory_package() {
+        assert_eq!(directory_package("common/types.proto").as_deref(), Some("common"));
+        assert_eq!(directory_package("acme/billing/v1/a.proto").as_deref(), Some("acme.billing.v1"));
+        assert_eq!(directory_package("types.proto"), None);
+        assert_eq!(directory_package("my-dir/types.proto"), None);
+    }
+
+    #[tokio::test]
+    async fn test_will_rename_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = "syntax = \"proto3\";\npackage common;\n\nmessage Id {}\n";
+        let types = "syntax = \"proto3\";\npackage common;\nimport \"common/base.proto\";\n\nmessage Money {\n  Id id = 1;\n}\n";
+        let order = "syntax = \"proto3\";\npackage api;\nimport \"common/types.proto\";\n\nmessage Order {\n  common.Money total = 1;\n}\n";
+
+        let workspace = WorkspaceManager::new();
+        workspace.add_workspace_root(dir.path().to_path_buf());
+        let files = [("common/base.proto", base), ("common/types.proto", types), ("api/order.proto", order)];
+        let uris = open_files(&workspace, dir.path(), &files).await;
+        let (base_uri, types_uri, order_uri) = (&uris[0], &uris[1], &uris[2]);
+        let rename = |from: &str, to: &str| RenameFilesParams {
+            files: vec![FileRename {
+                old_uri: Url::from_file_path(dir.path().join(from)).unwrap().to_string(),
+                new_uri: Url::from_file_path(dir.path().join(to)).unwrap().to_string(),
+            }],
+        };
+        let support = EditSupport { annotated: true, ..Default::default() };
+
+        // Moving one file: importers follow it, and the moved file's own
+        // imports still resolve. `base.proto` stays in package `common`, so
+        // only the moved file is offered the new package; its reference to
+        // `Id`, which stays behind, becomes absolute.
+        let edit = will_rename_files(rename("common/types.proto", "shared/types.proto"), &workspace, &support)
+            .await
+            .unwrap();
+        assert_eq!(
+            edits_to(&edit, order_uri),
+            vec![
+                (2, "shared/types.proto".to_string(), IMPORTS_ANNOTATION.to_string()),
+                (5, "shared".to_string(), PACKAGE_ANNOTATION.to_string()),
+            ]
+        );
+        assert_eq!(
+            edits_to(&edit, types_uri),
+            vec![
+                (1, "shared".to_string(), PACKAGE_ANNOTATION.to_string()),
+                (5, ".common.Id".to_string(), PACKAGE_ANNOTATION.to_string()),
+            ]
+        );
+        assert!(edits_to(&edit, base_uri).is_empty());
+        let annotation = &edit.change_annotations.as_ref().unwrap()[PACKAGE_ANNOTATION];
+        assert_eq!(
+            annotation.label,
+            "Update package 'common' to 'shared' in the moved files to match the new directory"
+        );
+        assert_eq!(annotation.needs_confirmation, Some(true));
+
+        // Moving the directory takes the whole package along
+        let edit = will_rename_files(rename("common", "shared"), &workspace, &support).await.unwrap();
+        assert_eq!(
+            edits_to(&edit, order_uri),
+            vec![
+                (2, "shared/types.proto".to_string(), IMPORTS_ANNOTATION.to_string()),
+                (5, "shared".to_string(), PACKAGE_ANNOTATION.to_string()),
+            ]
+        );
+        assert_eq!(
+            edits_to(&edit, types_uri),
+            vec![
+                (2, "shared/base.proto".to_string(), IMPORTS_ANNOTATION.to_string()),
+                (1, "shared".to_string(), PACKAGE_ANNOTATION.to_string()),
+            ]
+        );
+        assert_eq!(edits_to(&edit, base_uri), vec![(1, "shared".to_string(), PACKAGE_ANNOTATION.to_string())]);
+        let annotation = &edit.change_annotations.as_ref().unwrap()[PACKAGE_ANNOTATION];
+        assert_eq!(annotation.label, "Update package 'common' to 'shared' to match the new directory");
+        assert_eq!(annotation.needs_confirmation, Some(true));
+
+        // Without change annotations only the imports are updated
+        let plain = EditSupport::default();
+        let edit = will_rename_files(rename("common", "shared"), &workspace, &plain).await.unwrap();
+        let changes = edit.changes.unwrap();
+        let texts = |uri: &Url| changes.get(uri).into_iter().flatten().map(|e| e.new_text.clone()).collect::<Vec<_>>();
+        assert_eq!(texts(order_uri), vec!["shared/types.proto"]);
+        assert_eq!(texts(types_uri), vec!["shared/base.proto"]);
+        assert!(texts(base_uri).is_empty());
+
+        // Renaming within the same directory changes nothing
+        assert!(will_rename_files(rename("api/order.proto", "api/orders.proto"), &workspace, &support)
+            .await
+            .is_none());
+    }
+}
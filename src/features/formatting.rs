@@ -134,8 +134,7 @@ fn format_with_clang_format(content: &str, clang_format_path: &Path) -> Result<S
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     } else {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
+        Err(std::io::Error::other(
             format!("clang-format failed: {}", stderr),
         ))
     }
@@ -0,0 +1,110 @@
+//! Fuzzy matching of completion candidates against the word being typed.
+
+/// Score for a pattern character matching the candidate
+const MATCH: i32 = 1;
+/// Extra score when the match starts a camel hump or snake/dotted segment
+const SEGMENT_START: i32 = 8;
+/// Extra score when the match directly follows the previous one
+const CONSECUTIVE: i32 = 5;
+/// Penalty when characters are skipped between two matches
+const GAP: i32 = 3;
+/// Extra score when the first pattern character matches the candidate's first
+const LEADING: i32 = 6;
+/// Extra score when the case matches too
+const SAME_CASE: i32 = 1;
+/// Extra score when the candidate is the pattern, ignoring case
+const EXACT: i32 = 20;
+
+/// Scores how well `pattern` matches `candidate`, or `None` if the characters
+/// of `pattern` do not all appear in order (ignoring case). Matches at the start
+/// of camel humps and `_`/`.` separated segments score highest, so `UsrPrf`
+/// matches `UserProfile` and `crt_tm` matches `create_time`.
+pub fn fuzzy_score(pattern: &str, candidate: &str) -> Option<i32> {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    if pattern.len() > chars.len() {
+        return None;
+    }
+
+    let segment_start: Vec<bool> = (0..chars.len())
+        .map(|j| {
+            j == 0 || {
+                let (prev, ch) = (chars[j - 1], chars[j]);
+                prev == '_'
+                    || prev == '.'
+                    || (prev.is_lowercase() && ch.is_uppercase())
+                    || (prev.is_ascii_digit() != ch.is_ascii_digit() && ch != '_')
+            }
+        })
+        .collect();
+
+    const NONE: i32 = i32::MIN / 2;
+    // ending[j]: best score with the current pattern character matched at j
+    // best[j]: best score with it matched anywhere up to j
+    let mut ending = vec![NONE; chars.len()];
+    let mut best = vec![NONE; chars.len()];
+    for (i, &p) in pattern.iter().enumerate() {
+        let mut next_ending = vec![NONE; chars.len()];
+        for (j, &c) in chars.iter().enumerate() {
+            if !c.to_lowercase().eq(p.to_lowercase()) {
+                continue;
+            }
+            let mut bonus = MATCH;
+            if segment_start[j] {
+                bonus += SEGMENT_START;
+            }
+            if c == p {
+                bonus += SAME_CASE;
+            }
+            let previous = if i == 0 {
+                Some(if j == 0 { LEADING } else { 0 })
+            } else if j == 0 {
+                None
+            } else {
+                let consecutive = (ending[j - 1] > NONE).then(|| ending[j - 1] + CONSECUTIVE);
+                let gap = (best[j - 1] > NONE).then(|| best[j - 1] - GAP);
+                consecutive.max(gap)
+            };
+            if let Some(previous) = previous {
+                next_ending[j] = previous + bonus;
+            }
+        }
+        let mut running = NONE;
+        for (j, score) in next_ending.iter().enumerate() {
+            running = running.max(*score);
+            best[j] = running;
+        }
+        ending = next_ending;
+    }
+
+    let score = *best.last()?;
+    if score <= NONE {
+        return None;
+    }
+    let exact = pattern.len() == chars.len() && pattern.iter().zip(&chars).all(|(p, c)| p.to_lowercase().eq(c.to_lowercase()));
+    Some(if exact { score + EXACT } else { score })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("UsrPrf", "UserProfile").is_some());
+        assert!(fuzzy_score("crt_tm", "create_time").is_some());
+        assert!(fuzzy_score("up", "UserProfile").is_some());
+        assert!(fuzzy_score("UsrPrf", "uint32").is_none());
+        assert!(fuzzy_score("ba", "ab").is_none());
+        assert!(fuzzy_score("xa", "ya").is_none());
+
+        // Segment starts beat matches inside a word
+        assert!(fuzzy_score("up", "UserProfile") > fuzzy_score("up", "Lookup"));
+        // Prefixes beat scattered matches, exact matches beat prefixes
+        assert!(fuzzy_score("str", "string") > fuzzy_score("str", "SortRule"));
+        assert!(fuzzy_score("string", "string") > fuzzy_score("string", "strings"));
+    }
+}
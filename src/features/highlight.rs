@@ -0,0 +1,118 @@
+use crate::features::references::{find_type_references_in, reference_target, ReferenceTarget};
+use crate::workspace::WorkspaceManager;
+use tower_lsp::lsp_types::*;
+
+/// Highlight the occurrences of the symbol at the cursor in the current file.
+///
+/// The symbol is resolved the same way as go-to-definition and find
+/// references, so only names resolving to the same fully-qualified message or
+/// enum are highlighted; a field or enum value that happens to share the name
+/// is not. The declaration is a `Write` highlight, every use a `Read`.
+pub async fn provide_document_highlights(
+    params: DocumentHighlightParams,
+    workspace: &WorkspaceManager,
+    content: Option<&str>,
+) -> Option<Vec<DocumentHighlight>> {
+    let uri = &params.text_document_position_params.text_document.uri;
+    let position = params.text_document_position_params.position;
+    let content = content?;
+
+    let target = reference_target(uri, workspace, content, position).await?;
+    tracing::debug!("DocumentHighlight: highlighting {:?}", target);
+
+    let mut highlights = Vec::new();
+    if &target.declaration().uri == uri {
+        highlights.push(DocumentHighlight {
+            range: target.declaration().range,
+            kind: Some(DocumentHighlightKind::WRITE),
+        });
+    }
+    if let ReferenceTarget::Type { full_name, .. } = &target {
+        highlights.extend(
+            find_type_references_in(full_name, uri, workspace)
+                .await
+                .into_iter()
+                .map(|location| DocumentHighlight {
+                    range: location.range,
+                    kind: Some(DocumentHighlightKind::READ),
+                }),
+        );
+    }
+
+    if highlights.is_empty() {
+        None
+    } else {
+        Some(highlights)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(uri: &Url, line: u32, character: u32) -> DocumentHighlightParams {
+        DocumentHighlightParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri: uri.clone() },
+                position: Position { line, character },
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    fn spans(highlights: &[DocumentHighlight]) -> Vec<(u32, u32, DocumentHighlightKind)> {
+        highlights
+            .iter()
+            .map(|h| (h.range.start.line, h.range.start.character, h.kind.unwrap()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_document_highlights() {
+        let content = r#"syntax = "proto3";
+package shop;
+
+message Status {}
+
+message Order {
+  enum Status {
+    UNKNOWN = 0;
+  }
+  Status status = 1;
+  shop.Status overall = 2;
+  string Status_text = 3;
+}
+
+service Orders {
+  rpc Get(Status) returns (Order);
+}
+"#;
+        let workspace = WorkspaceManager::new();
+        let uri = Url::parse("file:///project/shop.proto").unwrap();
+        workspace.open_file(&uri, content).await.unwrap();
+
+        // The top-level message: its declaration, the qualified field type and
+        // the RPC input, but not the nested enum of the same name
+        let highlights = provide_document_highlights(params(&uri, 3, 9), &workspace, Some(content)).await.unwrap();
+        assert_eq!(
+            spans(&highlights),
+            vec![
+                (3, 8, DocumentHighlightKind::WRITE),
+                (10, 7, DocumentHighlightKind::READ),
+                (15, 10, DocumentHighlightKind::READ),
+            ]
+        );
+
+        // From a use of the nested enum
+        let highlights = provide_document_highlights(params(&uri, 9, 3), &workspace, Some(content)).await.unwrap();
+        assert_eq!(
+            spans(&highlights),
+            vec![(6, 7, DocumentHighlightKind::WRITE), (9, 2, DocumentHighlightKind::READ)]
+        );
+
+        // A field is only declared
+        let highlights = provide_document_highlights(params(&uri, 9, 10), &workspace, Some(content)).await.unwrap();
+        assert_eq!(spans(&highlights), vec![(9, 9, DocumentHighlightKind::WRITE)]);
+    }
+}
@@ -293,7 +293,7 @@ where
     None
 }
 
-fn format_message_hover(msg: &MessageElement) -> String {
+pub fn format_message_hover(msg: &MessageElement) -> String {
     let mut output = format!("**Message**: `{}`\n\n", msg.full_name);
     output.push_str("```protobuf\n");
     output.push_str(&format!("message {} {{\n", msg.name));
@@ -320,7 +320,7 @@ fn format_message_hover(msg: &MessageElement) -> String {
     output
 }
 
-fn format_enum_hover(e: &EnumElement) -> String {
+pub fn format_enum_hover(e: &EnumElement) -> String {
     let mut output = format!("**Enum**: `{}`\n\n", e.full_name);
     output.push_str("```protobuf\n");
     output.push_str(&format!("enum {} {{\n", e.name));
@@ -0,0 +1,260 @@
+//! Inlay hints, each kind switchable under `inlayHints` in the settings:
+//! - `resolvedTypes`: the fully-qualified type after short or relative type names
+//! - `jsonNames`: the effective JSON name after field names: an explicit
+//!   `json_name`, or the protoc default when it differs from the field name
+//! - `nextNumber`: the next free number after the `}` of each message and enum
+
+use crate::features::numbering::NumberedBlock;
+use crate::features::text_format::{tokenize, Token};
+use crate::features::tokens::{field_name_column, statement_end, token_at, utf16_column};
+use crate::parser::proto::{EnumElement, FieldElement, MessageElement};
+use crate::workspace::settings::InlayHintSettings;
+use crate::workspace::{TypeTable, WorkspaceManager};
+use tower_lsp::lsp_types::*;
+
+pub async fn provide_inlay_hints(
+    params: InlayHintParams,
+    workspace: &WorkspaceManager,
+    content: Option<&str>,
+) -> Option<Vec<InlayHint>> {
+    let uri = &params.text_document.uri;
+    let proto = workspace.get_file(uri)?;
+    let content = content?;
+    let settings = workspace.settings().inlay_hints;
+    // Parsed columns count bytes; hint positions count UTF-16 code units
+    let lines: Vec<&str> = content.lines().collect();
+    let mut hints = Vec::new();
+
+    if settings.resolved_types {
+        let mut files = vec![proto.clone()];
+        files.extend(workspace.collect_all_imports_async(uri).await);
+        let table = TypeTable::from_files(files.iter().map(|f| f.as_ref()));
+        for type_ref in proto.type_refs.iter().filter(|r| !r.name.starts_with('.')) {
+            let Some(def) = table.resolve(&type_ref.name, &type_ref.scope) else { continue };
+            if def.full_name == type_ref.name {
+                continue;
+            }
+            let Some(line) = lines.get(type_ref.line as usize) else { continue };
+            let end = type_ref.character as usize + type_ref.name.len();
+            let position = Position { line: type_ref.line, character: utf16_column(line, end) };
+            hints.push(hint(position, format!(".{}", def.full_name), InlayHintKind::TYPE, None));
+        }
+    }
+
+    if settings.json_names || settings.next_number {
+        let tokens = tokenize(content);
+        for msg in &proto.messages {
+            message_hints(msg, &settings, &lines, &tokens, &mut hints);
+        }
+        if settings.next_number {
+            for e in &proto.enums {
+                enum_hint(e, &tokens, &mut hints);
+            }
+        }
+    }
+
+    hints.retain(|h| params.range.start <= h.position && h.position <= params.range.end);
+    hints.sort_by_key(|h| (h.position.line, h.position.character));
+    Some(hints)
+}
+
+/// JSON names of a message's fields and the next numbers of the message and
+/// its nested enums, recursing into nested messages
+fn message_hints(
+    msg: &MessageElement,
+    settings: &InlayHintSettings,
+    lines: &[&str],
+    tokens: &[Token],
+    hints: &mut Vec<InlayHint>,
+) {
+    if settings.json_names {
+        for field in &msg.fields {
+            let (json_name, explicit) = effective_json_name(field);
+            if !explicit && json_name == field.name {
+                continue;
+            }
+            let Some(line) = lines.get(field.line as usize) else { continue };
+            let Some(column) = field_name_column(line, &field.name) else { continue };
+            let position = Position { line: field.line, character: utf16_column(line, column + field.name.len()) };
+            let tooltip = if explicit { "Set by the json_name option" } else { "Default JSON name" };
+            hints.push(hint(position, format!("json: {}", json_name), InlayHintKind::PARAMETER, Some(tooltip)));
+        }
+    }
+
+    if settings.next_number {
+        next_number_hint(NumberedBlock::Message(msg), msg.line, msg.character, tokens, hints);
+        for e in &msg.nested_enums {
+            enum_hint(e, tokens, hints);
+        }
+    }
+
+    for nested in &msg.nested_messages {
+        message_hints(nested, settings, lines, tokens, hints);
+    }
+}
+
+fn enum_hint(e: &EnumElement, tokens: &[Token], hints: &mut Vec<InlayHint>) {
+    next_number_hint(NumberedBlock::Enum(e), e.line, e.character, tokens, hints);
+}
+
+/// `next: N` after the `}` closing the block declared at `line`/`character`
+fn next_number_hint(block: NumberedBlock, line: u32, character: u32, tokens: &[Token], hints: &mut Vec<InlayHint>) {
+    let close = token_at(tokens, line, character).and_then(|start| statement_end(tokens, start));
+    if let (Some(close), Some(number)) = (close, block.next_number(None)) {
+        let close = &tokens[close];
+        let position = Position { line: close.line, character: close.end_character() };
+        hints.push(hint(position, format!("next: {}", number), InlayHintKind::PARAMETER, None));
+    }
+}
+
+/// The field's `json_name` option if set, otherwise the name protoc derives:
+/// underscores dropped and the letter after each one capitalized. The flag
+/// tells whether the option was set.
+fn effective_json_name(field: &FieldElement) -> (String, bool) {
+    let explicit = field
+        .options
+        .iter()
+        .find(|o| o.name == "json_name")
+        .map(|o| o.value.trim_matches(|c| c == '"' || c == '\'').to_string());
+    match explicit {
+        Some(name) => (name, true),
+        None => (default_json_name(&field.name), false),
+    }
+}
+
+fn default_json_name(name: &str) -> String {
+    let mut json_name = String::with_capacity(name.len());
+    let mut capitalize_next = false;
+    for c in name.chars() {
+        if c == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            json_name.push(c.to_ascii_uppercase());
+            capitalize_next = false;
+        } else {
+            json_name.push(c);
+        }
+    }
+    json_name
+}
+
+fn hint(position: Position, label: String, kind: InlayHintKind, tooltip: Option<&str>) -> InlayHint {
+    InlayHint {
+        position,
+        label: InlayHintLabel::String(label),
+        kind: Some(kind),
+        text_edits: None,
+        tooltip: tooltip.map(|t| InlayHintTooltip::String(t.to_string())),
+        padding_left: Some(true),
+        padding_right: None,
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::testing::open_files;
+
+    #[test]
+    fn test_default_json_name() {
+        assert_eq!(default_json_name("user_id"), "userId");
+        assert_eq!(default_json_name("name"), "name");
+        assert_eq!(default_json_name("_private__field_2"), "PrivateField2");
+    }
+
+    #[tokio::test]
+    async fn test_inlay_hints() {
+        let dir = tempfile::tempdir().unwrap();
+        let common = r#"syntax = "proto3";
+package shop.common;
+
+message Money {
+  int64 units = 1;
+}
+"#;
+        let orders = r#"syntax = "proto3";
+package shop.orders;
+import "common.proto";
+
+message Order {
+  message Line {
+    string sku = 1 [json_name = "SKU"];
+  }
+  common.Money total_price = 1;
+  .shop.common.Money tax = 2;
+  repeated Line lines = 3;
+  reserved 4 to 6;
+  map<string, shop.common.Money> by_item = 7;
+  /* 単価 */ common.Money unit_price = 8;
+}
+
+enum Channel {
+  CHANNEL_UNSPECIFIED = 0;
+  reserved 1;
+  CHANNEL_WEB = 2;
+}
+message Refund {
+  enum Reason { REASON_UNSPECIFIED = 0; }
+  string id = 1 [json_name = "id"];
+}
+"#;
+        let workspace = WorkspaceManager::new();
+        let uris = open_files(&workspace, dir.path(), &[("common.proto", common), ("orders.proto", orders)]).await;
+
+        let hints = || {
+            let params = InlayHintParams {
+                text_document: TextDocumentIdentifier { uri: uris[1].clone() },
+                range: Range { start: Position { line: 0, character: 0 }, end: Position { line: 30, character: 0 } },
+                work_done_progress_params: Default::default(),
+            };
+            let workspace = &workspace;
+            async move {
+                provide_inlay_hints(params, workspace, Some(orders))
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|h| {
+                        let InlayHintLabel::String(label) = h.label else { panic!("expected a string label") };
+                        (h.position.line, h.position.character, label)
+                    })
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            hints().await,
+            vec![
+                (6, 14, "json: SKU".to_string()),
+                (7, 3, "next: 2".to_string()),
+                (8, 14, ".shop.common.Money".to_string()),
+                (8, 26, "json: totalPrice".to_string()),
+                (10, 15, ".shop.orders.Order.Line".to_string()),
+                (12, 40, "json: byItem".to_string()),
+                // Columns after non-ASCII text count UTF-16 code units
+                (13, 23, ".shop.common.Money".to_string()),
+                (13, 34, "json: unitPrice".to_string()),
+                (14, 1, "next: 9".to_string()),
+                (20, 1, "next: 3".to_string()),
+                (22, 41, "next: 1".to_string()),
+                // An explicit `json_name` is shown even when it is the field name
+                (23, 11, "json: id".to_string()),
+                (24, 1, "next: 2".to_string()),
+            ]
+        );
+
+        workspace.update_settings(&serde_json::json!({
+            "inlayHints": { "resolvedTypes": false, "jsonNames": false }
+        }));
+        assert_eq!(
+            hints().await,
+            vec![
+                (7, 3, "next: 2".to_string()),
+                (14, 1, "next: 9".to_string()),
+                (20, 1, "next: 3".to_string()),
+                (22, 41, "next: 1".to_string()),
+                (24, 1, "next: 2".to_string()),
+            ]
+        );
+    }
+}
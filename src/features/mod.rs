@@ -12,18 +12,47 @@ pub mod code_actions;
 pub mod semantic_tokens;
 pub mod folding;
 pub mod document_link;
+pub mod suppressions;
+pub mod blocks;
+pub mod numbering;
+pub mod options;
+pub mod snippets;
+pub mod naming;
+pub mod fuzzy;
+pub mod text_format;
+pub mod tokens;
+#[cfg(test)]
+pub mod testing;
+pub mod file_rename;
+pub mod highlight;
+pub mod call_hierarchy;
+pub mod type_hierarchy;
+pub mod selection_range;
+pub mod code_lens;
+pub mod inlay_hints;
 
-pub use completion::provide_completion;
+pub use completion::{provide_completion, resolve_completion_item};
 pub use definition::provide_definition_async;
 pub use hover::provide_hover_async;
 pub use symbols::provide_document_symbols;
 pub use formatting::format_document;
-pub use diagnostics::validate_proto_file;
+pub use diagnostics::{
+    provide_document_diagnostics, provide_workspace_diagnostics, validate_dependents,
+    validate_proto_file, DiagnosticsCache,
+};
 pub use references::find_references;
-pub use rename::{prepare_rename, rename};
+pub use rename::{prepare_rename, rename, EditSupport};
+pub use file_rename::will_rename_files;
+pub use highlight::provide_document_highlights;
+pub use call_hierarchy::{incoming_calls, outgoing_calls, prepare_call_hierarchy};
+pub use type_hierarchy::{prepare_type_hierarchy, subtypes, supertypes};
+pub use selection_range::provide_selection_ranges;
+pub use code_lens::provide_code_lenses;
+pub use inlay_hints::provide_inlay_hints;
 pub use workspace_symbols::workspace_symbol;
 pub use signature_help::provide_signature_help;
 pub use code_actions::provide_code_actions;
 pub use semantic_tokens::provide_semantic_tokens_full;
 pub use folding::provide_folding_ranges;
 pub use document_link::provide_document_links;
+pub use numbering::provide_on_type_formatting;
@@ -0,0 +1,168 @@
+//! Naming conventions from the protobuf style guide, used to suggest names.
+
+/// Keywords that start a statement in a message body rather than a field type
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "message", "enum", "oneof", "option", "reserved", "extensions", "extend", "import",
+    "package", "syntax", "edition", "service", "rpc", "returns", "map", "group",
+];
+
+const LABELS: &[&str] = &["optional", "repeated", "required"];
+
+const SCALAR_TYPES: &[&str] = &[
+    "double", "float", "int32", "int64", "uint32", "uint64", "sint32", "sint64", "fixed32",
+    "fixed64", "sfixed32", "sfixed64", "bool", "string", "bytes",
+];
+
+/// Convert a CamelCase name to UPPER_SNAKE_CASE (`PhoneType` -> `PHONE_TYPE`,
+/// `HTTPStatus` -> `HTTP_STATUS`).
+pub fn to_upper_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_is_lower) {
+                out.push('_');
+            }
+        }
+        out.extend(ch.to_uppercase());
+    }
+    out
+}
+
+/// Convert a CamelCase name to lower_snake_case (`UserProfile` -> `user_profile`)
+pub fn to_lower_snake_case(name: &str) -> String {
+    to_upper_snake_case(name).to_lowercase()
+}
+
+/// English plural of the last word of a snake_case name (`address` ->
+/// `addresses`, `category` -> `categories`)
+pub fn pluralize(name: &str) -> String {
+    let ends_with_consonant_y = name.ends_with('y')
+        && name
+            .chars()
+            .rev()
+            .nth(1)
+            .is_some_and(|c| c.is_ascii_alphabetic() && !"aeiou".contains(c));
+    if ends_with_consonant_y {
+        format!("{}ies", &name[..name.len() - 1])
+    } else if ["s", "x", "z", "ch", "sh"].iter().any(|suffix| name.ends_with(suffix)) {
+        format!("{}es", name)
+    } else {
+        format!("{}s", name)
+    }
+}
+
+/// If `prefix` (the text before the cursor) declares a field up to its name,
+/// like `repeated foo.Bar ` or `map<string, Bar> ba`, returns the field type
+/// and whether the field holds several values (repeated or map).
+pub fn field_type_before_name(prefix: &str) -> Option<(String, bool)> {
+    let text = prefix.trim_start();
+    let (declaration, typed) = match text.rfind(char::is_whitespace) {
+        Some(i) => (&text[..i], &text[i + 1..]),
+        None => return None,
+    };
+    if !typed.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+
+    if let Some(rest) = declaration.strip_prefix("map") {
+        let args = rest.trim_start().strip_prefix('<')?.trim_end().strip_suffix('>')?;
+        let (_, value) = args.split_once(',')?;
+        let value = value.trim();
+        return is_type_name(value).then(|| (value.to_string(), true));
+    }
+
+    let words: Vec<&str> = declaration.split_whitespace().collect();
+    let (repeated, type_name) = match words.as_slice() {
+        [type_name] => (false, *type_name),
+        [label, type_name] if LABELS.contains(label) => (*label == "repeated", *type_name),
+        _ => return None,
+    };
+    if LABELS.contains(&type_name) || STATEMENT_KEYWORDS.contains(&type_name) || !is_type_name(type_name) {
+        return None;
+    }
+    Some((type_name.to_string(), repeated))
+}
+
+fn is_type_name(name: &str) -> bool {
+    let name = name.strip_prefix('.').unwrap_or(name);
+    !name.is_empty()
+        && name
+            .split('.')
+            .all(|part| part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+}
+
+/// Idiomatic names for a field of `type_name`: the type in lower_snake_case and
+/// its last word (`UserProfile` -> `user_profile`, `profile`), plural for
+/// repeated fields. Well-known types get their conventional names
+/// (`Timestamp` -> `create_time`, …). Scalars have no derivable name.
+pub fn field_name_suggestions(type_name: &str, repeated: bool) -> Vec<String> {
+    let base = type_name.rsplit('.').next().unwrap_or(type_name);
+    if SCALAR_TYPES.contains(&base) {
+        return Vec::new();
+    }
+
+    let mut names: Vec<String> = Vec::new();
+    if !repeated {
+        let conventional: &[&str] = match base {
+            "Timestamp" => &["create_time", "update_time"],
+            "Duration" => &["timeout"],
+            "FieldMask" => &["update_mask"],
+            _ => &[],
+        };
+        names.extend(conventional.iter().map(|n| n.to_string()));
+    }
+
+    let snake = to_lower_snake_case(base);
+    let last_word = snake.rsplit('_').next().unwrap_or(&snake).to_string();
+    for name in [snake.clone(), last_word] {
+        let name = if repeated { pluralize(&name) } else { name };
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_conversion() {
+        assert_eq!(to_upper_snake_case("Status"), "STATUS");
+        assert_eq!(to_upper_snake_case("PhoneType"), "PHONE_TYPE");
+        assert_eq!(to_upper_snake_case("HTTPStatus"), "HTTP_STATUS");
+        assert_eq!(to_upper_snake_case("V2Type"), "V2_TYPE");
+        assert_eq!(to_lower_snake_case("HTTPRequest"), "http_request");
+        assert_eq!(pluralize("address"), "addresses");
+        assert_eq!(pluralize("category"), "categories");
+        assert_eq!(pluralize("day"), "days");
+        assert_eq!(pluralize("user_profile"), "user_profiles");
+    }
+
+    #[test]
+    fn test_field_type_before_name() {
+        assert_eq!(field_type_before_name("  google.protobuf.Timestamp "), Some(("google.protobuf.Timestamp".to_string(), false)));
+        assert_eq!(field_type_before_name("  repeated Address addr"), Some(("Address".to_string(), true)));
+        assert_eq!(field_type_before_name("  map<string, Item> "), Some(("Item".to_string(), true)));
+        assert_eq!(field_type_before_name("  repeated "), None);
+        assert_eq!(field_type_before_name("  option "), None);
+        assert_eq!(field_type_before_name("  Address"), None);
+        assert_eq!(field_type_before_name("  Address addr = "), None);
+    }
+
+    #[test]
+    fn test_field_name_suggestions() {
+        assert_eq!(
+            field_name_suggestions("google.protobuf.Timestamp", false),
+            vec!["create_time", "update_time", "timestamp"]
+        );
+        assert_eq!(field_name_suggestions("UserProfile", false), vec!["user_profile", "profile"]);
+        assert_eq!(field_name_suggestions("UserProfile", true), vec!["user_profiles", "profiles"]);
+        assert!(field_name_suggestions("string", false).is_empty());
+    }
+}
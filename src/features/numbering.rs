@@ -0,0 +1,373 @@
+use crate::features::blocks::open_blocks;
+use crate::parser::proto::{EnumElement, MessageElement, NumberRange};
+use crate::parser::ParsedProto;
+use crate::workspace::WorkspaceManager;
+use tower_lsp::lsp_types::*;
+
+/// Largest valid field number (2^29 - 1)
+pub const FIELD_NUMBER_MAX: i32 = 536_870_911;
+
+/// Field numbers reserved for the protobuf implementation
+pub const PROTOC_RESERVED_RANGE: NumberRange = NumberRange {
+    start: 19_000,
+    end: 19_999,
+};
+
+/// The line being edited, with its live text. The last good parse may still
+/// hold the member being typed there; it is left out of the used numbers only
+/// while the live text still declares it, so a member that a newly inserted
+/// line pushed down keeps its number.
+#[derive(Debug, Clone, Copy)]
+pub struct EditedLine<'a> {
+    pub line: u32,
+    pub text: &'a str,
+}
+
+impl EditedLine<'_> {
+    /// Whether the member `name` that the parse places on `line` is the one
+    /// declared by the edited text
+    pub fn declares(&self, line: u32, name: &str) -> bool {
+        let declaration = self.text.split('=').next().unwrap_or("");
+        line == self.line
+            && declaration
+                .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .any(|word| word == name)
+    }
+}
+
+/// A block whose members are numbered
+#[derive(Debug, Clone, Copy)]
+pub enum NumberedBlock<'a> {
+    /// A message; fields inside its oneofs share its numbers
+    Message(&'a MessageElement),
+    Enum(&'a EnumElement),
+}
+
+impl NumberedBlock<'_> {
+    /// Number after the highest one in use, skipping reserved numbers. The
+    /// member being typed on the `edited` line is ignored.
+    pub fn next_number(&self, edited: Option<EditedLine>) -> Option<i32> {
+        let used = self.used_numbers(edited);
+        let start = match (used.iter().max(), self) {
+            (Some(max), _) => max.checked_add(1)?,
+            // proto3 requires the first enum value to be zero
+            (None, NumberedBlock::Enum(_)) => 0,
+            (None, NumberedBlock::Message(_)) => 1,
+        };
+        self.first_free_from(start, &used)
+    }
+
+    /// Lowest number that is neither used nor reserved
+    pub fn lowest_free_number(&self, edited: Option<EditedLine>) -> Option<i32> {
+        let used = self.used_numbers(edited);
+        let start = match self {
+            NumberedBlock::Message(_) => 1,
+            NumberedBlock::Enum(_) => 0,
+        };
+        self.first_free_from(start, &used)
+    }
+
+    fn used_numbers(&self, edited: Option<EditedLine>) -> Vec<i32> {
+        let edited = |line: u32, name: &str| edited.is_some_and(|e| e.declares(line, name));
+        match self {
+            NumberedBlock::Message(msg) => msg
+                .fields
+                .iter()
+                .filter(|f| !edited(f.line, &f.name))
+                .map(|f| f.number)
+                .collect(),
+            NumberedBlock::Enum(e) => e
+                .values
+                .iter()
+                .filter(|v| !edited(v.line, &v.name))
+                .map(|v| v.number)
+                .collect(),
+        }
+    }
+
+    fn first_free_from(&self, start: i32, used: &[i32]) -> Option<i32> {
+        let (reserved, max) = match self {
+            NumberedBlock::Message(msg) => (&msg.reserved_ranges, FIELD_NUMBER_MAX),
+            NumberedBlock::Enum(e) => (&e.reserved_ranges, i32::MAX),
+        };
+        let is_message = matches!(self, NumberedBlock::Message(_));
+
+        let mut number = start;
+        loop {
+            // Jump past whichever range covers the candidate
+            let covering = reserved
+                .iter()
+                .chain(is_message.then_some(&PROTOC_RESERVED_RANGE))
+                .find(|r| r.contains(number));
+            if let Some(range) = covering {
+                number = range.end.checked_add(1)?;
+            } else if used.contains(&number) {
+                number = number.checked_add(1)?;
+            } else {
+                break;
+            }
+        }
+        (number <= max).then_some(number)
+    }
+}
+
+/// Find the message or enum whose body contains `position`, by tracking the
+/// braces of `content` (the live text, which may not parse) and looking the
+/// blocks up by name in `proto`.
+pub fn enclosing_numbered_block<'a>(
+    proto: &'a ParsedProto,
+    content: &str,
+    position: Position,
+) -> Option<NumberedBlock<'a>> {
+    let mut block: Option<NumberedBlock<'a>> = None;
+    for header in open_blocks(content, position) {
+        let mut words = header.split_whitespace();
+        let keyword = words.next().unwrap_or("");
+        let name = words.next().unwrap_or("");
+        block = match (keyword, block) {
+            ("message", None) => proto
+                .messages
+                .iter()
+                .find(|m| m.name == name)
+                .map(NumberedBlock::Message),
+            ("message", Some(NumberedBlock::Message(parent))) => parent
+                .nested_messages
+                .iter()
+                .find(|m| m.name == name)
+                .map(NumberedBlock::Message),
+            ("enum", None) => proto.enums.iter().find(|e| e.name == name).map(NumberedBlock::Enum),
+            ("enum", Some(NumberedBlock::Message(parent))) => parent
+                .nested_enums
+                .iter()
+                .find(|e| e.name == name)
+                .map(NumberedBlock::Enum),
+            ("oneof", Some(NumberedBlock::Message(parent))) => Some(NumberedBlock::Message(parent)),
+            // extend, service, rpc and option bodies are not numbered
+            _ => None,
+        };
+        block?;
+    }
+    block
+}
+
+/// Whether `prefix` (the text before the cursor) ends in the `=` of a field or
+/// enum value declaration, optionally followed by the digits typed so far.
+pub fn is_number_assignment(prefix: &str, block: &NumberedBlock) -> bool {
+    let before_digits = prefix.trim_end_matches(|c: char| c.is_ascii_digit()).trim_end();
+    let Some(declaration) = before_digits.strip_suffix('=') else {
+        return false;
+    };
+    is_declaration(declaration, block)
+}
+
+/// Whether `text` is a field (`[label] type name`) or enum value (`NAME`)
+/// declaration still missing its number.
+fn is_declaration(text: &str, block: &NumberedBlock) -> bool {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let Some(name) = words.last() else {
+        return false;
+    };
+    let is_identifier = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_identifier || text.contains(['=', '[', '(', '{', '}']) {
+        return false;
+    }
+    match block {
+        NumberedBlock::Enum(_) => words.len() == 1 && !matches!(words[0], "option" | "reserved"),
+        NumberedBlock::Message(_) => {
+            words.len() >= 2
+                && !matches!(
+                    words[0],
+                    "option" | "reserved" | "extensions" | "import" | "package" | "syntax"
+                )
+        }
+    }
+}
+
+/// Insert the next free number when `=` is typed after a field or enum value
+/// name, or when a declaration without a number is ended with `;`.
+pub fn provide_on_type_formatting(
+    params: DocumentOnTypeFormattingParams,
+    workspace: &WorkspaceManager,
+    content: Option<&str>,
+) -> Option<Vec<TextEdit>> {
+    let uri = &params.text_document_position.text_document.uri;
+    let position = params.text_document_position.position;
+    let content = content?;
+    let proto = workspace.get_file(uri)?;
+
+    let line = content.lines().nth(position.line as usize)?;
+    let cursor = (position.character as usize).min(line.len());
+    let prefix = line.get(..cursor)?;
+    let block = enclosing_numbered_block(&proto, content, position)?;
+    let number = block.next_number(Some(EditedLine { line: position.line, text: line }))?;
+
+    match params.ch.as_str() {
+        "=" => {
+            if !is_declaration(prefix.strip_suffix('=')?, &block) {
+                return None;
+            }
+            let rest = line[cursor..].trim();
+            let new_text = if rest.is_empty() {
+                format!(" {};", number)
+            } else if rest.starts_with(';') {
+                format!(" {}", number)
+            } else {
+                return None;
+            };
+            Some(vec![TextEdit {
+                range: Range {
+                    start: position,
+                    end: position,
+                },
+                new_text,
+            }])
+        }
+        ";" => {
+            let declaration = prefix.strip_suffix(';')?;
+            if !is_declaration(declaration, &block) {
+                return None;
+            }
+            let at = Position {
+                line: position.line,
+                character: declaration.trim_end().len() as u32,
+            };
+            Some(vec![TextEdit {
+                range: Range {
+                    start: at,
+                    end: Position {
+                        line: position.line,
+                        character: declaration.len() as u32,
+                    },
+                },
+                new_text: format!(" = {}", number),
+            }])
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = r#"syntax = "proto3";
+package test;
+
+message User {
+  reserved 3, 5 to 7;
+  string name = 1;
+  oneof contact {
+    string email = 2;
+    string phone = 4;
+  }
+  message Address {
+    string city = 1;
+  }
+  enum Kind {
+    reserved 1;
+    KIND_UNSPECIFIED = 0;
+  }
+}
+
+message Big {
+  int32 a = 18999;
+}
+"#;
+
+    async fn parse() -> ParsedProto {
+        ParsedProto::parse("file:///test.proto".to_string(), CONTENT).await.unwrap()
+    }
+
+    fn at(line: u32, character: u32) -> Position {
+        Position { line, character }
+    }
+
+    #[tokio::test]
+    async fn test_next_numbers() {
+        let proto = parse().await;
+
+        // Oneof fields count, reserved 5..7 is skipped
+        let block = enclosing_numbered_block(&proto, CONTENT, at(9, 0)).unwrap();
+        assert!(matches!(block, NumberedBlock::Message(m) if m.name == "User"));
+        assert_eq!(block.next_number(None), Some(8));
+
+        let block = enclosing_numbered_block(&proto, CONTENT, at(8, 4)).unwrap();
+        assert!(matches!(block, NumberedBlock::Message(m) if m.name == "User"));
+
+        let block = enclosing_numbered_block(&proto, CONTENT, at(12, 0)).unwrap();
+        assert!(matches!(block, NumberedBlock::Message(m) if m.name == "Address"));
+        assert_eq!(block.next_number(None), Some(2));
+
+        // Reserved enum value 1 is skipped
+        let block = enclosing_numbered_block(&proto, CONTENT, at(16, 0)).unwrap();
+        assert_eq!(block.next_number(None), Some(2));
+        assert_eq!(block.next_number(Some(EditedLine { line: 15, text: "    KIND_UNSPECIFIED = " })), Some(0));
+        // A new line above the value pushed it down: line 15 no longer declares it
+        assert_eq!(block.next_number(Some(EditedLine { line: 15, text: "    KIND_OTHER = " })), Some(2));
+
+        // The protoc reserved band is skipped
+        let block = enclosing_numbered_block(&proto, CONTENT, at(21, 0)).unwrap();
+        assert_eq!(block.next_number(None), Some(20000));
+        assert_eq!(block.lowest_free_number(None), Some(1));
+
+        assert!(enclosing_numbered_block(&proto, CONTENT, at(2, 0)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_is_number_assignment() {
+        let proto = parse().await;
+        let message = NumberedBlock::Message(&proto.messages[0]);
+        let enumeration = NumberedBlock::Enum(&proto.messages[0].nested_enums[0]);
+
+        assert!(is_number_assignment("  string email = ", &message));
+        assert!(is_number_assignment("  repeated Foo foos =1", &message));
+        assert!(!is_number_assignment("  option deprecated = ", &message));
+        assert!(!is_number_assignment("  string s = 1 [default = ", &message));
+        assert!(!is_number_assignment("  email = ", &message));
+        assert!(is_number_assignment("  KIND_OTHER = ", &enumeration));
+        assert!(!is_number_assignment("  int32 KIND = ", &enumeration));
+    }
+
+    #[tokio::test]
+    async fn test_on_type_formatting() {
+        let workspace = WorkspaceManager::new();
+        let uri = Url::parse("file:///test.proto").unwrap();
+        workspace.open_file(&uri, CONTENT).await.unwrap();
+
+        let request = |content: &str, line: u32, character: u32, ch: &str| {
+            provide_on_type_formatting(
+                DocumentOnTypeFormattingParams {
+                    text_document_position: TextDocumentPositionParams {
+                        text_document: TextDocumentIdentifier { uri: uri.clone() },
+                        position: at(line, character),
+                    },
+                    ch: ch.to_string(),
+                    options: Default::default(),
+                },
+                &workspace,
+                Some(content),
+            )
+        };
+
+        let typed = CONTENT.replace("  string name = 1;\n", "  string name = 1;\n  string url =\n");
+        let edits = request(&typed, 6, 14, "=").unwrap();
+        assert_eq!(edits[0].new_text, " 8;");
+        assert_eq!(edits[0].range.start, at(6, 14));
+
+        // A line inserted above `city = 1` sits where the parse still has it
+        let inserted = CONTENT.replace("    string city = 1;\n", "    string zip =\n    string city = 1;\n");
+        assert_eq!(request(&inserted, 11, 16, "=").unwrap()[0].new_text, " 2;");
+
+        let typed = CONTENT.replace("  string name = 1;\n", "  string name = 1;\n  string url ;\n");
+        let edits = request(&typed, 6, 14, ";").unwrap();
+        assert_eq!(edits[0].new_text, " = 8");
+        assert_eq!(edits[0].range.start, at(6, 12));
+        assert_eq!(edits[0].range.end, at(6, 13));
+
+        // Statements other than declarations are left alone
+        let typed = CONTENT.replace("  string name = 1;\n", "  string name = 1;\n  option deprecated =\n");
+        assert!(request(&typed, 6, 21, "=").is_none());
+        assert!(request(CONTENT, 1, 13, ";").is_none());
+    }
+}
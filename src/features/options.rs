@@ -0,0 +1,922 @@
+use crate::features::blocks::open_blocks;
+use crate::features::text_format::{aggregate_values, TextField, TextValue, Token, TokenKind};
+use crate::parser::proto::{FieldElement, FieldLabelProto, MessageElement};
+use crate::parser::ParsedProto;
+use crate::workspace::{TypeKind, TypeTable, WorkspaceManager};
+use std::sync::Arc;
+use tower_lsp::lsp_types::*;
+
+/// Where an option appears, i.e. which `google.protobuf.*Options` message it sets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionTarget {
+    File,
+    Message,
+    Field,
+    Oneof,
+    Enum,
+    EnumValue,
+    Service,
+    Method,
+}
+
+impl OptionTarget {
+    /// Name of the descriptor.proto message extended by custom options for this target
+    pub fn options_message(self) -> &'static str {
+        match self {
+            OptionTarget::File => "FileOptions",
+            OptionTarget::Message => "MessageOptions",
+            OptionTarget::Field => "FieldOptions",
+            OptionTarget::Oneof => "OneofOptions",
+            OptionTarget::Enum => "EnumOptions",
+            OptionTarget::EnumValue => "EnumValueOptions",
+            OptionTarget::Service => "ServiceOptions",
+            OptionTarget::Method => "MethodOptions",
+        }
+    }
+}
+
+/// Value type of a built-in option
+#[derive(Debug, Clone, Copy)]
+pub enum BuiltinValue {
+    Bool,
+    String,
+    Enum(&'static [&'static str]),
+    /// `default`: typed like the field it belongs to
+    FieldDefault,
+}
+
+/// An option declared in `google/protobuf/descriptor.proto`
+#[derive(Debug)]
+pub struct BuiltinOption {
+    pub name: &'static str,
+    pub targets: &'static [OptionTarget],
+    pub value: BuiltinValue,
+    pub doc: &'static str,
+}
+
+use OptionTarget as T;
+
+const FEATURE_TARGETS: &[OptionTarget] = &[T::File, T::Message, T::Field, T::Enum];
+const DEPRECATED_TARGETS: &[OptionTarget] = &[
+    T::File,
+    T::Message,
+    T::Field,
+    T::Enum,
+    T::EnumValue,
+    T::Service,
+    T::Method,
+];
+
+pub const BUILTIN_OPTIONS: &[BuiltinOption] = &[
+    // FileOptions
+    BuiltinOption { name: "java_package", targets: &[T::File], value: BuiltinValue::String, doc: "Java package for the generated classes." },
+    BuiltinOption { name: "java_outer_classname", targets: &[T::File], value: BuiltinValue::String, doc: "Name of the Java wrapper class for this file." },
+    BuiltinOption { name: "java_multiple_files", targets: &[T::File], value: BuiltinValue::Bool, doc: "Generate a separate Java file for each top-level message, enum and service." },
+    BuiltinOption { name: "java_string_check_utf8", targets: &[T::File], value: BuiltinValue::Bool, doc: "Check UTF-8 when setting string fields in Java." },
+    BuiltinOption { name: "optimize_for", targets: &[T::File], value: BuiltinValue::Enum(&["SPEED", "CODE_SIZE", "LITE_RUNTIME"]), doc: "Generate code optimized for speed, size, or the lite runtime." },
+    BuiltinOption { name: "go_package", targets: &[T::File], value: BuiltinValue::String, doc: "Go import path of the generated package." },
+    BuiltinOption { name: "cc_generic_services", targets: &[T::File], value: BuiltinValue::Bool, doc: "Generate abstract C++ service classes." },
+    BuiltinOption { name: "java_generic_services", targets: &[T::File], value: BuiltinValue::Bool, doc: "Generate abstract Java service classes." },
+    BuiltinOption { name: "py_generic_services", targets: &[T::File], value: BuiltinValue::Bool, doc: "Generate abstract Python service classes." },
+    BuiltinOption { name: "cc_enable_arenas", targets: &[T::File], value: BuiltinValue::Bool, doc: "Enable arena allocation in generated C++ code." },
+    BuiltinOption { name: "objc_class_prefix", targets: &[T::File], value: BuiltinValue::String, doc: "Prefix for generated Objective-C classes." },
+    BuiltinOption { name: "csharp_namespace", targets: &[T::File], value: BuiltinValue::String, doc: "Namespace for generated C# classes." },
+    BuiltinOption { name: "swift_prefix", targets: &[T::File], value: BuiltinValue::String, doc: "Prefix for generated Swift types." },
+    BuiltinOption { name: "php_class_prefix", targets: &[T::File], value: BuiltinValue::String, doc: "Prefix for generated PHP classes." },
+    BuiltinOption { name: "php_namespace", targets: &[T::File], value: BuiltinValue::String, doc: "Namespace for generated PHP classes." },
+    BuiltinOption { name: "php_metadata_namespace", targets: &[T::File], value: BuiltinValue::String, doc: "Namespace for generated PHP metadata classes." },
+    BuiltinOption { name: "ruby_package", targets: &[T::File], value: BuiltinValue::String, doc: "Ruby package for the generated classes." },
+    // MessageOptions
+    BuiltinOption { name: "message_set_wire_format", targets: &[T::Message], value: BuiltinValue::Bool, doc: "Use the legacy MessageSet wire format." },
+    BuiltinOption { name: "no_standard_descriptor_accessor", targets: &[T::Message], value: BuiltinValue::Bool, doc: "Omit the standard `descriptor()` accessor." },
+    BuiltinOption { name: "map_entry", targets: &[T::Message], value: BuiltinValue::Bool, doc: "Set by protoc on synthesized map entry messages; do not use directly." },
+    // FieldOptions
+    BuiltinOption { name: "default", targets: &[T::Field], value: BuiltinValue::FieldDefault, doc: "Default value of a proto2 field." },
+    BuiltinOption { name: "json_name", targets: &[T::Field], value: BuiltinValue::String, doc: "Name of the field in the JSON mapping." },
+    BuiltinOption { name: "ctype", targets: &[T::Field], value: BuiltinValue::Enum(&["STRING", "CORD", "STRING_PIECE"]), doc: "C++ representation of a string or bytes field." },
+    BuiltinOption { name: "packed", targets: &[T::Field], value: BuiltinValue::Bool, doc: "Use the packed encoding for a repeated scalar field." },
+    BuiltinOption { name: "jstype", targets: &[T::Field], value: BuiltinValue::Enum(&["JS_NORMAL", "JS_STRING", "JS_NUMBER"]), doc: "JavaScript type used for 64-bit integer fields." },
+    BuiltinOption { name: "lazy", targets: &[T::Field], value: BuiltinValue::Bool, doc: "Parse the message field lazily." },
+    BuiltinOption { name: "unverified_lazy", targets: &[T::Field], value: BuiltinValue::Bool, doc: "Parse the message field lazily without eager verification." },
+    BuiltinOption { name: "weak", targets: &[T::Field], value: BuiltinValue::Bool, doc: "Weak field (Google-internal)." },
+    BuiltinOption { name: "debug_redact", targets: &[T::Field, T::EnumValue], value: BuiltinValue::Bool, doc: "Redact the value when printing for debugging." },
+    BuiltinOption { name: "retention", targets: &[T::Field], value: BuiltinValue::Enum(&["RETENTION_UNKNOWN", "RETENTION_RUNTIME", "RETENTION_SOURCE"]), doc: "Whether an option is kept in runtime descriptors." },
+    BuiltinOption { name: "targets", targets: &[T::Field], value: BuiltinValue::Enum(&["TARGET_TYPE_UNKNOWN", "TARGET_TYPE_FILE", "TARGET_TYPE_EXTENSION_RANGE", "TARGET_TYPE_MESSAGE", "TARGET_TYPE_FIELD", "TARGET_TYPE_ONEOF", "TARGET_TYPE_ENUM", "TARGET_TYPE_ENUM_ENTRY", "TARGET_TYPE_SERVICE", "TARGET_TYPE_METHOD"]), doc: "Kinds of elements an option may be set on." },
+    // EnumOptions
+    BuiltinOption { name: "allow_alias", targets: &[T::Enum], value: BuiltinValue::Bool, doc: "Allow several values to share a number." },
+    // MethodOptions
+    BuiltinOption { name: "idempotency_level", targets: &[T::Method], value: BuiltinValue::Enum(&["IDEMPOTENCY_UNKNOWN", "NO_SIDE_EFFECTS", "IDEMPOTENT"]), doc: "Whether the method has side effects." },
+    // Shared
+    BuiltinOption { name: "deprecated", targets: DEPRECATED_TARGETS, value: BuiltinValue::Bool, doc: "Mark the element as deprecated." },
+    BuiltinOption { name: "features.field_presence", targets: FEATURE_TARGETS, value: BuiltinValue::Enum(&["EXPLICIT", "IMPLICIT", "LEGACY_REQUIRED"]), doc: "Editions: whether fields track presence." },
+    BuiltinOption { name: "features.enum_type", targets: FEATURE_TARGETS, value: BuiltinValue::Enum(&["OPEN", "CLOSED"]), doc: "Editions: whether enums accept unknown values." },
+    BuiltinOption { name: "features.repeated_field_encoding", targets: FEATURE_TARGETS, value: BuiltinValue::Enum(&["PACKED", "EXPANDED"]), doc: "Editions: encoding of repeated scalar fields." },
+    BuiltinOption { name: "features.utf8_validation", targets: FEATURE_TARGETS, value: BuiltinValue::Enum(&["VERIFY", "NONE"]), doc: "Editions: whether string fields are checked for UTF-8." },
+    BuiltinOption { name: "features.message_encoding", targets: FEATURE_TARGETS, value: BuiltinValue::Enum(&["LENGTH_PREFIXED", "DELIMITED"]), doc: "Editions: wire encoding of message fields." },
+    BuiltinOption { name: "features.json_format", targets: FEATURE_TARGETS, value: BuiltinValue::Enum(&["ALLOW", "LEGACY_BEST_EFFORT"]), doc: "Editions: whether the JSON mapping must be well-defined." },
+];
+
+/// What the cursor is on inside an option
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OptionContext {
+    /// Typing an option name that starts at `start` (byte column)
+    Name { target: OptionTarget, start: usize },
+    /// Typing the value of `name`
+    Value { target: OptionTarget, name: String },
+    /// Inside the `{ … }` of a message-typed option, within the nested message
+    /// values of the fields in `path`; `field` is set after `field:`
+    Aggregate { target: OptionTarget, name: String, path: Vec<String>, field: Option<String> },
+}
+
+/// Completion items for option names and values. Returns `None` when the cursor
+/// is not inside an option, so the regular completions apply.
+pub async fn provide_option_completions(
+    uri: &Url,
+    workspace: &WorkspaceManager,
+    content: &str,
+    position: Position,
+) -> Option<Vec<CompletionItem>> {
+    let context = option_context(content, position)?;
+    let proto = workspace.get_file(uri)?;
+    let mut files = vec![proto];
+    files.extend(workspace.collect_all_imports_async(uri).await);
+    let index = OptionIndex::new(&files);
+
+    let items = match context {
+        OptionContext::Name { target, start } => index.name_items(target, position, start),
+        OptionContext::Value { target, name } => match index.resolve(target, &name) {
+            Some(OptionType::Builtin(value)) => builtin_value_items(value),
+            Some(OptionType::Field { field, scope }) => index.field_value_items(field, &scope),
+            None => Vec::new(),
+        },
+        OptionContext::Aggregate { target, name, path, field } => {
+            let mut message = index.resolve(target, &name).and_then(|t| index.option_message(&t));
+            for part in &path {
+                message = message.and_then(|m| {
+                    let field = m.fields.iter().find(|f| f.name == *part)?;
+                    index.field_message(field, &m.full_name)
+                });
+            }
+            match message {
+                Some(message) => match field {
+                    None => aggregate_field_items(message),
+                    Some(field) => message
+                        .fields
+                        .iter()
+                        .find(|f| f.name == field)
+                        .map(|f| index.field_value_items(f, &message.full_name))
+                        .unwrap_or_default(),
+                },
+                None => Vec::new(),
+            }
+        }
+    };
+    Some(items)
+}
+
+/// Diagnostics for aggregate option values (`option (x) = { … }`) that do not
+/// fit the option's message type: unknown fields and values of the wrong kind.
+/// Options that cannot be resolved are not checked.
+pub async fn validate_option_values(uri: &Url, workspace: &WorkspaceManager, content: &str) -> Vec<Diagnostic> {
+    let values = aggregate_values(content);
+    if values.is_empty() {
+        return Vec::new();
+    }
+    let Some(proto) = workspace.get_file(uri) else {
+        return Vec::new();
+    };
+    let mut files = vec![proto];
+    files.extend(workspace.collect_all_imports_async(uri).await);
+    let index = OptionIndex::new(&files);
+
+    let mut diagnostics = Vec::new();
+    for (open, fields) in values {
+        let inside = Position {
+            line: open.line,
+            // `open_blocks` counts bytes
+            character: open.offset + 1,
+        };
+        let Some((_, target, name)) = aggregate_option(&open_blocks(content, inside)) else {
+            continue;
+        };
+        if let Some(message) = index.resolve(target, &name).and_then(|t| index.option_message(&t)) {
+            index.check_fields(&fields, message, &mut diagnostics);
+        }
+    }
+    diagnostics
+}
+
+/// Work out whether `position` is on an option name, value or aggregate field
+fn option_context(content: &str, position: Position) -> Option<OptionContext> {
+    let line = content.lines().nth(position.line as usize).unwrap_or("");
+    let cursor = (position.character as usize).min(line.len());
+    let prefix = line.get(..cursor)?;
+    let blocks = open_blocks(content, position);
+
+    // Inside `{ … }` after `option (x) =` or `[(x) =`
+    if let Some((depth, target, name)) = aggregate_option(&blocks) {
+        let path = blocks[depth + 1..].iter().map(|h| nested_field_name(h)).collect();
+        let tail = &prefix[prefix.rfind(['{', '}']).map(|i| i + 1).unwrap_or(0)..];
+        let partial = tail.rsplit(char::is_whitespace).next().unwrap_or("");
+        let before = tail[..tail.len() - partial.len()].trim_end();
+        let field = if let Some((field, _)) = partial.split_once(':') {
+            Some(field.to_string())
+        } else {
+            before
+                .strip_suffix(':')
+                .map(|b| b.rsplit(char::is_whitespace).next().unwrap_or("").to_string())
+        };
+        return Some(OptionContext::Aggregate { target, name, path, field });
+    }
+
+    // Compact options: `string s = 1 [deprecated = true, json_name = "x"]`
+    if let Some(open) = unclosed_bracket(prefix) {
+        let target = enclosing_target(&blocks, true)?;
+        let segment_start = prefix[open..].rfind(',').map(|i| open + i).unwrap_or(open) + 1;
+        let segment = &prefix[segment_start..];
+        return Some(match segment.split_once('=') {
+            Some((name, _)) => OptionContext::Value {
+                target,
+                name: name.trim().to_string(),
+            },
+            None => OptionContext::Name {
+                target,
+                start: segment_start + (segment.len() - segment.trim_start().len()),
+            },
+        });
+    }
+
+    // Option statements: `option java_package = "x";`
+    let indent = prefix.len() - prefix.trim_start().len();
+    let rest = prefix.trim_start().strip_prefix("option")?;
+    if !rest.starts_with(char::is_whitespace) || rest.contains(';') {
+        return None;
+    }
+    let target = enclosing_target(&blocks, false)?;
+    Some(match rest.split_once('=') {
+        Some((name, _)) => OptionContext::Value {
+            target,
+            name: name.trim().to_string(),
+        },
+        None => OptionContext::Name {
+            target,
+            start: indent + "option".len() + (rest.len() - rest.trim_start().len()),
+        },
+    })
+}
+
+/// The aggregate option value among the open `blocks`: the index of its block,
+/// the option target and the option name
+fn aggregate_option(blocks: &[String]) -> Option<(usize, OptionTarget, String)> {
+    let depth = blocks.iter().position(|header| header.ends_with('='))?;
+    let header = blocks[depth].trim_end_matches('=');
+    let compact = header.contains('[');
+    let name = option_name_in(header)?;
+    let target = enclosing_target(&blocks[..depth], compact)?;
+    Some((depth, target, name))
+}
+
+/// Field whose message value a block inside an aggregate opens, e.g. `backoff`
+/// in `retries: 3 backoff` or `items` in `items: [{ a: 1 }, `
+fn nested_field_name(header: &str) -> String {
+    let header = match unclosed_bracket(header) {
+        Some(open) => &header[..open],
+        None => header,
+    };
+    let header = header.trim_end().trim_end_matches(':').trim_end();
+    header.rsplit(char::is_whitespace).next().unwrap_or("").to_string()
+}
+
+/// The option being assigned at the end of a block header, e.g. `(my.config)`
+/// in `string s = 1 [deprecated = true, (my.config) `
+fn option_name_in(header: &str) -> Option<String> {
+    let start = header
+        .rfind(['[', ','])
+        .map(|i| i + 1)
+        .or_else(|| header.find("option ").map(|i| i + "option ".len()))?;
+    let name = header[start..].trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Byte offset of a `[` before the cursor that has not been closed yet
+fn unclosed_bracket(prefix: &str) -> Option<usize> {
+    let mut open = None;
+    let mut quote = None;
+    for (i, ch) in prefix.char_indices() {
+        match (quote, ch) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '/') if prefix[i..].starts_with("//") => return None,
+            (None, '[') => open = Some(i),
+            (None, ']') => open = None,
+            _ => {}
+        }
+    }
+    open
+}
+
+/// Target of an option inside the given blocks; `compact` for `[...]` options
+fn enclosing_target(blocks: &[String], compact: bool) -> Option<OptionTarget> {
+    let keyword = blocks
+        .last()
+        .map(|header| header.split_whitespace().next().unwrap_or(""));
+    Some(match (keyword, compact) {
+        (None, false) => T::File,
+        (Some("message"), false) => T::Message,
+        (Some("message" | "oneof" | "extend"), true) => T::Field,
+        (Some("oneof"), false) => T::Oneof,
+        (Some("enum"), false) => T::Enum,
+        (Some("enum"), true) => T::EnumValue,
+        (Some("service"), false) => T::Service,
+        (Some("rpc"), false) => T::Method,
+        _ => return None,
+    })
+}
+
+/// Type of a resolved option
+enum OptionType<'a> {
+    Builtin(BuiltinValue),
+    /// A custom option (extension field) or one of its sub-fields; `scope` is
+    /// where the field's type name is resolved from
+    Field { field: &'a FieldElement, scope: String },
+}
+
+/// Custom options and types visible from a file
+struct OptionIndex<'a> {
+    files: &'a [Arc<ParsedProto>],
+    table: TypeTable,
+    package: String,
+}
+
+impl<'a> OptionIndex<'a> {
+    fn new(files: &'a [Arc<ParsedProto>]) -> Self {
+        Self {
+            files,
+            table: TypeTable::from_files(files.iter().map(|f| f.as_ref())),
+            package: files
+                .first()
+                .and_then(|f| f.package.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Extension fields of `google.protobuf.<target>Options`, with the package
+    /// of the file declaring them
+    fn custom_options(&self, target: OptionTarget) -> Vec<(&'a FieldElement, &'a str)> {
+        let mut options = Vec::new();
+        for file in self.files {
+            let package = file.package.as_deref().unwrap_or("");
+            for extend in &file.extends {
+                let extendee = extend.name.rsplit('.').next().unwrap_or(&extend.name);
+                if extendee == target.options_message() {
+                    options.extend(extend.fields.iter().map(|f| (f, package)));
+                }
+            }
+        }
+        options
+    }
+
+    /// Name to write for a custom option: package-qualified unless declared in
+    /// the current package
+    fn custom_option_name(&self, field: &FieldElement, package: &str) -> String {
+        if package.is_empty() || package == self.package {
+            format!("({})", field.name)
+        } else {
+            format!("({}.{})", package, field.name)
+        }
+    }
+
+    fn name_items(&self, target: OptionTarget, position: Position, start: usize) -> Vec<CompletionItem> {
+        let range = Range {
+            start: Position {
+                line: position.line,
+                character: start as u32,
+            },
+            end: position,
+        };
+        let mut items = Vec::new();
+
+        for option in BUILTIN_OPTIONS.iter().filter(|o| o.targets.contains(&target)) {
+            items.push(CompletionItem {
+                label: option.name.to_string(),
+                kind: Some(CompletionItemKind::PROPERTY),
+                detail: Some(format!(
+                    "{} (google.protobuf.{})",
+                    builtin_value_label(option.value),
+                    target.options_message()
+                )),
+                documentation: Some(Documentation::String(option.doc.to_string())),
+                sort_text: Some(format!("0{}", option.name)),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range,
+                    new_text: option.name.to_string(),
+                })),
+                ..Default::default()
+            });
+        }
+
+        for (field, package) in self.custom_options(target) {
+            let name = self.custom_option_name(field, package);
+            items.push(CompletionItem {
+                label: name.clone(),
+                kind: Some(CompletionItemKind::PROPERTY),
+                detail: Some(format!("{} (extends google.protobuf.{})", field.field_type, target.options_message())),
+                sort_text: Some(format!("1{}", name)),
+                filter_text: Some(name.clone()),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit { range, new_text: name })),
+                ..Default::default()
+            });
+        }
+
+        items
+    }
+
+    /// Find an option by the name written in the source: a built-in name, or a
+    /// parenthesized extension optionally followed by a sub-field path
+    fn resolve(&self, target: OptionTarget, name: &str) -> Option<OptionType<'a>> {
+        let Some(rest) = name.strip_prefix('(') else {
+            return BUILTIN_OPTIONS
+                .iter()
+                .find(|o| o.name == name && o.targets.contains(&target))
+                .map(|o| OptionType::Builtin(o.value));
+        };
+        let (extension, path) = rest.split_once(')')?;
+        let extension = extension.trim().trim_start_matches('.');
+        let (mut field, package) = self
+            .custom_options(target)
+            .into_iter()
+            .find(|(field, package)| {
+                extension == field.name
+                    || (!package.is_empty() && extension == format!("{}.{}", package, field.name))
+            })?;
+        let mut scope = package.to_string();
+
+        for part in path.split('.').filter(|p| !p.is_empty()) {
+            let message = self.option_message(&OptionType::Field { field, scope })?;
+            field = message.fields.iter().find(|f| f.name == part)?;
+            scope = message.full_name.clone();
+        }
+        Some(OptionType::Field { field, scope })
+    }
+
+    /// Message type of a message-typed option
+    fn option_message(&self, option: &OptionType<'a>) -> Option<&'a MessageElement> {
+        let OptionType::Field { field, scope } = option else {
+            return None;
+        };
+        let def = self.table.resolve(field.type_name.as_deref()?, scope)?;
+        if def.kind != TypeKind::Message {
+            return None;
+        }
+        let file = self.files.iter().find(|f| f.uri == def.uri)?;
+        file.find_message_by_name(&def.full_name)
+    }
+
+    /// Message type of a message-typed field declared in `scope`
+    fn field_message(&self, field: &'a FieldElement, scope: &str) -> Option<&'a MessageElement> {
+        self.option_message(&OptionType::Field { field, scope: scope.to_string() })
+    }
+
+    /// Check the fields of a text format message value against `message`
+    fn check_fields(&self, fields: &[TextField], message: &'a MessageElement, diagnostics: &mut Vec<Diagnostic>) {
+        for text_field in fields.iter().filter(|f| !f.extension) {
+            let Some(field) = message.fields.iter().find(|f| f.name == text_field.name.text) else {
+                diagnostics.push(option_value_diagnostic(
+                    &text_field.name,
+                    "unknown-option-field",
+                    format!("Unknown field '{}' in {}", text_field.name.text, message.full_name),
+                ));
+                continue;
+            };
+            // Map entries are not checked
+            if field.field_type.starts_with("map<") {
+                continue;
+            }
+            if text_field.list && !matches!(field.label, Some(FieldLabelProto::Repeated)) {
+                diagnostics.push(option_value_diagnostic(
+                    &text_field.name,
+                    "option-value-type-mismatch",
+                    format!("Field '{}' is not repeated and cannot take a list", field.name),
+                ));
+            }
+            for value in &text_field.values {
+                self.check_value(field, &message.full_name, value, diagnostics);
+            }
+        }
+    }
+
+    /// Check a value of `field`, declared in the message `scope`
+    fn check_value(&self, field: &'a FieldElement, scope: &str, value: &TextValue, diagnostics: &mut Vec<Diagnostic>) {
+        let expected = match field.type_name.as_deref() {
+            None => field.field_type.clone(),
+            Some(type_name) => {
+                let Some(def) = self.table.resolve(type_name, scope) else {
+                    return;
+                };
+                let file = self.files.iter().find(|f| f.uri == def.uri);
+                match (def.kind, value) {
+                    (TypeKind::Message, TextValue::Message { fields, .. }) => {
+                        if let Some(message) = file.and_then(|f| f.find_message_by_name(&def.full_name)) {
+                            self.check_fields(fields, message, diagnostics);
+                        }
+                        return;
+                    }
+                    (TypeKind::Message, _) => format!("message {}", def.full_name),
+                    (TypeKind::Enum, TextValue::Scalar { token, .. }) => {
+                        let Some(enumeration) = file.and_then(|f| f.find_enum_by_name(&def.full_name)) else {
+                            return;
+                        };
+                        if token.kind == TokenKind::Identifier
+                            && !enumeration.values.iter().any(|v| v.name == token.text)
+                        {
+                            diagnostics.push(option_value_diagnostic(
+                                token,
+                                "option-value-type-mismatch",
+                                format!("'{}' is not a value of enum {}", token.text, def.full_name),
+                            ));
+                            return;
+                        }
+                        if token.kind == TokenKind::Identifier || is_integer(token) {
+                            return;
+                        }
+                        format!("enum {}", def.full_name)
+                    }
+                    (TypeKind::Enum, _) => format!("enum {}", def.full_name),
+                }
+            }
+        };
+
+        let (token, found) = match value {
+            TextValue::Message { open, .. } => (open, "a message".to_string()),
+            TextValue::Scalar { token, negative } => {
+                if field.type_name.is_none() && scalar_matches(&field.field_type, token, *negative) {
+                    return;
+                }
+                let found = match token.kind {
+                    TokenKind::String => "a string".to_string(),
+                    TokenKind::Number if *negative => "a negative number".to_string(),
+                    TokenKind::Number => "a number".to_string(),
+                    _ => format!("'{}'", token.text),
+                };
+                (token, found)
+            }
+        };
+        diagnostics.push(option_value_diagnostic(
+            token,
+            "option-value-type-mismatch",
+            format!("Field '{}' expects {}, found {}", field.name, expected, found),
+        ));
+    }
+
+    /// Values for a field: `true`/`false` for bools, the values of an enum
+    fn field_value_items(&self, field: &FieldElement, scope: &str) -> Vec<CompletionItem> {
+        let Some(type_name) = field.type_name.as_deref() else {
+            return if field.field_type == "bool" {
+                builtin_value_items(BuiltinValue::Bool)
+            } else {
+                Vec::new()
+            };
+        };
+        let Some(def) = self.table.resolve(type_name, scope) else {
+            return Vec::new();
+        };
+        if def.kind != TypeKind::Enum {
+            return Vec::new();
+        }
+        let Some(enumeration) = self
+            .files
+            .iter()
+            .find(|f| f.uri == def.uri)
+            .and_then(|f| f.find_enum_by_name(&def.full_name))
+        else {
+            return Vec::new();
+        };
+        enumeration
+            .values
+            .iter()
+            .map(|value| CompletionItem {
+                label: value.name.clone(),
+                kind: Some(CompletionItemKind::ENUM_MEMBER),
+                detail: Some(format!("{} = {}", def.full_name, value.number)),
+                sort_text: Some(format!("0{:010}", value.number)),
+                ..Default::default()
+            })
+            .collect()
+    }
+}
+
+fn builtin_value_label(value: BuiltinValue) -> &'static str {
+    match value {
+        BuiltinValue::Bool => "bool",
+        BuiltinValue::String => "string",
+        BuiltinValue::Enum(_) => "enum",
+        BuiltinValue::FieldDefault => "field type",
+    }
+}
+
+fn builtin_value_items(value: BuiltinValue) -> Vec<CompletionItem> {
+    let (values, kind): (&[&str], _) = match value {
+        BuiltinValue::Bool => (&["true", "false"], CompletionItemKind::KEYWORD),
+        BuiltinValue::Enum(values) => (values, CompletionItemKind::ENUM_MEMBER),
+        BuiltinValue::String | BuiltinValue::FieldDefault => return Vec::new(),
+    };
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| CompletionItem {
+            label: value.to_string(),
+            kind: Some(kind),
+            sort_text: Some(format!("0{:02}", i)),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Whether a text format scalar is a valid value for a field of scalar type
+/// `field_type`
+fn scalar_matches(field_type: &str, token: &Token, negative: bool) -> bool {
+    match field_type {
+        "string" | "bytes" => token.kind == TokenKind::String,
+        "bool" => match token.kind {
+            TokenKind::Identifier => matches!(token.text, "true" | "false" | "True" | "False" | "t" | "f"),
+            TokenKind::Number => !negative && matches!(token.text, "0" | "1"),
+            _ => false,
+        },
+        "float" | "double" => {
+            token.kind == TokenKind::Number
+                || (token.kind == TokenKind::Identifier
+                    && ["inf", "infinity", "nan"].contains(&token.text.to_ascii_lowercase().as_str()))
+        }
+        "uint32" | "uint64" | "fixed32" | "fixed64" => !negative && is_integer(token),
+        _ => is_integer(token),
+    }
+}
+
+fn is_integer(token: &Token) -> bool {
+    token.kind == TokenKind::Number
+        && (token.text.starts_with("0x") || token.text.starts_with("0X") || !token.text.contains(['.', 'e', 'E']))
+}
+
+fn option_value_diagnostic(token: &Token, code: &str, message: String) -> Diagnostic {
+    Diagnostic {
+        range: Range {
+            start: Position {
+                line: token.line,
+                character: token.character,
+            },
+            end: Position {
+                line: token.line,
+                character: token.end_character(),
+            },
+        },
+        severity: Some(DiagnosticSeverity::ERROR),
+        code: Some(NumberOrString::String(code.to_string())),
+        source: Some("protobuf-lsp".to_string()),
+        message,
+        ..Default::default()
+    }
+}
+
+/// Field names of a message-typed option's value, inserted as `name: `
+fn aggregate_field_items(message: &MessageElement) -> Vec<CompletionItem> {
+    message
+        .fields
+        .iter()
+        .map(|field| CompletionItem {
+            label: field.name.clone(),
+            kind: Some(CompletionItemKind::FIELD),
+            detail: Some(format!("{} = {}", field.field_type, field.number)),
+            insert_text: Some(format!("{}: ", field.name)),
+            sort_text: Some(format!("0{:010}", field.number)),
+            ..Default::default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(content: &str, line: u32, character: u32) -> Option<OptionContext> {
+        option_context(content, Position { line, character })
+    }
+
+    /// Context at the `|` marker
+    fn context_at(marked: &str) -> Option<OptionContext> {
+        let offset = marked.find('|').unwrap();
+        let line = marked[..offset].matches('\n').count() as u32;
+        let character = (offset - marked[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0)) as u32;
+        context(&marked.replace('|', ""), line, character)
+    }
+
+    #[test]
+    fn test_option_context() {
+        assert_eq!(
+            context_at("syntax = \"proto3\";\noption java_|\n"),
+            Some(OptionContext::Name { target: T::File, start: 7 })
+        );
+        assert_eq!(
+            context_at("message User {\n  option deprecated = |\n}\n"),
+            Some(OptionContext::Value { target: T::Message, name: "deprecated".to_string() })
+        );
+        assert_eq!(
+            context_at("message User {\n  string name = 1 [json_name = \"n\", (ext|\n}\n"),
+            Some(OptionContext::Name { target: T::Field, start: 36 })
+        );
+        assert_eq!(
+            context_at("message User {\n  oneof kind {\n    option |\n  }\n}\n"),
+            Some(OptionContext::Name { target: T::Oneof, start: 11 })
+        );
+        assert_eq!(
+            context_at("enum Color {\n  RED = 0 [deprecated = |\n}\n"),
+            Some(OptionContext::Value { target: T::EnumValue, name: "deprecated".to_string() })
+        );
+        assert_eq!(
+            context_at("service S {\n  rpc Get(A) returns (B) {\n    option idem|\n  }\n}\n"),
+            Some(OptionContext::Name { target: T::Method, start: 11 })
+        );
+        // Not an option
+        assert_eq!(context_at("message User {\n  string a = |2;\n}\n"), None);
+        assert_eq!(context_at("message User {\n  string a = 2 [deprecated = true]; |\n}\n"), None);
+    }
+
+    #[test]
+    fn test_aggregate_context() {
+        let content = "message A {\n  option (my.config) = {\n    retries: 3 mode: \n    backoff {\n      policy: [{ \n";
+        let aggregate = |path: &[&str], field: Option<&str>| {
+            Some(OptionContext::Aggregate {
+                target: T::Message,
+                name: "(my.config)".to_string(),
+                path: path.iter().map(|p| p.to_string()).collect(),
+                field: field.map(str::to_string),
+            })
+        };
+        assert_eq!(context(content, 2, 15), aggregate(&[], None));
+        assert_eq!(context(content, 2, 21), aggregate(&[], Some("mode")));
+        assert_eq!(context(content, 3, 13), aggregate(&["backoff"], None));
+        assert_eq!(context(content, 4, 17), aggregate(&["backoff", "policy"], None));
+    }
+
+    #[tokio::test]
+    async fn test_custom_option_completion() {
+        let dir = tempfile::tempdir().unwrap();
+        let options_path = dir.path().join("options.proto");
+        let options = r#"syntax = "proto3";
+package my;
+import "google/protobuf/descriptor.proto";
+
+enum Mode {
+  MODE_UNSPECIFIED = 0;
+  MODE_FAST = 1;
+}
+
+message Backoff {
+  int32 base_ms = 1;
+  Mode mode = 2;
+}
+
+message Config {
+  int32 retries = 1;
+  Mode mode = 2;
+  bool enabled = 3;
+  Backoff backoff = 4;
+}
+
+extend google.protobuf.FieldOptions {
+  bool sensitive = 50000;
+  Mode field_mode = 50001;
+}
+
+extend google.protobuf.MessageOptions {
+  Config config = 50002;
+}
+"#;
+        std::fs::write(&options_path, options).unwrap();
+        let user_path = dir.path().join("user.proto");
+        let user = "syntax = \"proto3\";\npackage app;\nimport \"options.proto\";\n\nmessage User {\n  option (my.config) = { mode: };\n  string name = 1 [(my.field_mode) = ];\n  string other = 2 [(\n}\n\nmessage Job {\n  option (my.config) = { backoff { mode: } };\n}\n";
+        std::fs::write(&user_path, user).unwrap();
+
+        let workspace = WorkspaceManager::new();
+        let options_uri = Url::from_file_path(&options_path).unwrap();
+        let user_uri = Url::from_file_path(&user_path).unwrap();
+        workspace.open_file(&options_uri, options).await.unwrap();
+        // The in-progress text does not parse; completion works off the last good parse
+        let valid = "syntax = \"proto3\";\npackage app;\nimport \"options.proto\";\n\nmessage User {}\n";
+        workspace.open_file(&user_uri, valid).await.unwrap();
+        workspace.open_file(&user_uri, user).await.unwrap();
+
+        let labels = |items: Vec<CompletionItem>| items.into_iter().map(|i| i.label).collect::<Vec<_>>();
+        let complete = |line, character| {
+            provide_option_completions(&user_uri, &workspace, user, Position { line, character })
+        };
+
+        // Custom field options are offered with the built-ins
+        let names = labels(complete(7, 21).await.unwrap());
+        assert!(names.contains(&"(my.sensitive)".to_string()));
+        assert!(names.contains(&"(my.field_mode)".to_string()));
+        assert!(names.contains(&"json_name".to_string()));
+        assert!(!names.contains(&"(my.config)".to_string()));
+
+        // Enum-typed custom option values
+        assert_eq!(labels(complete(6, 37).await.unwrap()), vec!["MODE_UNSPECIFIED", "MODE_FAST"]);
+
+        // Aggregate value: field names, then the values of an enum field
+        assert_eq!(labels(complete(5, 25).await.unwrap()), vec!["retries", "mode", "enabled", "backoff"]);
+        assert_eq!(labels(complete(5, 31).await.unwrap()), vec!["MODE_UNSPECIFIED", "MODE_FAST"]);
+
+        // Nested message values complete the fields of the nested type
+        assert_eq!(labels(complete(11, 35).await.unwrap()), vec!["base_ms", "mode"]);
+        assert_eq!(labels(complete(11, 41).await.unwrap()), vec!["MODE_UNSPECIFIED", "MODE_FAST"]);
+    }
+
+    #[tokio::test]
+    async fn test_option_value_diagnostics() {
+        let dir = tempfile::tempdir().unwrap();
+        let options_path = dir.path().join("options.proto");
+        let options = r#"syntax = "proto3";
+package my;
+import "google/protobuf/descriptor.proto";
+
+enum Mode {
+  MODE_UNSPECIFIED = 0;
+  MODE_FAST = 1;
+}
+
+message Backoff {
+  uint32 base_ms = 1;
+  double factor = 2;
+}
+
+message Config {
+  int32 retries = 1;
+  Mode mode = 2;
+  Backoff backoff = 3;
+  repeated string tags = 4;
+  string name = 5;
+}
+
+extend google.protobuf.MessageOptions {
+  Config config = 50000;
+}
+"#;
+        std::fs::write(&options_path, options).unwrap();
+        let user_path = dir.path().join("user.proto");
+        let user = r#"syntax = "proto3";
+import "options.proto";
+
+message Good {
+  option (my.config) = {
+    retries: 3
+    mode: MODE_FAST
+    backoff { base_ms: 100 factor: 1.5 }
+    tags: ["a", "b"]
+  };
+}
+
+message Bad {
+  option (my.config) = {
+    retries: "three"
+    mode: MODE_SLOW
+    backoff { base_ms: -1 jitter: 2 }
+    name: ["x"]
+    /* café 注释 */ timeout: 5
+  };
+}
+"#;
+        std::fs::write(&user_path, user).unwrap();
+
+        let workspace = WorkspaceManager::new();
+        let options_uri = Url::from_file_path(&options_path).unwrap();
+        let user_uri = Url::from_file_path(&user_path).unwrap();
+        workspace.open_file(&options_uri, options).await.unwrap();
+        workspace.open_file(&user_uri, user).await.unwrap();
+
+        let diagnostics = validate_option_values(&user_uri, &workspace, user).await;
+        let found: Vec<(u32, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.range.start.line, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (14, "Field 'retries' expects int32, found a string"),
+                (15, "'MODE_SLOW' is not a value of enum my.Mode"),
+                (16, "Field 'base_ms' expects uint32, found a negative number"),
+                (16, "Unknown field 'jitter' in my.Backoff"),
+                (17, "Field 'name' is not repeated and cannot take a list"),
+                (18, "Unknown field 'timeout' in my.Config"),
+            ]
+        );
+        assert_eq!(diagnostics[3].code, Some(NumberOrString::String("unknown-option-field".to_string())));
+        assert_eq!(
+            (diagnostics[3].range.start.character, diagnostics[3].range.end.character),
+            (26, 32)
+        );
+        // Columns after non-ASCII comment text count UTF-16 code units
+        assert_eq!(
+            (diagnostics[5].range.start.character, diagnostics[5].range.end.character),
+            (18, 25)
+        );
+    }
+}
@@ -1,17 +1,66 @@
-use crate::workspace::WorkspaceManager;
+use crate::parser::proto::{EnumElement, MessageElement, TypeReference};
+use crate::parser::ParsedProto;
+use crate::workspace::{TypeDefinition, TypeKind, TypeTable, WorkspaceManager};
+use std::sync::Arc;
 use tower_lsp::lsp_types::{Location, Position, Range, ReferenceParams, Url};
 
+/// The symbol a reference search is about
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReferenceTarget {
+    /// A message or enum, referenced from fields, RPCs and `extend` blocks
+    Type {
+        full_name: String,
+        kind: TypeKind,
+        /// File declaring the type
+        uri: String,
+        /// The name in the declaration
+        declaration: Location,
+    },
+    /// A field, enum value, service or method. Nothing refers to these by a
+    /// type name, so the declaration is the only occurrence.
+    Declaration {
+        name: String,
+        /// Fully-qualified scope the name is declared in: the message of a
+        /// field, the parent of an enum (enum values are its siblings), the
+        /// service of a method, or the package
+        scope: String,
+        declaration: Location,
+    },
+}
+
+impl ReferenceTarget {
+    pub fn declaration(&self) -> &Location {
+        match self {
+            ReferenceTarget::Type { declaration, .. } | ReferenceTarget::Declaration { declaration, .. } => {
+                declaration
+            }
+        }
+    }
+
+    /// The simple name of the symbol
+    pub fn name(&self) -> &str {
+        match self {
+            ReferenceTarget::Type { full_name, .. } => full_name.rsplit('.').next().unwrap_or(full_name),
+            ReferenceTarget::Declaration { name, .. } => name,
+        }
+    }
+
+    /// Fully-qualified scope the symbol is declared in (empty at the root)
+    pub fn scope(&self) -> &str {
+        match self {
+            ReferenceTarget::Type { full_name, .. } => full_name.rsplit_once('.').map(|(scope, _)| scope).unwrap_or(""),
+            ReferenceTarget::Declaration { scope, .. } => scope,
+        }
+    }
+}
+
 /// Find all references to the symbol at the given cursor position.
 ///
-/// The algorithm mirrors the Go implementation in
-/// `protobuf-language-server/components/references.go`:
-///
-/// 1. Extract the symbol name (without package prefix) under the cursor.
-/// 2. Optionally include the declaration site itself.
-/// 3. Search the current file for whole-word occurrences (skipping comments,
-///    imports and blank lines).
-/// 4. Recursively search all imported files, using a visited-set to avoid
-///    cycles.
+/// The symbol is resolved to its fully-qualified definition, either from its
+/// declaration or from a type reference under the cursor. Every indexed file is
+/// then searched for type references that resolve (with protoc scoping, against
+/// that file and its imports) to the same definition, whether written short,
+/// package-qualified or with a leading dot.
 pub async fn find_references(
     params: ReferenceParams,
     workspace: &WorkspaceManager,
@@ -19,239 +68,263 @@ pub async fn find_references(
 ) -> Option<Vec<Location>> {
     let uri = params.text_document_position.text_document.uri.clone();
     let position = params.text_document_position.position;
-
-    let proto = workspace.get_file(&uri)?;
     let content = content?;
 
-    // Extract the word at cursor (without dots – we want the simple symbol name)
-    let line_str = content.lines().nth(position.line as usize).unwrap_or("");
-    let symbol_name = get_word(line_str, position.character as usize, false);
-    if symbol_name.is_empty() {
-        return Some(Vec::new());
-    }
-
-    tracing::debug!("FindReferences: looking for symbol '{}'", symbol_name);
-
-    let mut results: Vec<Location> = Vec::new();
+    let target = reference_target(&uri, workspace, content, position).await?;
+    tracing::debug!("FindReferences: looking for {:?}", target);
 
-    // Track definition location so we can skip it in file search to avoid duplicates
-    let mut def_uri: Option<Url> = None;
-    let mut def_line: Option<u32> = None;
-
-    // If includeDeclaration, find the definition and add it
+    let mut results = Vec::new();
     if params.context.include_declaration {
-        // Quick search: look in current file for message/enum/service with this name
-        if let Some(msg) = proto.find_message_by_name(&symbol_name) {
-            def_uri = Some(uri.clone());
-            def_line = Some(msg.line);
-            results.push(make_location(&uri, msg.line, msg.character, symbol_name.len()));
-        } else if let Some(e) = proto.find_enum_by_name(&symbol_name) {
-            def_uri = Some(uri.clone());
-            def_line = Some(e.line);
-            results.push(make_location(&uri, e.line, e.character, symbol_name.len()));
-        } else if let Some(svc) = proto.find_service_by_name(&symbol_name) {
-            def_uri = Some(uri.clone());
-            def_line = Some(svc.line);
-            results.push(make_location(&uri, svc.line, svc.character, symbol_name.len()));
-        }
+        results.push(target.declaration().clone());
+    }
+    if let ReferenceTarget::Type { full_name, .. } = &target {
+        results.extend(find_type_references(full_name, workspace).await);
     }
-
-    // Search current file
-    search_file_for_references(
-        content,
-        &uri,
-        &symbol_name,
-        def_uri.as_ref(),
-        def_line,
-        &mut results,
-    );
-
-    // Recursively search imported files
-    let mut searched: std::collections::HashSet<String> = std::collections::HashSet::new();
-    searched.insert(uri.to_string());
-    search_imported_files(
-        workspace,
-        &proto,
-        &uri,
-        &symbol_name,
-        &mut searched,
-        &mut results,
-        def_uri.as_ref(),
-        def_line,
-    )
-    .await;
 
     tracing::debug!("FindReferences: found {} references", results.len());
     Some(results)
 }
 
-/// Recursively search imported files for references.
-async fn search_imported_files(
+/// Resolve the symbol at `position` in `uri`: a message or enum declaration,
+/// one component of a type reference, or the declaration of a field, enum
+/// value, service or method. `content` is the live text of `uri`.
+pub async fn reference_target(
+    uri: &Url,
     workspace: &WorkspaceManager,
-    proto: &crate::parser::ParsedProto,
-    current_uri: &Url,
-    symbol_name: &str,
-    searched: &mut std::collections::HashSet<String>,
-    results: &mut Vec<Location>,
-    def_uri: Option<&Url>,
-    def_line: Option<u32>,
-) {
-    for imp in &proto.imports {
-        // Try to resolve and load the imported file
-        let imported = match workspace.get_imported_file(current_uri, &imp.path).await {
-            Some(f) => f,
-            None => {
-                if let Some(f) = workspace.get_imported_file_cached(current_uri, &imp.path) {
-                    f
-                } else {
-                    continue;
-                }
-            }
-        };
+    content: &str,
+    position: Position,
+) -> Option<ReferenceTarget> {
+    let proto = workspace.get_file(uri)?;
 
-        let import_uri_str = imported.uri.clone();
-        if searched.contains(&import_uri_str) {
-            continue;
-        }
-        searched.insert(import_uri_str.clone());
+    if let Some(full_name) = type_declaration_at(&proto, position) {
+        let table = TypeTable::from_files([proto.as_ref()]);
+        return type_target(table.resolve(&format!(".{}", full_name), "")?);
+    }
 
-        let import_url = match Url::parse(&import_uri_str) {
-            Ok(u) => u,
-            Err(_) => continue,
-        };
+    if let Some(type_ref) = proto.type_refs.iter().find(|r| {
+        r.line == position.line
+            && (r.character..=r.character + r.name.len() as u32).contains(&position.character)
+    }) {
+        let mut files = vec![proto.clone()];
+        files.extend(workspace.collect_all_imports_async(uri).await);
+        let table = TypeTable::from_files(files.iter().map(|f| f.as_ref()));
+        let offset = (position.character - type_ref.character) as usize;
+        let (full_name, _) = referenced_components(&table, type_ref)
+            .into_iter()
+            .find(|(_, (start, end))| (*start..=*end).contains(&offset))?;
+        return type_target(table.resolve(&format!(".{}", full_name), "")?);
+    }
 
-        // Read imported file content via filesystem
-        let file_content = match read_file_from_uri(&import_url) {
-            Some(c) => c,
-            None => continue,
-        };
+    member_declaration_at(uri, &proto, content, position)
+}
 
-        search_file_for_references(
-            &file_content,
-            &import_url,
-            symbol_name,
-            def_uri,
-            def_line,
-            results,
-        );
+fn type_target(def: &TypeDefinition) -> Option<ReferenceTarget> {
+    Some(ReferenceTarget::Type {
+        full_name: def.full_name.clone(),
+        kind: def.kind,
+        uri: def.uri.clone(),
+        declaration: make_location(
+            &Url::parse(&def.uri).ok()?,
+            def.line,
+            def.character,
+            def.name.len(),
+        ),
+    })
+}
 
-        // Recurse into this file's imports
-        Box::pin(search_imported_files(
-            workspace,
-            &imported,
-            &import_url,
-            symbol_name,
-            searched,
-            results,
-            def_uri,
-            def_line,
-        ))
-        .await;
-    }
+/// References to the type `full_name` across the indexed workspace. Each
+/// location covers the component of the written name that denotes the type,
+/// e.g. `Outer` in `pkg.Outer.Inner` when searching for `pkg.Outer`.
+pub async fn find_type_references(full_name: &str, workspace: &WorkspaceManager) -> Vec<Location> {
+    let mut results = Vec::new();
+    for_each_type_reference(full_name, workspace, |location, _, _, _| results.push(location)).await;
+    results
 }
 
-/// Search a single file's content for all whole-word occurrences of `symbol_name`.
-fn search_file_for_references(
-    content: &str,
-    file_uri: &Url,
-    symbol_name: &str,
-    def_uri: Option<&Url>,
-    def_line: Option<u32>,
-    results: &mut Vec<Location>,
+/// Calls `visit` for every reference to the type `full_name` in the indexed
+/// workspace, with its location, the table of types visible from the file, the
+/// reference and the byte range of the component within the written name
+pub async fn for_each_type_reference(
+    full_name: &str,
+    workspace: &WorkspaceManager,
+    mut visit: impl FnMut(Location, &TypeTable, &TypeReference, (usize, usize)),
 ) {
-    for (line_num, line) in content.lines().enumerate() {
-        let line_num = line_num as u32;
+    let mut all_files = workspace.get_all_files();
+    all_files.sort_by(|a, b| a.0.cmp(&b.0));
 
-        // Skip definition line to avoid duplicates
-        if let (Some(du), Some(dl)) = (def_uri, def_line) {
-            if du == file_uri && dl == line_num {
-                continue;
-            }
-        }
+    for (file_uri, proto) in all_files {
+        let Ok(file_url) = Url::parse(&file_uri) else { continue };
+        visit_file_references(full_name, &file_url, &proto, workspace, &mut visit).await;
+    }
+}
 
-        let trimmed = line.trim();
+/// Calls `visit` for every resolved type reference in the indexed workspace,
+/// once per component: `pkg.Outer.Inner` is a reference to `pkg.Outer` and to
+/// `pkg.Outer.Inner`. Gets the fully-qualified name referred to, the location
+/// of the component, and the file and reference it is written in.
+///
+/// As in find references, each file's references resolve against the file and
+/// its imports, using the table `WorkspaceManager::visible_types` caches per
+/// file.
+pub async fn for_each_reference(
+    workspace: &WorkspaceManager,
+    mut visit: impl FnMut(&str, Location, &ParsedProto, &TypeReference),
+) {
+    let mut all_files = workspace.get_all_files();
+    all_files.sort_by(|a, b| a.0.cmp(&b.0));
 
-        // Skip empty, import, and comment lines
-        if trimmed.is_empty()
-            || trimmed.starts_with("import")
-            || trimmed.starts_with("//")
-            || trimmed.starts_with("/*")
-        {
+    for (file_uri, proto) in &all_files {
+        if proto.type_refs.is_empty() {
             continue;
         }
-
-        // Find all occurrences
-        let mut idx = 0usize;
-        while let Some(found) = line[idx..].find(symbol_name) {
-            let abs_pos = idx + found;
-            if is_whole_word(line, abs_pos, symbol_name.len()) {
-                results.push(make_location(file_uri, line_num, abs_pos as u32, symbol_name.len()));
-            }
-            idx = abs_pos + 1;
-            if idx >= line.len() {
-                break;
+        let Ok(file_url) = Url::parse(file_uri) else { continue };
+        let Some(table) = workspace.visible_types(&file_url).await else { continue };
+        for type_ref in &proto.type_refs {
+            for (component, (start, end)) in referenced_components(&table, type_ref) {
+                let location = make_location(&file_url, type_ref.line, type_ref.character + start as u32, end - start);
+                visit(&component, location, proto, type_ref);
             }
         }
     }
 }
 
-// ---------------------------------------------------------------------------
-// Helper functions
-// ---------------------------------------------------------------------------
-
-/// Check whether the match at `start` for `length` bytes is a whole word (not
-/// part of a larger identifier).
-fn is_whole_word(line: &str, start: usize, length: usize) -> bool {
-    let bytes = line.as_bytes();
-    // Check character before
-    if start > 0 {
-        let prev = bytes[start - 1];
-        if is_identifier_char(prev) {
-            return false;
-        }
+/// References to the type `full_name` within the file `uri`
+pub async fn find_type_references_in(full_name: &str, uri: &Url, workspace: &WorkspaceManager) -> Vec<Location> {
+    let mut results = Vec::new();
+    if let Some(proto) = workspace.get_file(uri) {
+        visit_file_references(full_name, uri, &proto, workspace, &mut |location, _, _, _| results.push(location)).await;
+    }
+    results
+}
+
+async fn visit_file_references(
+    full_name: &str,
+    file_url: &Url,
+    proto: &Arc<ParsedProto>,
+    workspace: &WorkspaceManager,
+    visit: &mut impl FnMut(Location, &TypeTable, &TypeReference, (usize, usize)),
+) {
+    // Only files mentioning the name can refer to the type
+    let simple_name = full_name.rsplit('.').next().unwrap_or(full_name);
+    let mentions = proto
+        .type_refs
+        .iter()
+        .any(|r| r.name.split('.').any(|part| part == simple_name));
+    if !mentions {
+        return;
     }
-    // Check character after
-    let end = start + length;
-    if end < bytes.len() {
-        let next = bytes[end];
-        if is_identifier_char(next) {
-            return false;
+
+    let mut files = vec![proto.clone()];
+    files.extend(workspace.collect_all_imports_async(file_url).await);
+    let table = TypeTable::from_files(files.iter().map(|f| f.as_ref()));
+    for type_ref in &proto.type_refs {
+        for (component, (start, end)) in referenced_components(&table, type_ref) {
+            if component == full_name {
+                let location = make_location(file_url, type_ref.line, type_ref.character + start as u32, end - start);
+                visit(location, &table, type_ref, (start, end));
+            }
         }
     }
-    true
 }
 
-fn is_identifier_char(ch: u8) -> bool {
-    ch.is_ascii_alphanumeric() || ch == b'_'
+/// The fully-qualified name denoted by each prefix of a resolved type
+/// reference, with the byte range of its last component within the written
+/// name: `pkg.Outer.Inner` yields `pkg`, `pkg.Outer` and `pkg.Outer.Inner`.
+fn referenced_components(table: &TypeTable, type_ref: &TypeReference) -> Vec<(String, (usize, usize))> {
+    let Some(def) = table.resolve(&type_ref.name, &type_ref.scope) else {
+        return Vec::new();
+    };
+    let leading_dot = usize::from(type_ref.name.starts_with('.'));
+    let written = &type_ref.name[leading_dot..];
+    let Some(prefix) = def.full_name.strip_suffix(written) else {
+        return Vec::new();
+    };
+
+    let mut components = Vec::new();
+    let mut start = 0;
+    for (i, part) in written.split('.').enumerate() {
+        if i > 0 {
+            start += 1;
+        }
+        let end = start + part.len();
+        components.push((
+            format!("{}{}", prefix, &written[..end]),
+            (leading_dot + start, leading_dot + end),
+        ));
+        start = end;
+    }
+    components
 }
 
-/// Extract the word at `idx` in `line`, optionally including dots.
-fn get_word(line: &str, idx: usize, include_dot: bool) -> String {
-    if line.is_empty() {
-        return String::new();
+/// Fully-qualified name of the message or enum whose declared name is at `position`
+fn type_declaration_at(proto: &ParsedProto, position: Position) -> Option<String> {
+    fn on_name(line: u32, character: u32, name: &str, position: Position) -> bool {
+        line == position.line && (character..=character + name.len() as u32).contains(&position.character)
+    }
+    fn in_enums(enums: &[EnumElement], position: Position) -> Option<String> {
+        enums
+            .iter()
+            .find(|e| on_name(e.line, e.character, &e.name, position))
+            .map(|e| e.full_name.clone())
+    }
+    fn in_messages(messages: &[MessageElement], position: Position) -> Option<String> {
+        messages.iter().find_map(|msg| {
+            if on_name(msg.line, msg.character, &msg.name, position) {
+                return Some(msg.full_name.clone());
+            }
+            in_enums(&msg.nested_enums, position).or_else(|| in_messages(&msg.nested_messages, position))
+        })
     }
-    let bytes = line.as_bytes();
-    let idx = idx.min(bytes.len().saturating_sub(1));
+    in_messages(&proto.messages, position).or_else(|| in_enums(&proto.enums, position))
+}
 
-    let is_word_char = |ch: u8| -> bool {
-        ch.is_ascii_alphanumeric() || ch == b'_' || (include_dot && ch == b'.')
-    };
+/// Declaration of a field, enum value, service or method named by the word at
+/// `position`, if one is declared on that line
+fn member_declaration_at(uri: &Url, proto: &ParsedProto, content: &str, position: Position) -> Option<ReferenceTarget> {
+    /// (name, line, scope) of each declaration
+    fn fields_and_values<'a>(messages: &'a [MessageElement], out: &mut Vec<(&'a str, u32, &'a str)>) {
+        for msg in messages {
+            out.extend(msg.fields.iter().map(|f| (f.name.as_str(), f.line, msg.full_name.as_str())));
+            for e in &msg.nested_enums {
+                out.extend(e.values.iter().map(|v| (v.name.as_str(), v.line, msg.full_name.as_str())));
+            }
+            fields_and_values(&msg.nested_messages, out);
+        }
+    }
 
-    let mut l = idx;
-    while l > 0 && is_word_char(bytes[l]) {
-        l -= 1;
+    let package = proto.package.as_deref().unwrap_or("");
+    let mut declared = Vec::new();
+    fields_and_values(&proto.messages, &mut declared);
+    for e in &proto.enums {
+        declared.extend(e.values.iter().map(|v| (v.name.as_str(), v.line, package)));
     }
-    if l != idx && !is_word_char(bytes[l]) {
-        l += 1;
+    for ext in &proto.extends {
+        declared.extend(ext.fields.iter().map(|f| (f.name.as_str(), f.line, package)));
     }
-    // Edge: if l == 0 and is word char, keep it
-
-    let mut r = idx;
-    while r < bytes.len() && is_word_char(bytes[r]) {
-        r += 1;
+    for svc in &proto.services {
+        declared.push((svc.name.as_str(), svc.line, package));
+        declared.extend(svc.methods.iter().map(|m| (m.name.as_str(), m.line, svc.full_name.as_str())));
     }
 
-    line[l..r].to_string()
+    let line = content.lines().nth(position.line as usize)?;
+    let (start, word) = word_at(line, position.character as usize)?;
+    let (_, _, scope) = declared
+        .iter()
+        .find(|(name, line, _)| *line == position.line && *name == word)?;
+    Some(ReferenceTarget::Declaration {
+        name: word.to_string(),
+        scope: scope.to_string(),
+        declaration: make_location(uri, position.line, start as u32, word.len()),
+    })
+}
+
+/// The identifier touching byte offset `idx` of `line`, with its start offset
+pub fn word_at(line: &str, idx: usize) -> Option<(usize, &str)> {
+    let is_word_char = |ch: char| ch.is_ascii_alphanumeric() || ch == '_';
+    let idx = idx.min(line.len());
+    let start = line[..idx].rfind(|c| !is_word_char(c)).map(|i| i + 1).unwrap_or(0);
+    let end = line[idx..].find(|c| !is_word_char(c)).map(|i| idx + i).unwrap_or(line.len());
+    (start < end).then(|| (start, &line[start..end]))
 }
 
 fn make_location(uri: &Url, line: u32, character: u32, name_len: usize) -> Location {
@@ -267,30 +340,114 @@ fn make_location(uri: &Url, line: u32, character: u32, name_len: usize) -> Locat
     }
 }
 
-/// Read file content from a file:// URI.
-fn read_file_from_uri(uri: &Url) -> Option<String> {
-    let path = uri.to_file_path().ok()?;
-    std::fs::read_to_string(path).ok()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tower_lsp::lsp_types::{ReferenceContext, TextDocumentIdentifier, TextDocumentPositionParams};
 
     #[test]
-    fn test_get_word() {
-        assert_eq!(get_word("string user_id = 1;", 7, false), "user_id");
-        assert_eq!(get_word("string user_id = 1;", 0, false), "string");
-        assert_eq!(get_word("pkg.Msg", 4, true), "pkg.Msg");
-        assert_eq!(get_word("pkg.Msg", 4, false), "Msg");
-        assert_eq!(get_word("", 0, false), "");
+    fn test_word_at() {
+        assert_eq!(word_at("string user_id = 1;", 7), Some((7, "user_id")));
+        assert_eq!(word_at("string user_id = 1;", 14), Some((7, "user_id")));
+        assert_eq!(word_at("string user_id = 1;", 0), Some((0, "string")));
+        assert_eq!(word_at("  = 1;", 2), None);
+        assert_eq!(word_at("", 0), None);
     }
 
-    #[test]
-    fn test_is_whole_word() {
-        assert!(is_whole_word("User user = 1;", 0, 4)); // "User"
-        assert!(is_whole_word("User user = 1;", 5, 4)); // "user"
-        assert!(!is_whole_word("UserInfo user = 1;", 0, 4)); // "User" inside "UserInfo"
-        assert!(!is_whole_word("some_User = 1;", 5, 4)); // "User" inside "some_User"
+    #[tokio::test]
+    async fn test_semantic_references() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = r#"syntax = "proto3";
+package a;
+
+message User {}
+
+enum Status {
+  STATUS_UNSPECIFIED = 0;
+}
+
+message Outer {
+  enum Status {
+    OUTER_STATUS_UNSPECIFIED = 0;
+  }
+  Status nested = 1;
+  .a.Status top = 2;
+  User user = 3;
+}
+"#;
+        let b = r#"syntax = "proto3";
+package b;
+import "a.proto";
+
+message User {
+  a.User remote = 1 [json_name = "User"];
+  .a.User absolute = 2;
+  User local = 3;
+  a.Outer.Status nested = 4;
+}
+"#;
+        let a_path = dir.path().join("a.proto");
+        let b_path = dir.path().join("b.proto");
+        std::fs::write(&a_path, a).unwrap();
+        std::fs::write(&b_path, b).unwrap();
+        let a_uri = Url::from_file_path(&a_path).unwrap();
+        let b_uri = Url::from_file_path(&b_path).unwrap();
+
+        let workspace = WorkspaceManager::new();
+        workspace.open_file(&a_uri, a).await.unwrap();
+        workspace.open_file(&b_uri, b).await.unwrap();
+
+        let references = |uri: &Url, line, character, content: &'static str| {
+            let params = ReferenceParams {
+                text_document_position: TextDocumentPositionParams {
+                    text_document: TextDocumentIdentifier { uri: uri.clone() },
+                    position: Position { line, character },
+                },
+                context: ReferenceContext { include_declaration: true },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            };
+            let workspace = &workspace;
+            async move {
+                let mut found: Vec<(String, u32, u32, u32)> = find_references(params, workspace, Some(content))
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|l| {
+                        let file = l.uri.path().rsplit('/').next().unwrap().to_string();
+                        (file, l.range.start.line, l.range.start.character, l.range.end.character)
+                    })
+                    .collect();
+                found.sort();
+                found
+            }
+        };
+        let at = |file: &str, line, start, end| (file.to_string(), line, start, end);
+
+        // From the declaration of a.User: qualified and absolute uses, not b.User
+        // or the string option value
+        assert_eq!(
+            references(&a_uri, 3, 9, a).await,
+            vec![at("a.proto", 3, 8, 12), at("a.proto", 15, 2, 6), at("b.proto", 5, 4, 8), at("b.proto", 6, 5, 9)]
+        );
+
+        // From a use: b.User's own reference
+        assert_eq!(references(&b_uri, 7, 3, b).await, vec![at("b.proto", 4, 8, 12), at("b.proto", 7, 2, 6)]);
+
+        // The top-level Status is not the nested Outer.Status
+        assert_eq!(references(&a_uri, 5, 6, a).await, vec![at("a.proto", 5, 5, 11), at("a.proto", 14, 5, 11)]);
+        assert_eq!(
+            references(&a_uri, 13, 3, a).await,
+            vec![at("a.proto", 10, 7, 13), at("a.proto", 13, 2, 8), at("b.proto", 8, 10, 16)]
+        );
+
+        // A component of a qualified name refers to the enclosing message
+        assert_eq!(
+            references(&b_uri, 8, 5, b).await,
+            vec![at("a.proto", 9, 8, 13), at("b.proto", 8, 4, 9)]
+        );
+
+        // Fields only have their declaration
+        assert_eq!(references(&b_uri, 7, 8, b).await, vec![at("b.proto", 7, 7, 12)]);
     }
 }
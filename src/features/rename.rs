@@ -1,206 +1,1126 @@
-use crate::workspace::WorkspaceManager;
-use std::collections::HashMap;
+use crate::features::references::{for_each_type_reference, reference_target, word_at, ReferenceTarget};
+use crate::features::text_format::{aggregate_values, tokenize, TextField, TextValue, Token, TokenKind};
+use crate::parser::proto::{EnumElement, FieldElement, MessageElement};
+use crate::parser::ParsedProto;
+use crate::workspace::{TypeTable, WorkspaceManager};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use tower_lsp::lsp_types::*;
 
-/// Prepare rename: validate that the symbol at cursor is renamable and return its range.
-pub fn prepare_rename(
+/// Change annotation of the edit to the declaration
+const DECLARATION_ANNOTATION: &str = "rename-declaration";
+/// Change annotation of the edits to references
+const REFERENCES_ANNOTATION: &str = "rename-references";
+/// Change annotation of the edits to language package options
+const OPTIONS_ANNOTATION: &str = "rename-language-packages";
+
+/// A replacement made by a rename: the location, its new text and the id of
+/// its change annotation
+pub type RenameEdit = (Location, String, &'static str);
+
+/// What the client accepts in workspace edits, and the version of each of its
+/// open documents
+#[derive(Debug, Default)]
+pub struct EditSupport {
+    /// Versioned `documentChanges` with change annotations. Without them edits
+    /// are sent as plain `changes`, and edits the user would be asked to
+    /// confirm as optional are left out.
+    pub annotated: bool,
+    pub versions: HashMap<Url, i32>,
+}
+
+/// File options that spell the package in a language's own form
+const LANGUAGE_PACKAGE_OPTIONS: &[&str] =
+    &["go_package", "java_package", "csharp_namespace", "php_namespace", "ruby_package"];
+
+/// Prepare rename: check that the cursor is on a renamable symbol and return its range.
+pub async fn prepare_rename(
     params: TextDocumentPositionParams,
     workspace: &WorkspaceManager,
     content: Option<&str>,
 ) -> Option<PrepareRenameResponse> {
     let uri = &params.text_document.uri;
     let position = params.position;
-
-    let _proto = workspace.get_file(uri)?;
     let content = content?;
 
-    let line_str = content.lines().nth(position.line as usize)?;
-    let symbol = get_word(line_str, position.character as usize);
-    if symbol.is_empty() {
-        return None;
+    if let Some((range, _)) = package_statement(content).filter(|(range, _)| contains(range, position)) {
+        return Some(PrepareRenameResponse::Range(range));
     }
 
-    // Check that this symbol refers to a renamable entity (message, enum, service, field, method)
-    if !is_renamable_symbol(&symbol, workspace, uri) {
+    let target = reference_target(uri, workspace, content, position).await?;
+    if is_well_known(&target) {
         return None;
     }
 
-    let start_col = find_word_start(line_str, position.character as usize);
-    let range = Range {
+    let line = content.lines().nth(position.line as usize)?;
+    let (start, word) = word_at(line, position.character as usize)?;
+    Some(PrepareRenameResponse::Range(Range {
         start: Position {
             line: position.line,
-            character: start_col as u32,
+            character: start as u32,
         },
         end: Position {
             line: position.line,
-            character: start_col as u32 + symbol.len() as u32,
+            character: (start + word.len()) as u32,
         },
-    };
-
-    Some(PrepareRenameResponse::Range(range))
+    }))
 }
 
-/// Rename a symbol across the workspace, producing a WorkspaceEdit.
+/// Rename the symbol at the cursor across the workspace. On the name in the
+/// `package` statement, the package is renamed (see `rename_package`).
+///
+/// Messages and enums are renamed at their declaration and at every reference
+/// resolving to them; only the component naming the type is replaced, so
+/// `pkg.Old` and `Outer.Old` become `pkg.New` and `Outer.New`. Fields, enum
+/// values, services and methods are renamed at their declaration.
+///
+/// Returns an error message when `new_name` is not an identifier, when the
+/// scope already defines it, when a rewritten reference would resolve to a
+/// different type, or when a field or enum value is still spelled in option
+/// values (`[default = OLD]`, `option (x) = OLD;`, `{ mode: OLD }`). The edit
+/// is shaped by `support`: versioned `document_changes` with change annotations
+/// separating the declaration from its references, or plain `changes`.
 pub async fn rename(
     params: RenameParams,
     workspace: &WorkspaceManager,
     content: Option<&str>,
-) -> Option<WorkspaceEdit> {
+    support: &EditSupport,
+) -> Result<Option<WorkspaceEdit>, String> {
     let uri = params.text_document_position.text_document.uri.clone();
     let position = params.text_document_position.position;
     let new_name = params.new_name;
 
-    let _proto = workspace.get_file(&uri)?;
-    let content = content?;
+    let Some(content) = content else { return Ok(None) };
+    if let Some((_, package)) = package_statement(content).filter(|(range, _)| contains(range, position)) {
+        return rename_package(package, &new_name, workspace, support).await;
+    }
+    let Some(target) = reference_target(&uri, workspace, content, position).await else {
+        return Ok(None);
+    };
+    let old_name = target.name().to_string();
+    tracing::debug!("Rename: '{}' -> '{}'", old_name, new_name);
 
-    let line_str = content.lines().nth(position.line as usize)?;
-    let old_name = get_word(line_str, position.character as usize);
-    if old_name.is_empty() {
-        return None;
+    if !is_identifier(&new_name) {
+        return Err(format!("'{}' is not a valid identifier", new_name));
+    }
+    if is_well_known(&target) {
+        return Err(format!("'{}' is a well-known type and cannot be renamed", old_name));
+    }
+    if new_name == old_name {
+        return Ok(None);
     }
 
-    tracing::debug!("Rename: '{}' -> '{}'", old_name, new_name);
+    let scope = target.scope().to_string();
+    let package = workspace
+        .get_file(&target.declaration().uri)
+        .and_then(|proto| proto.package.clone())
+        .unwrap_or_default();
+    if declared_symbols(workspace, &package).contains(&join(&scope, &new_name)) {
+        let owner = if scope.is_empty() { "the root scope".to_string() } else { format!("'{}'", scope) };
+        return Err(format!("'{}' is already defined in {}", new_name, owner));
+    }
+
+    let mut references = Vec::new();
+    let mut conflicts = Vec::new();
+    if let ReferenceTarget::Type { full_name, .. } = &target {
+        for_each_type_reference(full_name, workspace, |location, table, type_ref, (start, _)| {
+            // The written name up to the renamed component must not already
+            // mean something else from the reference's scope
+            let rewritten = format!("{}{}", &type_ref.name[..start], new_name);
+            if let Some(def) = table.resolve(&rewritten, &type_ref.scope) {
+                conflicts.push(format!(
+                    "'{}' at {}:{} would refer to '{}'",
+                    rewritten,
+                    location.uri.path().rsplit('/').next().unwrap_or(""),
+                    location.range.start.line + 1,
+                    def.full_name
+                ));
+            }
+            references.push(location);
+        })
+        .await;
+    }
+    if let Some(conflict) = conflicts.first() {
+        return Err(format!("Renaming '{}' to '{}' is ambiguous: {}", old_name, new_name, conflict));
+    }
+    let uses = option_value_uses(&target, workspace).await;
+    if !uses.is_empty() {
+        return Err(format!(
+            "Cannot rename '{}': option values at {} still use it",
+            old_name,
+            uses.join(", ")
+        ));
+    }
 
-    // Use references logic to find all occurrences
-    let ref_params = ReferenceParams {
-        text_document_position: TextDocumentPositionParams {
-            text_document: TextDocumentIdentifier { uri: uri.clone() },
-            position,
+    let declaration = target.declaration().clone();
+    let mut change_annotations = HashMap::new();
+    change_annotations.insert(
+        DECLARATION_ANNOTATION.to_string(),
+        ChangeAnnotation {
+            label: format!("Rename '{}' to '{}'", old_name, new_name),
+            needs_confirmation: Some(false),
+            description: None,
         },
-        context: ReferenceContext {
-            include_declaration: true,
+    );
+    if !references.is_empty() {
+        change_annotations.insert(
+            REFERENCES_ANNOTATION.to_string(),
+            references_annotation(&references, std::slice::from_ref(&declaration.uri)),
+        );
+    }
+
+    let mut edits: Vec<RenameEdit> = vec![(declaration, new_name.clone(), DECLARATION_ANNOTATION)];
+    edits.extend(references.into_iter().map(|location| (location, new_name.clone(), REFERENCES_ANNOTATION)));
+    Ok(Some(workspace_edit(edits, change_annotations, support)))
+}
+
+/// Rename the package declared by `package old;` to `new_name`.
+///
+/// Edits the package statement of every file declaring the package and the
+/// package part of every qualified reference to its types, such as `old.User`
+/// or `.old.User`. Names in the package's own files that only resolved through
+/// its parent packages, such as `common.Money` in `acme.billing` for
+/// `acme.common.Money`, are made absolute when the new package no longer
+/// reaches them. `go_package`, `java_package` and the other language package
+/// options that spell the package are rewritten under a separate change
+/// annotation that needs confirmation, so the user can leave them out; clients
+/// without change annotations do not get them.
+pub async fn rename_package(
+    old: &str,
+    new_name: &str,
+    workspace: &WorkspaceManager,
+    support: &EditSupport,
+) -> Result<Option<WorkspaceEdit>, String> {
+    tracing::debug!("Rename package: '{}' -> '{}'", old, new_name);
+    let (mut edits, change_annotations) = package_rename_edits(old, new_name, workspace).await?;
+    if !support.annotated {
+        edits.retain(|(_, _, annotation)| *annotation != OPTIONS_ANNOTATION);
+    }
+    if edits.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(workspace_edit(edits, change_annotations, support)))
+}
+
+/// Edits renaming package `old` to `new_name`, each with the change annotation
+/// it belongs to, and those annotations. Empty when nothing changes.
+pub async fn package_rename_edits(
+    old: &str,
+    new_name: &str,
+    workspace: &WorkspaceManager,
+) -> Result<(Vec<RenameEdit>, HashMap<String, ChangeAnnotation>), String> {
+    package_move_edits(old, new_name, |_| true, workspace).await
+}
+
+/// Edits moving the files of package `old` that `moving` accepts into package
+/// `new_name`, leaving the others in `old`. References to the moved types are
+/// rewritten as for a package rename; so are names in the moved files that
+/// reach types staying behind and no longer resolve from the new package.
+pub async fn package_move_edits(
+    old: &str,
+    new_name: &str,
+    moving: impl Fn(&Url) -> bool,
+    workspace: &WorkspaceManager,
+) -> Result<(Vec<RenameEdit>, HashMap<String, ChangeAnnotation>), String> {
+    if !new_name.split('.').all(is_identifier) {
+        return Err(format!("'{}' is not a valid package name", new_name));
+    }
+    if old == "google.protobuf" {
+        return Err("'google.protobuf' is the package of the well-known types and cannot be renamed".to_string());
+    }
+    if new_name == old {
+        return Ok(Default::default());
+    }
+
+    // Types of the renamed package must not collide with those already in the new one
+    let moved = declared_symbols_where(workspace, old, |uri| Url::parse(uri).is_ok_and(|uri| moving(&uri)));
+    let existing = declared_symbols(workspace, new_name);
+    let mut collisions: Vec<&str> = moved
+        .iter()
+        .filter_map(|symbol| symbol.strip_prefix(old)?.strip_prefix('.'))
+        .filter(|name| existing.contains(&join(new_name, name)))
+        .collect();
+    collisions.sort();
+    if let Some(name) = collisions.first() {
+        return Err(format!("'{}' is already defined in package '{}'", name, new_name));
+    }
+
+    let mut all_files = workspace.get_all_files();
+    all_files.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut declarations = Vec::new();
+    let mut references = Vec::new();
+    let mut options = Vec::new();
+    for (file_uri, proto) in &all_files {
+        let Ok(uri) = Url::parse(file_uri) else { continue };
+        let Some(content) = workspace.get_content(&uri) else { continue };
+
+        let in_package = proto.package.as_deref() == Some(old) && moving(&uri);
+        if in_package {
+            if let Some(range) = package_statement(&content).map(|(range, _)| range) {
+                declarations.push((Location { uri: uri.clone(), range }, new_name.to_string(), DECLARATION_ANNOTATION));
+            }
+            for (range, value) in language_package_options(&content) {
+                let rewritten = rewrite_language_package(value, old, new_name);
+                if rewritten != value {
+                    options.push((Location { uri: uri.clone(), range }, rewritten, OPTIONS_ANNOTATION));
+                }
+            }
+        }
+
+        // Child packages (`old.internal`) reach the package's types by short names
+        let child_package = proto
+            .package
+            .as_deref()
+            .and_then(|package| package.strip_prefix(old))
+            .is_some_and(|rest| rest.starts_with('.'));
+        let in_old_package = proto.package.as_deref() == Some(old);
+        if !in_old_package && !child_package && !proto.type_refs.iter().any(|r| r.name.contains('.')) {
+            continue;
+        }
+        let mut files = vec![proto.clone()];
+        files.extend(workspace.collect_all_imports_async(&uri).await);
+        let table = TypeTable::from_files(files.iter().map(|f| f.as_ref()));
+        for type_ref in &proto.type_refs {
+            let Some(def) = table.resolve(&type_ref.name, &type_ref.scope) else { continue };
+            let declared_in_package = files.iter().find(|f| f.uri == def.uri).is_some_and(|f| {
+                f.package.as_deref() == Some(old) && Url::parse(&f.uri).is_ok_and(|uri| moving(&uri))
+            });
+            if !declared_in_package {
+                // Moved files go to the new scope; names that resolved through
+                // the old package or its parents may stop resolving there, so
+                // they become absolute
+                let moved_scope = type_ref.scope.strip_prefix(old).map(|rest| format!("{}{}", new_name, rest));
+                let Some(moved_scope) = moved_scope.filter(|_| in_package && !type_ref.name.starts_with('.')) else {
+                    continue;
+                };
+                if table.resolve(&type_ref.name, &moved_scope).is_some_and(|moved| moved.full_name == def.full_name) {
+                    continue;
+                }
+                let range = Range {
+                    start: Position { line: type_ref.line, character: type_ref.character },
+                    end: Position {
+                        line: type_ref.line,
+                        character: type_ref.character + type_ref.name.len() as u32,
+                    },
+                };
+                references.push((Location { uri: uri.clone(), range }, format!(".{}", def.full_name), REFERENCES_ANNOTATION));
+                continue;
+            }
+            // `def.full_name` is the scope-supplied prefix followed by the
+            // written name; the package part is whatever the prefix lacks
+            let leading_dot = usize::from(type_ref.name.starts_with('.'));
+            let written = &type_ref.name[leading_dot..];
+            let supplied = def.full_name.len() - written.len();
+            if supplied >= old.len() {
+                // The scope supplied the whole package. Moved files of the
+                // package go with it; from anywhere else the name stops resolving,
+                // so it becomes the absolute new name.
+                if in_package {
+                    continue;
+                }
+                let range = Range {
+                    start: Position { line: type_ref.line, character: type_ref.character },
+                    end: Position {
+                        line: type_ref.line,
+                        character: type_ref.character + written.len() as u32,
+                    },
+                };
+                let replacement = format!(".{}.{}", new_name, &def.full_name[old.len() + 1..]);
+                references.push((Location { uri: uri.clone(), range }, replacement, REFERENCES_ANNOTATION));
+                continue;
+            }
+            let written_package = &old[supplied..];
+            let replacement = match old[..supplied].strip_suffix('.') {
+                // Keep a partial qualification when the new package shares its head
+                Some(head) if new_name.strip_prefix(head).is_some_and(|rest| rest.starts_with('.')) => {
+                    new_name[head.len() + 1..].to_string()
+                }
+                _ => new_name.to_string(),
+            };
+            let start = type_ref.character + leading_dot as u32;
+            let range = Range {
+                start: Position { line: type_ref.line, character: start },
+                end: Position {
+                    line: type_ref.line,
+                    character: start + written_package.len() as u32,
+                },
+            };
+            references.push((Location { uri: uri.clone(), range }, replacement, REFERENCES_ANNOTATION));
+        }
+    }
+    if declarations.is_empty() {
+        return Ok(Default::default());
+    }
+
+    let mut change_annotations = HashMap::new();
+    change_annotations.insert(
+        DECLARATION_ANNOTATION.to_string(),
+        ChangeAnnotation {
+            label: format!("Rename package '{}' to '{}'", old, new_name),
+            needs_confirmation: Some(false),
+            description: Some(format!(
+                "{} file{}",
+                declarations.len(),
+                if declarations.len() == 1 { "" } else { "s" }
+            )),
         },
-        work_done_progress_params: Default::default(),
-        partial_result_params: Default::default(),
-    };
+    );
+    if !references.is_empty() {
+        let declaring: Vec<Url> = declarations.iter().map(|(location, _, _)| location.uri.clone()).collect();
+        let locations: Vec<Location> = references.iter().map(|(location, _, _)| location.clone()).collect();
+        change_annotations.insert(REFERENCES_ANNOTATION.to_string(), references_annotation(&locations, &declaring));
+    }
+    if !options.is_empty() {
+        change_annotations.insert(
+            OPTIONS_ANNOTATION.to_string(),
+            ChangeAnnotation {
+                label: "Update language package options".to_string(),
+                needs_confirmation: Some(true),
+                description: Some("go_package, java_package, csharp_namespace, php_namespace, ruby_package".to_string()),
+            },
+        );
+    }
 
-    let references =
-        crate::features::references::find_references(ref_params, workspace, Some(content)).await?;
+    let edits = declarations.into_iter().chain(references).chain(options).collect();
+    Ok((edits, change_annotations))
+}
 
-    if references.is_empty() {
-        return None;
+/// Annotation of reference edits. Edits outside the files holding the
+/// declaration need confirmation.
+fn references_annotation(references: &[Location], declaring: &[Url]) -> ChangeAnnotation {
+    let files: HashSet<&Url> = references.iter().map(|location| &location.uri).collect();
+    ChangeAnnotation {
+        label: "Update references".to_string(),
+        needs_confirmation: Some(files.iter().any(|file| !declaring.contains(file))),
+        description: Some(format!(
+            "{} reference{} in {} file{}",
+            references.len(),
+            if references.len() == 1 { "" } else { "s" },
+            files.len(),
+            if files.len() == 1 { "" } else { "s" }
+        )),
     }
+}
 
-    // Group edits by document URI
-    let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
-    for location in references {
-        let edit = TextEdit {
-            range: location.range,
-            new_text: new_name.clone(),
-        };
-        changes
-            .entry(location.uri)
-            .or_insert_with(Vec::new)
-            .push(edit);
+/// Workspace edit replacing each location with its text: versioned document
+/// edits carrying their change annotations, or plain `changes` when the client
+/// does not take annotated `documentChanges`
+pub fn workspace_edit(
+    edits: Vec<RenameEdit>,
+    change_annotations: HashMap<String, ChangeAnnotation>,
+    support: &EditSupport,
+) -> WorkspaceEdit {
+    if !support.annotated {
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for (location, new_text, _) in edits {
+            changes.entry(location.uri).or_default().push(TextEdit { range: location.range, new_text });
+        }
+        return WorkspaceEdit { changes: Some(changes), document_changes: None, change_annotations: None };
     }
 
-    Some(WorkspaceEdit {
-        changes: Some(changes),
-        document_changes: None,
-        change_annotations: None,
-    })
+    let mut files: BTreeMap<Url, Vec<OneOf<TextEdit, AnnotatedTextEdit>>> = BTreeMap::new();
+    for (location, new_text, annotation) in edits {
+        files.entry(location.uri).or_default().push(OneOf::Right(AnnotatedTextEdit {
+            text_edit: TextEdit {
+                range: location.range,
+                new_text,
+            },
+            annotation_id: annotation.to_string(),
+        }));
+    }
+    let edits = files
+        .into_iter()
+        .map(|(uri, edits)| TextDocumentEdit {
+            text_document: OptionalVersionedTextDocumentIdentifier {
+                version: support.versions.get(&uri).copied(),
+                uri,
+            },
+            edits,
+        })
+        .collect();
+    WorkspaceEdit {
+        changes: None,
+        document_changes: Some(DocumentChanges::Edits(edits)),
+        change_annotations: Some(change_annotations),
+    }
 }
 
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
 
-fn get_word(line: &str, idx: usize) -> String {
-    if line.is_empty() {
-        return String::new();
-    }
-    let bytes = line.as_bytes();
-    let idx = idx.min(bytes.len().saturating_sub(1));
+/// Members that option values can spell by their bare name
+#[derive(Clone, Copy, PartialEq)]
+enum OptionMember {
+    Field,
+    EnumValue,
+}
 
-    let is_word_char = |ch: u8| -> bool { ch.is_ascii_alphanumeric() || ch == b'_' };
+/// Where option values in the declaring file and its dependents spell the
+/// field or enum value `target` declares, as `file.proto:line`. Enum values
+/// are found after `=` and as aggregate values, fields as aggregate keys.
+async fn option_value_uses(target: &ReferenceTarget, workspace: &WorkspaceManager) -> Vec<String> {
+    let ReferenceTarget::Declaration { name, declaration, .. } = target else { return Vec::new() };
+    let Some(member) = workspace
+        .get_file(&declaration.uri)
+        .and_then(|proto| option_member(&proto, name, declaration.range.start.line))
+    else {
+        return Vec::new();
+    };
 
-    let mut l = idx;
-    while l > 0 && is_word_char(bytes[l]) {
-        l -= 1;
-    }
-    if l != idx && !is_word_char(bytes[l]) {
-        l += 1;
+    let mut files = vec![declaration.uri.clone()];
+    files.extend(
+        workspace
+            .find_dependents(&workspace.get_tracked_uris(), std::slice::from_ref(&declaration.uri))
+            .await,
+    );
+    let mut uses = Vec::new();
+    for uri in files {
+        let Some(content) = workspace.get_content(&uri) else { continue };
+        let mut lines = Vec::new();
+        if member == OptionMember::EnumValue {
+            let tokens = tokenize(&content);
+            lines.extend(
+                tokens
+                    .windows(2)
+                    .filter(|pair| pair[0].is("=") && is_name(&pair[1], name))
+                    .map(|pair| pair[1].line),
+            );
+        }
+        for (_, fields) in aggregate_values(&content) {
+            aggregate_uses(&fields, name, member, &mut lines);
+        }
+        lines.sort_unstable();
+        lines.dedup();
+        let file = uri.path().rsplit('/').next().unwrap_or("").to_string();
+        uses.extend(lines.into_iter().map(|line| format!("{}:{}", file, line + 1)));
     }
+    uses
+}
 
-    let mut r = idx;
-    while r < bytes.len() && is_word_char(bytes[r]) {
-        r += 1;
+fn aggregate_uses(fields: &[TextField], name: &str, member: OptionMember, lines: &mut Vec<u32>) {
+    for field in fields {
+        if member == OptionMember::Field && !field.extension && field.name.text == name {
+            lines.push(field.name.line);
+        }
+        for value in &field.values {
+            match value {
+                TextValue::Scalar { token, .. } => {
+                    if member == OptionMember::EnumValue && is_name(token, name) {
+                        lines.push(token.line);
+                    }
+                }
+                TextValue::Message { fields, .. } => aggregate_uses(fields, name, member, lines),
+            }
+        }
     }
+}
 
-    line[l..r].to_string()
+fn is_name(token: &Token, name: &str) -> bool {
+    token.kind == TokenKind::Identifier && token.text == name
 }
 
-fn find_word_start(line: &str, idx: usize) -> usize {
-    if line.is_empty() {
-        return 0;
+/// Whether the declaration of `name` on `line` is a field or an enum value
+fn option_member(proto: &ParsedProto, name: &str, line: u32) -> Option<OptionMember> {
+    fn in_enums(enums: &[EnumElement], name: &str, line: u32) -> bool {
+        enums.iter().flat_map(|e| &e.values).any(|v| v.name == name && v.line == line)
+    }
+    fn in_fields(fields: &[FieldElement], name: &str, line: u32) -> bool {
+        fields.iter().any(|f| f.name == name && f.line == line)
+    }
+    fn in_messages(messages: &[MessageElement], name: &str, line: u32) -> Option<OptionMember> {
+        messages.iter().find_map(|msg| {
+            if in_fields(&msg.fields, name, line) {
+                Some(OptionMember::Field)
+            } else if in_enums(&msg.nested_enums, name, line) {
+                Some(OptionMember::EnumValue)
+            } else {
+                in_messages(&msg.nested_messages, name, line)
+            }
+        })
     }
-    let bytes = line.as_bytes();
-    let idx = idx.min(bytes.len().saturating_sub(1));
-
-    let is_word_char = |ch: u8| -> bool { ch.is_ascii_alphanumeric() || ch == b'_' };
 
-    let mut l = idx;
-    while l > 0 && is_word_char(bytes[l]) {
-        l -= 1;
+    if in_enums(&proto.enums, name, line) {
+        return Some(OptionMember::EnumValue);
     }
-    if l != idx && !is_word_char(bytes[l]) {
-        l += 1;
+    if proto.extends.iter().any(|extend| in_fields(&extend.fields, name, line)) {
+        return Some(OptionMember::Field);
     }
-    l
+    in_messages(&proto.messages, name, line)
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Types from `google/protobuf/*.proto` ship with protoc and cannot be edited
+fn is_well_known(target: &ReferenceTarget) -> bool {
+    matches!(target, ReferenceTarget::Type { full_name, .. } if full_name.starts_with("google.protobuf."))
 }
 
-/// Check if the symbol is a known renamable entity in the workspace.
-fn is_renamable_symbol(symbol: &str, workspace: &WorkspaceManager, uri: &Url) -> bool {
-    if let Some(proto) = workspace.get_file(uri) {
-        // Check messages
-        if proto.find_message_by_name(symbol).is_some() {
-            return true;
+/// The range and name of the `package` statement
+pub fn package_statement(content: &str) -> Option<(Range, &str)> {
+    content.lines().enumerate().find_map(|(line_index, line)| {
+        let rest = line.trim_start().strip_prefix("package")?;
+        if !rest.starts_with(char::is_whitespace) {
+            return None;
         }
-        // Check enums
-        if proto.find_enum_by_name(symbol).is_some() {
-            return true;
+        let name = rest.split(';').next()?.trim();
+        let start = line.find(name)?;
+        Some((
+            Range {
+                start: Position { line: line_index as u32, character: start as u32 },
+                end: Position {
+                    line: line_index as u32,
+                    character: (start + name.len()) as u32,
+                },
+            },
+            name,
+        ))
+    })
+}
+
+/// Language package options of a file: the range inside the quotes and the value
+fn language_package_options(content: &str) -> Vec<(Range, &str)> {
+    let mut options = Vec::new();
+    for (line_index, line) in content.lines().enumerate() {
+        let Some(rest) = line.trim_start().strip_prefix("option") else { continue };
+        let Some((name, _)) = rest.split_once('=') else { continue };
+        if !LANGUAGE_PACKAGE_OPTIONS.contains(&name.trim()) {
+            continue;
         }
-        // Check services
-        if proto.find_service_by_name(symbol).is_some() {
-            return true;
+        let (Some(open), Some(close)) = (line.find('"'), line.rfind('"')) else { continue };
+        if open < close {
+            let range = Range {
+                start: Position { line: line_index as u32, character: open as u32 + 1 },
+                end: Position { line: line_index as u32, character: close as u32 },
+            };
+            options.push((range, &line[open + 1..close]));
         }
-        // Check methods
-        if proto.find_method_by_name(symbol).is_some() {
-            return true;
+    }
+    options
+}
+
+/// Replace the spellings of package `old` in a language package option with
+/// those of `new`: `acme/billing/v1` and the `billingv1` alias in go_package,
+/// `com.acme.billing.v1` in java_package, `Acme.Billing.V1`, `Acme\\Billing\\V1`
+/// and `Acme::Billing::V1` in the C#, PHP and Ruby options
+fn rewrite_language_package(value: &str, old: &str, new: &str) -> String {
+    fn pascal(package: &str, separator: &str) -> String {
+        package
+            .split('.')
+            .map(|part| {
+                let mut chars = part.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+    /// Go package name: the last two components, e.g. `billingv1`
+    fn go_name(package: &str) -> String {
+        let parts: Vec<&str> = package.split('.').collect();
+        parts[parts.len().saturating_sub(2)..].concat()
+    }
+
+    let (path, alias) = match value.split_once(';') {
+        Some((path, alias)) => (path, Some(alias)),
+        None => (value, None),
+    };
+    let mut path = path.to_string();
+    for (from, to) in [
+        (old.replace('.', "/"), new.replace('.', "/")),
+        (old.to_string(), new.to_string()),
+        (pascal(old, "."), pascal(new, ".")),
+        (pascal(old, "\\\\"), pascal(new, "\\\\")),
+        (pascal(old, "::"), pascal(new, "::")),
+    ] {
+        let replaced = replace_whole(&path, &from, &to);
+        if replaced != path {
+            path = replaced;
+            break;
         }
-        // Check fields in messages
-        for msg in &proto.messages {
-            if msg.fields.iter().any(|f| f.name == symbol) {
-                return true;
-            }
+    }
+    match alias {
+        Some(alias) if alias == go_name(old) => format!("{};{}", path, go_name(new)),
+        Some(alias) => format!("{};{}", path, alias),
+        None => path,
+    }
+}
+
+fn contains(range: &Range, position: Position) -> bool {
+    range.start.line == position.line && (range.start.character..=range.end.character).contains(&position.character)
+}
+
+/// Replace occurrences of `from` that are not part of a longer identifier
+fn replace_whole(haystack: &str, from: &str, to: &str) -> String {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut out = String::with_capacity(haystack.len());
+    let mut copied = 0;
+    for (i, _) in haystack.match_indices(from) {
+        let end = i + from.len();
+        let bounded = !haystack[..i].chars().next_back().is_some_and(is_word)
+            && !haystack[end..].chars().next().is_some_and(is_word);
+        if bounded && i >= copied {
+            out.push_str(&haystack[copied..i]);
+            out.push_str(to);
+            copied = end;
         }
-        // Check enum values
-        for e in &proto.enums {
-            if e.values.iter().any(|v| v.name == symbol) {
-                return true;
+    }
+    out.push_str(&haystack[copied..]);
+    out
+}
+
+fn join(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+/// Fully-qualified names of everything declared in `package` across the
+/// indexed workspace: types, fields, enum values (in the scope enclosing their
+/// enum), services and methods
+fn declared_symbols(workspace: &WorkspaceManager, package: &str) -> HashSet<String> {
+    declared_symbols_where(workspace, package, |_| true)
+}
+
+/// `declared_symbols` of the files of `package` whose URI `include` accepts
+fn declared_symbols_where(workspace: &WorkspaceManager, package: &str, include: impl Fn(&str) -> bool) -> HashSet<String> {
+    fn add_messages(messages: &[MessageElement], symbols: &mut HashSet<String>) {
+        for msg in messages {
+            symbols.insert(msg.full_name.clone());
+            symbols.extend(msg.fields.iter().map(|f| join(&msg.full_name, &f.name)));
+            for e in &msg.nested_enums {
+                symbols.insert(e.full_name.clone());
+                symbols.extend(e.values.iter().map(|v| join(&msg.full_name, &v.name)));
             }
+            add_messages(&msg.nested_messages, symbols);
         }
     }
 
-    // Also check across workspace
-    let results = workspace.find_symbol(symbol);
-    !results.is_empty()
+    let mut symbols = HashSet::new();
+    let files: Vec<_> = workspace
+        .get_all_files()
+        .into_iter()
+        .filter(|(uri, proto)| proto.package.as_deref().unwrap_or("") == package && include(uri))
+        .collect();
+    for (_, proto) in &files {
+        let proto: &ParsedProto = proto;
+        add_messages(&proto.messages, &mut symbols);
+        for e in &proto.enums {
+            symbols.insert(e.full_name.clone());
+            symbols.extend(e.values.iter().map(|v| join(package, &v.name)));
+        }
+        for ext in &proto.extends {
+            symbols.extend(ext.fields.iter().map(|f| join(package, &f.name)));
+        }
+        for svc in &proto.services {
+            symbols.insert(svc.full_name.clone());
+            symbols.extend(svc.methods.iter().map(|m| join(&svc.full_name, &m.name)));
+        }
+    }
+    symbols
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::features::testing::open_files;
 
     #[test]
-    fn test_get_word() {
-        assert_eq!(get_word("message UserRequest {", 8), "UserRequest");
-        assert_eq!(get_word("  string name = 1;", 9), "name");
-        assert_eq!(get_word("", 0), "");
+    fn test_is_identifier() {
+        assert!(is_identifier("UserRequest"));
+        assert!(is_identifier("_user_id2"));
+        assert!(!is_identifier("2fa"));
+        assert!(!is_identifier("user-id"));
+        assert!(!is_identifier("pkg.User"));
+        assert!(!is_identifier(""));
+    }
+
+    #[tokio::test]
+    async fn test_semantic_rename() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = r#"syntax = "proto3";
+package a;
+
+message User {
+  string name = 1;
+}
+
+message Outer {
+  message Inner {}
+  Inner inner = 1;
+  message Account {}
+  User owner = 2;
+}
+
+service Users {
+  rpc Get(User) returns (User);
+}
+"#;
+        let b = r#"syntax = "proto3";
+package b;
+import "a.proto";
+
+message Profile {
+  a.User user = 1;
+  .a.Outer.Inner inner = 2;
+}
+"#;
+        let a_path = dir.path().join("a.proto");
+        let b_path = dir.path().join("b.proto");
+        std::fs::write(&a_path, a).unwrap();
+        std::fs::write(&b_path, b).unwrap();
+        let a_uri = Url::from_file_path(&a_path).unwrap();
+        let b_uri = Url::from_file_path(&b_path).unwrap();
+
+        let workspace = WorkspaceManager::new();
+        workspace.open_file(&a_uri, a).await.unwrap();
+        workspace.open_file(&b_uri, b).await.unwrap();
+        let support = EditSupport { annotated: true, versions: HashMap::from([(a_uri.clone(), 7)]) };
+
+        let rename_to = |uri: &Url, line, character, content: &'static str, new_name: &str| {
+            let params = RenameParams {
+                text_document_position: TextDocumentPositionParams {
+                    text_document: TextDocumentIdentifier { uri: uri.clone() },
+                    position: Position { line, character },
+                },
+                new_name: new_name.to_string(),
+                work_done_progress_params: Default::default(),
+            };
+            let (workspace, support) = (&workspace, &support);
+            async move { rename(params, workspace, Some(content), support).await }
+        };
+        let edited = |edit: WorkspaceEdit| -> Vec<(String, Option<i32>, u32, u32, String)> {
+            let Some(DocumentChanges::Edits(documents)) = edit.document_changes else {
+                panic!("expected document edits");
+            };
+            documents
+                .into_iter()
+                .flat_map(|document| {
+                    let file = document.text_document.uri.path().rsplit('/').next().unwrap().to_string();
+                    let version = document.text_document.version;
+                    document.edits.into_iter().map(move |edit| {
+                        let OneOf::Right(edit) = edit else { panic!("expected annotated edits") };
+                        (
+                            file.clone(),
+                            version,
+                            edit.text_edit.range.start.line,
+                            edit.text_edit.range.start.character,
+                            edit.annotation_id,
+                        )
+                    })
+                })
+                .collect()
+        };
+
+        // Qualified references only have the type's component replaced
+        let edit = rename_to(&a_uri, 3, 9, a, "Member").await.unwrap().unwrap();
+        let annotations = edit.change_annotations.clone().unwrap();
+        assert_eq!(annotations[REFERENCES_ANNOTATION].needs_confirmation, Some(true));
+        assert_eq!(
+            edited(edit),
+            vec![
+                ("a.proto".to_string(), Some(7), 3, 8, DECLARATION_ANNOTATION.to_string()),
+                ("a.proto".to_string(), Some(7), 11, 2, REFERENCES_ANNOTATION.to_string()),
+                ("a.proto".to_string(), Some(7), 15, 10, REFERENCES_ANNOTATION.to_string()),
+                ("a.proto".to_string(), Some(7), 15, 25, REFERENCES_ANNOTATION.to_string()),
+                ("b.proto".to_string(), None, 5, 4, REFERENCES_ANNOTATION.to_string()),
+            ]
+        );
+
+        // `.a.Outer.Inner` becomes `.a.Container.Inner`
+        let edit = rename_to(&b_uri, 6, 6, b, "Container").await.unwrap().unwrap();
+        assert_eq!(
+            edited(edit),
+            vec![
+                ("a.proto".to_string(), Some(7), 7, 8, DECLARATION_ANNOTATION.to_string()),
+                ("b.proto".to_string(), None, 6, 5, REFERENCES_ANNOTATION.to_string()),
+            ]
+        );
+
+        // Fields are renamed at their declaration
+        let edit = rename_to(&a_uri, 4, 10, a, "full_name").await.unwrap().unwrap();
+        assert_eq!(edited(edit), vec![("a.proto".to_string(), Some(7), 4, 9, DECLARATION_ANNOTATION.to_string())]);
+
+        // Invalid and conflicting names are rejected
+        assert_eq!(
+            rename_to(&a_uri, 3, 9, a, "2User").await,
+            Err("'2User' is not a valid identifier".to_string())
+        );
+        assert_eq!(
+            rename_to(&a_uri, 3, 9, a, "Outer").await,
+            Err("'Outer' is already defined in 'a'".to_string())
+        );
+        assert_eq!(
+            rename_to(&a_uri, 8, 11, a, "Account").await,
+            Err("'Account' is already defined in 'a.Outer'".to_string())
+        );
+        // `User owner = 2;` inside Outer would become the nested Outer.Inner
+        assert_eq!(
+            rename_to(&a_uri, 3, 9, a, "Inner").await,
+            Err("Renaming 'User' to 'Inner' is ambiguous: 'Inner' at a.proto:12 would refer to 'a.Outer.Inner'".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rename_used_in_option_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = r#"syntax = "proto2";
+package opts;
+import "google/protobuf/descriptor.proto";
+
+enum Mode {
+  MODE_FAST = 0;
+  MODE_SAFE = 1;
+  MODE_SLOW = 2;
+}
+
+message Config {
+  optional Mode mode = 1 [default = MODE_SAFE];
+  optional int32 retries = 2;
+  optional int32 timeout = 3;
+}
+
+extend google.protobuf.MessageOptions {
+  optional Config config = 50000;
+}
+"#;
+        let job = r#"syntax = "proto2";
+package jobs;
+import "config.proto";
+
+message Job {
+  option (opts.config) = {
+    mode: MODE_FAST
+    retries: 3
+  };
+}
+"#;
+        let workspace = WorkspaceManager::new();
+        let uris = open_files(&workspace, dir.path(), &[("config.proto", config), ("job.proto", job)]).await;
+        let support = EditSupport::default();
+        let rename_at = |line, character| {
+            let params = RenameParams {
+                text_document_position: TextDocumentPositionParams {
+                    text_document: TextDocumentIdentifier { uri: uris[0].clone() },
+                    position: Position { line, character },
+                },
+                new_name: "Renamed".to_string(),
+                work_done_progress_params: Default::default(),
+            };
+            let (workspace, support) = (&workspace, &support);
+            async move { rename(params, workspace, Some(config), support).await }
+        };
+
+        // Enum values spelled after `=` and as aggregate values
+        assert_eq!(
+            rename_at(6, 4).await,
+            Err("Cannot rename 'MODE_SAFE': option values at config.proto:12 still use it".to_string())
+        );
+        assert_eq!(
+            rename_at(5, 4).await,
+            Err("Cannot rename 'MODE_FAST': option values at job.proto:7 still use it".to_string())
+        );
+        // Fields spelled as aggregate keys
+        assert_eq!(
+            rename_at(12, 17).await,
+            Err("Cannot rename 'retries': option values at job.proto:8 still use it".to_string())
+        );
+        // Members no option value spells are renamed
+        assert!(rename_at(7, 4).await.unwrap().is_some());
+        assert!(rename_at(13, 17).await.unwrap().is_some());
     }
 
     #[test]
-    fn test_find_word_start() {
-        assert_eq!(find_word_start("message UserRequest {", 8), 8);
-        assert_eq!(find_word_start("message UserRequest {", 12), 8);
+    fn test_rewrite_language_package() {
+        let (old, new) = ("acme.billing.v1", "acme.payments.v1");
+        assert_eq!(
+            rewrite_language_package("example.com/gen/acme/billing/v1;billingv1", old, new),
+            "example.com/gen/acme/payments/v1;paymentsv1"
+        );
+        assert_eq!(rewrite_language_package("com.acme.billing.v1", old, new), "com.acme.payments.v1");
+        assert_eq!(rewrite_language_package("Acme.Billing.V1", old, new), "Acme.Payments.V1");
+        assert_eq!(rewrite_language_package("Acme\\\\Billing\\\\V1", old, new), "Acme\\\\Payments\\\\V1");
+        assert_eq!(rewrite_language_package("Acme::Billing::V1", old, new), "Acme::Payments::V1");
+        // Only whole components are replaced
+        assert_eq!(rewrite_language_package("example.com/billing2;billing2", "billing", "payments"), "example.com/billing2;billing2");
+        assert_eq!(rewrite_language_package("example.com/billing;billing", "billing", "payments"), "example.com/payments;payments");
+    }
+
+    #[tokio::test]
+    async fn test_package_rename() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = r#"syntax = "proto3";
+package acme.billing.v1;
+
+option go_package = "example.com/gen/acme/billing/v1;billingv1";
+option java_package = "com.acme.billing.v1";
+
+message Invoice {}
+message Line {
+  Invoice invoice = 1;
+}
+"#;
+        let b = r#"syntax = "proto3";
+package acme.shop;
+import "a.proto";
+
+message Order {
+  acme.billing.v1.Invoice invoice = 1;
+  billing.v1.Invoice partial = 2;
+  .acme.billing.v1.Invoice absolute = 3;
+}
+"#;
+        // A child package reaches the renamed package's types by short names
+        let d = r#"syntax = "proto3";
+package acme.billing.v1.internal;
+import "a.proto";
+
+message Audit {
+  Invoice invoice = 1;
+  v1.Invoice partial = 2;
+}
+"#;
+        // Another file of the package reaches `acme.common` through the parent scope
+        let e = "syntax = \"proto3\";\npackage acme.common;\n\nmessage Money {}\n";
+        let f = r#"syntax = "proto3";
+package acme.billing.v1;
+import "e.proto";
+
+message Payment {
+  common.Money amount = 1;
+}
+"#;
+        let a_path = dir.path().join("a.proto");
+        let b_path = dir.path().join("b.proto");
+        let d_path = dir.path().join("d.proto");
+        std::fs::write(&a_path, a).unwrap();
+        std::fs::write(&b_path, b).unwrap();
+        std::fs::write(&d_path, d).unwrap();
+        let a_uri = Url::from_file_path(&a_path).unwrap();
+        let b_uri = Url::from_file_path(&b_path).unwrap();
+        let d_uri = Url::from_file_path(&d_path).unwrap();
+
+        let workspace = WorkspaceManager::new();
+        workspace.open_file(&a_uri, a).await.unwrap();
+        workspace.open_file(&b_uri, b).await.unwrap();
+        workspace.open_file(&d_uri, d).await.unwrap();
+        for (name, content) in [("e.proto", e), ("f.proto", f)] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            workspace.open_file(&Url::from_file_path(&path).unwrap(), content).await.unwrap();
+        }
+        let support = EditSupport { annotated: true, ..Default::default() };
+
+        let params = |new_name: &str| RenameParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri: a_uri.clone() },
+                position: Position { line: 1, character: 14 },
+            },
+            new_name: new_name.to_string(),
+            work_done_progress_params: Default::default(),
+        };
+
+        let prepared = prepare_rename(params("").text_document_position, &workspace, Some(a)).await;
+        assert_eq!(
+            prepared,
+            Some(PrepareRenameResponse::Range(Range {
+                start: Position { line: 1, character: 8 },
+                end: Position { line: 1, character: 23 },
+            }))
+        );
+
+        let edited = |edit: WorkspaceEdit| -> Vec<(String, u32, u32, u32, String)> {
+            let Some(DocumentChanges::Edits(documents)) = edit.document_changes else {
+                panic!("expected document edits");
+            };
+            documents
+                .into_iter()
+                .flat_map(|document| {
+                    let file = document.text_document.uri.path().rsplit('/').next().unwrap().to_string();
+                    document.edits.into_iter().map(move |edit| {
+                        let OneOf::Right(edit) = edit else { panic!("expected annotated edits") };
+                        let range = edit.text_edit.range;
+                        (file.clone(), range.start.line, range.start.character, range.end.character, edit.text_edit.new_text)
+                    })
+                })
+                .collect()
+        };
+        let edit = |file: &str, line, start, end, text: &str| (file.to_string(), line, start, end, text.to_string());
+
+        let renamed = rename(params("acme.payments.v1"), &workspace, Some(a), &support).await.unwrap().unwrap();
+        let annotations = renamed.change_annotations.clone().unwrap();
+        assert_eq!(annotations[OPTIONS_ANNOTATION].needs_confirmation, Some(true));
+        assert_eq!(
+            edited(renamed),
+            vec![
+                edit("a.proto", 1, 8, 23, "acme.payments.v1"),
+                edit("a.proto", 3, 21, 62, "example.com/gen/acme/payments/v1;paymentsv1"),
+                edit("a.proto", 4, 23, 42, "com.acme.payments.v1"),
+                edit("b.proto", 5, 2, 17, "acme.payments.v1"),
+                edit("b.proto", 6, 2, 12, "payments.v1"),
+                edit("b.proto", 7, 3, 18, "acme.payments.v1"),
+                edit("d.proto", 5, 2, 9, ".acme.payments.v1.Invoice"),
+                edit("d.proto", 6, 2, 4, "acme.payments.v1"),
+                // `common.Money` still resolves from `acme.payments.v1`
+                edit("f.proto", 1, 8, 23, "acme.payments.v1"),
+            ]
+        );
+
+        // From `corp.billing.v1` it would not, so it becomes absolute
+        let renamed = rename(params("corp.billing.v1"), &workspace, Some(a), &support).await.unwrap().unwrap();
+        let in_f: Vec<_> = edited(renamed).into_iter().filter(|e| e.0 == "f.proto").collect();
+        assert_eq!(
+            in_f,
+            vec![edit("f.proto", 1, 8, 23, "corp.billing.v1"), edit("f.proto", 5, 2, 14, ".acme.common.Money")]
+        );
+
+        // Clients without change annotations get plain edits, leaving out the
+        // language package options they could not decline
+        let plain = EditSupport::default();
+        let renamed = rename(params("acme.payments.v1"), &workspace, Some(a), &plain).await.unwrap().unwrap();
+        assert!(renamed.document_changes.is_none() && renamed.change_annotations.is_none());
+        let mut changed: Vec<(String, u32, String)> = renamed
+            .changes
+            .unwrap()
+            .into_iter()
+            .flat_map(|(uri, edits)| {
+                let file = uri.path().rsplit('/').next().unwrap().to_string();
+                edits.into_iter().map(move |edit| (file.clone(), edit.range.start.line, edit.new_text))
+            })
+            .collect();
+        changed.sort();
+        assert_eq!(
+            changed,
+            vec![
+                ("a.proto".to_string(), 1, "acme.payments.v1".to_string()),
+                ("b.proto".to_string(), 5, "acme.payments.v1".to_string()),
+                ("b.proto".to_string(), 6, "payments.v1".to_string()),
+                ("b.proto".to_string(), 7, "acme.payments.v1".to_string()),
+                ("d.proto".to_string(), 5, ".acme.payments.v1.Invoice".to_string()),
+                ("d.proto".to_string(), 6, "acme.payments.v1".to_string()),
+                ("f.proto".to_string(), 1, "acme.payments.v1".to_string()),
+            ]
+        );
+
+        assert_eq!(
+            rename(params("acme..v1"), &workspace, Some(a), &support).await,
+            Err("'acme..v1' is not a valid package name".to_string())
+        );
+
+        // Types of the renamed package would collide with the target package's
+        let c_path = dir.path().join("c.proto");
+        let c = "syntax = \"proto3\";\npackage acme.payments.v1;\n\nmessage Invoice {}\n";
+        std::fs::write(&c_path, c).unwrap();
+        workspace.open_file(&Url::from_file_path(&c_path).unwrap(), c).await.unwrap();
+        assert_eq!(
+            rename(params("acme.payments.v1"), &workspace, Some(a), &support).await,
+            Err("'Invoice' is already defined in package 'acme.payments.v1'".to_string())
+        );
     }
 }
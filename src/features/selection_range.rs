@@ -0,0 +1,347 @@
+//! Selection ranges from the parsed element tree. Each element's start comes
+//! from the AST and its end from the tokens that follow it (comments and
+//! strings are skipped by the tokenizer), so expanding the selection grows
+//! name → field → oneof → message body → message → enclosing message → file.
+//! Within the innermost statement, string literals, qualified names, option
+//! entries and bracket pairs (`[…]`, `(…)`, `{…}`) add steps of their own.
+
+use crate::features::text_format::{tokenize, Token, TokenKind};
+use crate::features::tokens::{statement_end, token_at};
+use crate::parser::proto::{EnumElement, MessageElement};
+use crate::parser::ParsedProto;
+use crate::workspace::WorkspaceManager;
+use std::cmp::Reverse;
+use tower_lsp::lsp_types::*;
+
+pub fn provide_selection_ranges(
+    params: SelectionRangeParams,
+    workspace: &WorkspaceManager,
+    content: Option<&str>,
+) -> Option<Vec<SelectionRange>> {
+    let proto = workspace.get_file(&params.text_document.uri)?;
+    let content = content?;
+    let tokens = tokenize(content);
+
+    let selections = params
+        .positions
+        .into_iter()
+        .map(|position| {
+            let mut ranges = vec![file_range(content)];
+            let mut tree = Tree { tokens: &tokens, position, ranges: &mut ranges, statement: None };
+            tree.visit_file(&proto);
+            // Outside any element, brackets and strings are matched over the file
+            if let Some(statement) = tree.statement.or((!tokens.is_empty()).then(|| (0, tokens.len() - 1))) {
+                statement_ranges(&tokens, statement, position, &mut ranges);
+            }
+            nest(ranges, position)
+        })
+        .collect();
+    Some(selections)
+}
+
+/// Walks the elements containing the position, collecting their ranges
+struct Tree<'a, 't> {
+    tokens: &'a [Token<'t>],
+    position: Position,
+    ranges: &'a mut Vec<Range>,
+    /// Token span of the innermost element holding the position
+    statement: Option<(usize, usize)>,
+}
+
+impl Tree<'_, '_> {
+    fn visit_file(&mut self, proto: &ParsedProto) {
+        for import in &proto.imports {
+            self.statement(import.line, import.character, None);
+        }
+        for msg in &proto.messages {
+            self.visit_message(msg);
+        }
+        for e in &proto.enums {
+            self.visit_enum(e);
+        }
+        for ext in &proto.extends {
+            if self.block(ext.line, ext.character, "extend") {
+                for field in &ext.fields {
+                    self.statement(field.line, field.character, None);
+                }
+            }
+        }
+        for svc in &proto.services {
+            if self.block(svc.line, svc.character, "service") {
+                for method in &svc.methods {
+                    self.statement(method.line, method.character, Some("rpc"));
+                }
+            }
+        }
+    }
+
+    fn visit_message(&mut self, msg: &MessageElement) {
+        if !self.block(msg.line, msg.character, "message") {
+            return;
+        }
+        for nested in &msg.nested_messages {
+            self.visit_message(nested);
+        }
+        for e in &msg.nested_enums {
+            self.visit_enum(e);
+        }
+        for oneof in &msg.oneofs {
+            self.block(oneof.line, oneof.character, "oneof");
+        }
+        // Oneof fields are listed here too and land inside their oneof
+        for field in &msg.fields {
+            self.statement(field.line, field.character, None);
+        }
+    }
+
+    fn visit_enum(&mut self, e: &EnumElement) {
+        if self.block(e.line, e.character, "enum") {
+            for value in &e.values {
+                self.statement(value.line, value.character, None);
+            }
+        }
+    }
+
+    /// A `keyword Name { … }` element whose name is at `line`/`character`. Adds
+    /// the name, the body between the braces and the whole element when they
+    /// contain the position; returns whether the element does.
+    fn block(&mut self, line: u32, character: u32, keyword: &str) -> bool {
+        let Some(name) = token_at(self.tokens, line, character) else { return false };
+        let Some((_, end)) = self.statement(line, character, Some(keyword)) else { return false };
+        self.add(token_range(&self.tokens[name], &self.tokens[name]));
+        if let Some(open) = (name..end).find(|&i| self.tokens[i].is("{")) {
+            if open + 1 < end {
+                self.add(token_range(&self.tokens[open + 1], &self.tokens[end - 1]));
+            }
+        }
+        true
+    }
+
+    /// A statement or block starting at the token at `line`/`character`, or at
+    /// the `keyword` right before it. Records its span as the innermost
+    /// statement when it contains the position.
+    fn statement(&mut self, line: u32, character: u32, keyword: Option<&str>) -> Option<(usize, usize)> {
+        let mut start = token_at(self.tokens, line, character)?;
+        if let Some(keyword) = keyword {
+            if start > 0 && self.tokens[start - 1].text == keyword {
+                start -= 1;
+            }
+        }
+        let end = statement_end(self.tokens, start)?;
+        let range = token_range(&self.tokens[start], &self.tokens[end]);
+        if !contains(&range, self.position) {
+            return None;
+        }
+        self.ranges.push(range);
+        self.statement = Some((start, end));
+        Some((start, end))
+    }
+
+    fn add(&mut self, range: Range) {
+        if contains(&range, self.position) {
+            self.ranges.push(range);
+        }
+    }
+}
+
+/// Ranges within the statement spanning tokens `start..=end`: the word or
+/// string at the position, a dotted name, option entries and bracket pairs
+fn statement_ranges(tokens: &[Token], (start, end): (usize, usize), position: Position, ranges: &mut Vec<Range>) {
+    let statement = &tokens[start..=end];
+    let mut add = |range: Range| {
+        if contains(&range, position) {
+            ranges.push(range);
+        }
+    };
+
+    if let Some(i) = statement.iter().position(|t| contains(&token_range(t, t), position)) {
+        let token = &statement[i];
+        match token.kind {
+            TokenKind::String if token.text.len() >= 2 => {
+                let mut inner = token_range(token, token);
+                inner.start.character += 1;
+                inner.end.character -= 1;
+                add(inner);
+            }
+            TokenKind::Identifier => {
+                // `pkg.Outer.Inner`, from the first to the last component
+                let mut first = i;
+                while first >= 2 && statement[first - 1].is(".") && adjacent(&statement[first - 1], &statement[first]) {
+                    first -= 2;
+                }
+                let mut last = i;
+                while last + 2 < statement.len() && statement[last + 1].is(".") && adjacent(&statement[last], &statement[last + 1]) {
+                    last += 2;
+                }
+                if first > 0 && statement[first - 1].is(".") {
+                    first -= 1;
+                }
+                add(token_range(&statement[first], &statement[last]));
+            }
+            _ => {}
+        }
+        add(token_range(token, token));
+    }
+
+    // Bracket pairs around the position, each with its contents
+    let mut open: Vec<usize> = Vec::new();
+    for (i, token) in statement.iter().enumerate() {
+        if token.is("{") || token.is("[") || token.is("(") {
+            open.push(i);
+        } else if token.is("}") || token.is("]") || token.is(")") {
+            let Some(o) = open.pop() else { break };
+            add(token_range(&statement[o], token));
+            if o + 1 < i {
+                add(token_range(&statement[o + 1], &statement[i - 1]));
+            }
+            if statement[o].is("[") {
+                // One `name = value` entry of a compact option list
+                let mut entry_start = o + 1;
+                for j in o + 1..=i {
+                    if statement[j].is(",") || j == i {
+                        if entry_start < j {
+                            add(token_range(&statement[entry_start], &statement[j - 1]));
+                        }
+                        entry_start = j + 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn adjacent(a: &Token, b: &Token) -> bool {
+    a.line == b.line && a.end_character() == b.character
+}
+
+fn token_range(first: &Token, last: &Token) -> Range {
+    Range {
+        start: Position { line: first.line, character: first.character },
+        end: Position { line: last.line, character: last.end_character() },
+    }
+}
+
+fn file_range(content: &str) -> Range {
+    let lines = content.lines().count();
+    let last = content.lines().last().unwrap_or("");
+    Range {
+        start: Position { line: 0, character: 0 },
+        end: Position { line: lines.saturating_sub(1) as u32, character: last.encode_utf16().count() as u32 },
+    }
+}
+
+fn contains(range: &Range, position: Position) -> bool {
+    range.start <= position && position <= range.end
+}
+
+/// Links the ranges containing `position` from innermost to outermost,
+/// dropping duplicates and any range that does not contain the previous one
+fn nest(mut ranges: Vec<Range>, position: Position) -> SelectionRange {
+    ranges.sort_by_key(|r| (Reverse(r.start), r.end));
+    let mut chain: Vec<Range> = Vec::new();
+    for range in ranges {
+        let encloses = chain
+            .last()
+            .is_none_or(|inner| range.start <= inner.start && inner.end <= range.end && range != *inner);
+        if encloses {
+            chain.push(range);
+        }
+    }
+
+    let mut selection = None;
+    for range in chain.into_iter().rev() {
+        selection = Some(SelectionRange { range, parent: selection.map(Box::new) });
+    }
+    selection.unwrap_or(SelectionRange {
+        range: Range { start: position, end: position },
+        parent: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text of each range, innermost first
+    fn chain(content: &str, selection: &SelectionRange) -> Vec<String> {
+        let lines: Vec<&str> = content.lines().collect();
+        let text = |range: &Range| {
+            if range.start.line == range.end.line {
+                lines[range.start.line as usize][range.start.character as usize..range.end.character as usize].to_string()
+            } else {
+                format!("{}..{}", range.start.line, range.end.line)
+            }
+        };
+        let mut texts = vec![text(&selection.range)];
+        let mut parent = &selection.parent;
+        while let Some(p) = parent {
+            texts.push(text(&p.range));
+            parent = &p.parent;
+        }
+        texts
+    }
+
+    #[tokio::test]
+    async fn test_selection_ranges() {
+        let content = r#"syntax = "proto3";
+package shop;
+
+message Order {
+  message Line {
+    string sku = 1 [json_name = "SKU", deprecated = true];
+  }
+  oneof payment {
+    // Card payment
+    shop.Card card = 2;
+  }
+}
+
+service Orders {
+  rpc Get(GetRequest) returns (Order);
+}
+"#;
+        let workspace = WorkspaceManager::new();
+        let uri = Url::parse("file:///project/shop.proto").unwrap();
+        workspace.open_file(&uri, content).await.unwrap();
+
+        let select = |line: u32, character: u32| {
+            let params = SelectionRangeParams {
+                text_document: TextDocumentIdentifier { uri: uri.clone() },
+                positions: vec![Position { line, character }],
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            };
+            let selection = provide_selection_ranges(params, &workspace, Some(content)).unwrap().remove(0);
+            chain(content, &selection)
+        };
+
+        // Field name → field → oneof → message body → message → file
+        assert_eq!(select(9, 16), vec!["card", "shop.Card card = 2;", "7..10", "4..10", "3..11", "0..15"]);
+
+        // A qualified type name grows component by component
+        assert_eq!(select(9, 10)[..3], ["Card", "shop.Card", "shop.Card card = 2;"]);
+
+        // String literal → quoted → option entry → option list → brackets → field
+        assert_eq!(
+            select(5, 34),
+            vec![
+                "SKU",
+                "\"SKU\"",
+                "json_name = \"SKU\"",
+                "json_name = \"SKU\", deprecated = true",
+                "[json_name = \"SKU\", deprecated = true]",
+                "string sku = 1 [json_name = \"SKU\", deprecated = true];",
+                "4..6",
+                "4..10",
+                "3..11",
+                "0..15",
+            ]
+        );
+
+        // RPC parameter list
+        assert_eq!(
+            select(14, 12),
+            vec!["GetRequest", "(GetRequest)", "rpc Get(GetRequest) returns (Order);", "13..15", "0..15"]
+        );
+    }
+}
@@ -1,4 +1,6 @@
-use crate::workspace::WorkspaceManager;
+use crate::parser::proto::{EnumElement, MessageElement};
+use crate::parser::ParsedProto;
+use crate::workspace::{TypeTable, WorkspaceManager};
 use tower_lsp::lsp_types::*;
 
 /// Token types supported by our semantic tokens provider.
@@ -16,12 +18,15 @@ pub const TOKEN_TYPES: &[SemanticTokenType] = &[
     SemanticTokenType::COMMENT,        // 10: comments
 ];
 
-/// Token modifiers (none used currently, but required by the protocol).
+/// Token modifiers, as bit positions in `token_modifiers`.
 pub const TOKEN_MODIFIERS: &[SemanticTokenModifier] = &[
-    SemanticTokenModifier::DECLARATION,
-    SemanticTokenModifier::DEFINITION,
+    SemanticTokenModifier::DECLARATION, // bit 0
+    SemanticTokenModifier::DEFINITION,  // bit 1
+    SemanticTokenModifier::DEPRECATED,  // bit 2: deprecated symbols and their uses
 ];
 
+const MODIFIER_DEPRECATED: u32 = 1 << 2;
+
 const TOKEN_TYPE: u32 = 0;
 const TOKEN_ENUM: u32 = 1;
 const TOKEN_ENUM_MEMBER: u32 = 2;
@@ -41,7 +46,7 @@ pub fn provide_semantic_tokens_full(
     content: Option<&str>,
 ) -> Option<SemanticTokensResult> {
     let uri = &params.text_document.uri;
-    let _proto = workspace.get_file(uri)?;
+    let proto = workspace.get_file(uri)?;
     let content = content?;
 
     let mut tokens: Vec<RawToken> = Vec::new();
@@ -80,6 +85,8 @@ pub fn provide_semantic_tokens_full(
         tokenize_line(line, line_num, trimmed, &mut tokens);
     }
 
+    apply_deprecated_modifiers(&proto, workspace, uri, content, &mut tokens);
+
     // Sort tokens by position
     tokens.sort_by(|a, b| a.line.cmp(&b.line).then(a.start.cmp(&b.start)));
 
@@ -395,6 +402,74 @@ fn tokenize_field_parts(line: &str, line_num: u32, tokens: &mut Vec<RawToken>) {
     add_number_literals(line, line_num, tokens);
 }
 
+/// Mark tokens naming a deprecated symbol, either at its declaration or where a
+/// deprecated type is referenced. Imports are taken from the cache only.
+fn apply_deprecated_modifiers(
+    proto: &ParsedProto,
+    workspace: &WorkspaceManager,
+    uri: &Url,
+    content: &str,
+    tokens: &mut [RawToken],
+) {
+    let mut spans: Vec<(u32, &str)> = Vec::new();
+    for msg in &proto.messages {
+        collect_deprecated_in_message(msg, &mut spans);
+    }
+    for e in &proto.enums {
+        collect_deprecated_in_enum(e, &mut spans);
+    }
+    for svc in &proto.services {
+        for method in svc.methods.iter().filter(|m| m.deprecated) {
+            spans.push((method.line, &method.name));
+        }
+    }
+
+    let imports = workspace.collect_all_imports_cached(uri);
+    let table = TypeTable::from_files(std::iter::once(proto).chain(imports.iter().map(|p| p.as_ref())));
+    for type_ref in &proto.type_refs {
+        if table.resolve(&type_ref.name, &type_ref.scope).is_some_and(|def| def.deprecated) {
+            spans.push((type_ref.line, &type_ref.name));
+        }
+    }
+    if spans.is_empty() {
+        return;
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    for token in tokens.iter_mut().filter(|t| t.token_type != TOKEN_KEYWORD) {
+        let Some(line) = lines.get(token.line as usize) else { continue };
+        let start = token.start as usize;
+        let Some(text) = line.get(start..start + token.length as usize) else { continue };
+        if spans.iter().any(|(l, name)| *l == token.line && *name == text) {
+            token.token_modifiers |= MODIFIER_DEPRECATED;
+        }
+    }
+}
+
+fn collect_deprecated_in_message<'a>(msg: &'a MessageElement, spans: &mut Vec<(u32, &'a str)>) {
+    if msg.deprecated {
+        spans.push((msg.line, &msg.name));
+    }
+    for field in msg.fields.iter().filter(|f| f.deprecated) {
+        spans.push((field.line, &field.name));
+    }
+    for nested in &msg.nested_messages {
+        collect_deprecated_in_message(nested, spans);
+    }
+    for e in &msg.nested_enums {
+        collect_deprecated_in_enum(e, spans);
+    }
+}
+
+fn collect_deprecated_in_enum<'a>(e: &'a EnumElement, spans: &mut Vec<(u32, &'a str)>) {
+    if e.deprecated {
+        spans.push((e.line, &e.name));
+    }
+    for value in e.values.iter().filter(|v| v.deprecated) {
+        spans.push((value.line, &value.name));
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
@@ -559,6 +634,47 @@ mod tests {
         assert_eq!(result, vec!["GetUserRequest", "GetUserResponse"]);
     }
 
+    #[tokio::test]
+    async fn test_deprecated_modifier() {
+        let workspace = WorkspaceManager::new();
+        let uri = Url::parse("file:///test/tokens.proto").unwrap();
+        let content = r#"syntax = "proto3";
+message Old {
+  option deprecated = true;
+}
+message User {
+  Old old = 1;
+  string name = 2 [deprecated = true];
+  string email = 3;
+}
+"#;
+        workspace.open_file(&uri, content).await.unwrap();
+        let params = SemanticTokensParams {
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+        };
+        let Some(SemanticTokensResult::Tokens(result)) =
+            provide_semantic_tokens_full(params, &workspace, Some(content))
+        else {
+            panic!("expected tokens");
+        };
+
+        // Decode back to absolute positions and collect deprecated tokens
+        let lines: Vec<&str> = content.lines().collect();
+        let (mut line, mut start) = (0u32, 0u32);
+        let mut deprecated = Vec::new();
+        for token in &result.data {
+            line += token.delta_line;
+            start = if token.delta_line == 0 { start + token.delta_start } else { token.delta_start };
+            if token.token_modifiers_bitset & MODIFIER_DEPRECATED != 0 {
+                let text = &lines[line as usize][start as usize..(start + token.length) as usize];
+                deprecated.push((line, text));
+            }
+        }
+        assert_eq!(deprecated, vec![(1, "Old"), (5, "Old"), (6, "name")]);
+    }
+
     #[test]
     fn test_is_builtin_type() {
         assert!(is_builtin_type("string"));
@@ -0,0 +1,292 @@
+use crate::features::blocks::open_blocks;
+use crate::features::completion::import_insertion_edits;
+use crate::features::numbering::{enclosing_numbered_block, EditedLine};
+use crate::parser::ParsedProto;
+use crate::workspace::Settings;
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, InsertTextFormat, Position, TextEdit,
+};
+
+/// Where a snippet may be inserted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SnippetScope {
+    TopLevel,
+    Message,
+    Service,
+}
+
+/// Snippet completions for the block constructs valid at `position`: top-level
+/// declarations at top level, nested declarations, `oneof` and `map` fields in a
+/// message, and `rpc` in a service. Only offered where a statement starts.
+/// `priority` prefixes the sort text so each snippet follows its keyword.
+pub fn snippet_completions(
+    proto: &ParsedProto,
+    content: &str,
+    position: Position,
+    settings: &Settings,
+    priority: &str,
+) -> Vec<CompletionItem> {
+    let line = content.lines().nth(position.line as usize).unwrap_or("");
+    let prefix = line.get(..(position.character as usize).min(line.len())).unwrap_or(line);
+    let typed = prefix.trim_start();
+    if !typed.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Vec::new();
+    }
+
+    let blocks = open_blocks(content, position);
+    let scope = match blocks.last().and_then(|h| h.split_whitespace().next()) {
+        None => SnippetScope::TopLevel,
+        Some("message") => SnippetScope::Message,
+        Some("service") => SnippetScope::Service,
+        // Enum, oneof, extend and rpc bodies hold plain declarations
+        _ => return Vec::new(),
+    };
+
+    // Fields inserted into the current message take its next free number
+    let number = enclosing_numbered_block(proto, content, position)
+        .and_then(|block| block.next_number(Some(EditedLine { line: position.line, text: line })))
+        .unwrap_or(1);
+
+    let mut items = Vec::new();
+    let mut push = |keyword: &str, label: &str, detail: &str, body: String| {
+        items.push(CompletionItem {
+            label: label.to_string(),
+            kind: Some(CompletionItemKind::SNIPPET),
+            detail: Some(detail.to_string()),
+            sort_text: Some(format!("{}{}~", priority, keyword)),
+            filter_text: Some(keyword.to_string()),
+            insert_text: Some(body),
+            insert_text_format: Some(InsertTextFormat::SNIPPET),
+            ..Default::default()
+        });
+    };
+
+    match scope {
+        SnippetScope::TopLevel | SnippetScope::Message => {
+            push(
+                "message",
+                "message",
+                "Message definition",
+                "message ${1:Name} {\n\t${2:string} ${3:name} = ${4:1};$0\n}".to_string(),
+            );
+            push(
+                "enum",
+                "enum",
+                "Enum definition with an unspecified zero value",
+                "enum ${1:Name} {\n\t${2:NAME}_UNSPECIFIED = 0;\n\t${2:NAME}_${3:VALUE} = ${4:1};$0\n}"
+                    .to_string(),
+            );
+            push(
+                "extend",
+                "extend",
+                "Extension of an options message",
+                "extend ${1:google.protobuf.FieldOptions} {\n\t${2:string} ${3:name} = ${4:50000};$0\n}"
+                    .to_string(),
+            );
+        }
+        SnippetScope::Service => {}
+    }
+
+    match scope {
+        SnippetScope::TopLevel => {
+            push(
+                "service",
+                "service",
+                "Service definition",
+                "service ${1:Name} {\n\trpc ${2:Method}(${3:Request}) returns (${4:Response});$0\n}"
+                    .to_string(),
+            );
+        }
+        SnippetScope::Message => {
+            push(
+                "oneof",
+                "oneof",
+                "Oneof with a first field",
+                format!("oneof ${{1:name}} {{\n\t${{2:string}} ${{3:field}} = ${{4:{}}};$0\n}}", number),
+            );
+            push(
+                "map",
+                "map",
+                "Map field",
+                format!("map<${{1:string}}, ${{2:string}}> ${{3:name}} = ${{4:{}}};$0", number),
+            );
+        }
+        SnippetScope::Service => {
+            push(
+                "rpc",
+                "rpc",
+                "RPC method",
+                "rpc ${1:Method}(${2:${1:Method}Request}) returns (${3:${1:Method}Response});$0"
+                    .to_string(),
+            );
+        }
+    }
+
+    if scope == SnippetScope::TopLevel && !settings.standard_methods.is_empty() {
+        items.push(standard_methods_item(proto, content, &settings.standard_methods, priority));
+    }
+
+    items
+}
+
+/// An AIP-style resource service with the configured standard methods and
+/// their request and response messages
+fn standard_methods_item(
+    proto: &ParsedProto,
+    content: &str,
+    methods: &[String],
+    priority: &str,
+) -> CompletionItem {
+    let has = |method: &str| methods.iter().any(|m| m == method);
+
+    let mut rpcs = String::new();
+    let mut messages = String::new();
+    for method in methods {
+        let (rpc, message) = match method.as_str() {
+            "Get" => (
+                "rpc Get${1:Book}(Get${1:Book}Request) returns (${1:Book});",
+                "message Get${1:Book}Request {\n\tstring name = 1;\n}",
+            ),
+            "List" => (
+                "rpc List${2:Books}(List${2:Books}Request) returns (List${2:Books}Response);",
+                "message List${2:Books}Request {\n\tstring parent = 1;\n\tint32 page_size = 2;\n\tstring page_token = 3;\n}\n\n\
+                 message List${2:Books}Response {\n\trepeated ${1:Book} ${3:books} = 1;\n\tstring next_page_token = 2;\n}",
+            ),
+            "Create" => (
+                "rpc Create${1:Book}(Create${1:Book}Request) returns (${1:Book});",
+                "message Create${1:Book}Request {\n\tstring parent = 1;\n\t${1:Book} ${4:book} = 2;\n}",
+            ),
+            "Update" => (
+                "rpc Update${1:Book}(Update${1:Book}Request) returns (${1:Book});",
+                "message Update${1:Book}Request {\n\t${1:Book} ${4:book} = 1;\n\tgoogle.protobuf.FieldMask update_mask = 2;\n}",
+            ),
+            "Delete" => (
+                "rpc Delete${1:Book}(Delete${1:Book}Request) returns (google.protobuf.Empty);",
+                "message Delete${1:Book}Request {\n\tstring name = 1;\n}",
+            ),
+            _ => continue,
+        };
+        rpcs.push_str(&format!("\t{}\n", rpc));
+        messages.push_str(&format!("\n\n{}", message));
+    }
+    let body = format!("service ${{1:Book}}Service {{\n{}}}{}$0", rpcs, messages);
+
+    // Well-known types used by the template, unless already imported
+    let mut imports = Vec::new();
+    if has("Update") {
+        imports.push("google/protobuf/field_mask.proto");
+    }
+    if has("Delete") {
+        imports.push("google/protobuf/empty.proto");
+    }
+    imports.retain(|path| !proto.imports.iter().any(|i| i.path == *path));
+    let additional_text_edits: Vec<TextEdit> = if imports.is_empty() {
+        Vec::new()
+    } else {
+        import_insertion_edits(content, &imports)
+    };
+
+    CompletionItem {
+        label: "service (standard methods)".to_string(),
+        kind: Some(CompletionItemKind::SNIPPET),
+        detail: Some(format!("Resource service with {}", methods.join(", "))),
+        sort_text: Some(format!("{}service~~", priority)),
+        filter_text: Some("service".to_string()),
+        insert_text: Some(body),
+        insert_text_format: Some(InsertTextFormat::SNIPPET),
+        additional_text_edits: (!additional_text_edits.is_empty()).then_some(additional_text_edits),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn snippets_at(content: &str, line: u32, character: u32, settings: &Settings) -> Vec<CompletionItem> {
+        let proto = ParsedProto::parse("file:///test.proto".to_string(), content).await.unwrap();
+        snippet_completions(&proto, content, Position { line, character }, settings, "0")
+    }
+
+    fn labels(items: &[CompletionItem]) -> Vec<&str> {
+        items.iter().map(|i| i.label.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_snippet_scopes() {
+        let content = r#"syntax = "proto3";
+package test;
+
+import "google/protobuf/empty.proto";
+
+message User {
+  string name = 1;
+
+}
+
+service Users {
+
+}
+
+enum Kind {
+
+}
+"#;
+        let settings = Settings::default();
+
+        let top = snippets_at(content, 2, 0, &settings).await;
+        assert_eq!(
+            labels(&top),
+            vec!["message", "enum", "extend", "service", "service (standard methods)"]
+        );
+
+        let message = snippets_at(content, 7, 2, &settings).await;
+        assert_eq!(labels(&message), vec!["message", "enum", "extend", "oneof", "map"]);
+        // Field numbers continue from the message
+        assert!(message[4].insert_text.as_ref().unwrap().contains("${4:2}"));
+
+        let service = snippets_at(content, 11, 2, &settings).await;
+        assert_eq!(labels(&service), vec!["rpc"]);
+
+        assert!(snippets_at(content, 15, 2, &settings).await.is_empty());
+        // Not at the start of a statement
+        assert!(snippets_at(content, 6, 9, &settings).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_snippet_number_after_inserted_line() {
+        let parsed = "syntax = \"proto3\";\n\nmessage User {\n  string name = 1;\n}\n";
+        let proto = ParsedProto::parse("file:///test.proto".to_string(), parsed).await.unwrap();
+        // A new line above `name`, which the last good parse still places here
+        let live = "syntax = \"proto3\";\n\nmessage User {\n  ma\n  string name = 1;\n}\n";
+        let items = snippet_completions(&proto, live, Position { line: 3, character: 4 }, &Settings::default(), "0");
+        let map = items.iter().find(|i| i.label == "map").unwrap();
+        assert!(map.insert_text.as_ref().unwrap().contains("${4:2}"));
+    }
+
+    #[tokio::test]
+    async fn test_standard_methods_snippet() {
+        let content = "syntax = \"proto3\";\npackage test;\n\nimport \"google/protobuf/empty.proto\";\n\n";
+        let mut settings = Settings {
+            standard_methods: vec!["Get".to_string(), "Update".to_string(), "Delete".to_string()],
+            ..Settings::default()
+        };
+
+        let items = snippets_at(content, 5, 0, &settings).await;
+        let item = items.iter().find(|i| i.label == "service (standard methods)").unwrap();
+        let body = item.insert_text.as_ref().unwrap();
+        assert!(body.contains("rpc Get${1:Book}"));
+        assert!(body.contains("message Update${1:Book}Request"));
+        assert!(!body.contains("List"));
+
+        // Only the import that is still missing is added
+        let edits = item.additional_text_edits.as_ref().unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].new_text, "import \"google/protobuf/field_mask.proto\";\n");
+        assert_eq!(edits[0].range.start, Position { line: 4, character: 0 });
+
+        settings.standard_methods.clear();
+        let items = snippets_at(content, 5, 0, &settings).await;
+        assert!(!items.iter().any(|i| i.label == "service (standard methods)"));
+    }
+}
@@ -0,0 +1,319 @@
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+
+/// Comment directive suppressing diagnostics on its own line, or on the next line
+/// when the comment stands alone: `// protobuf-lsp:ignore field-lower-snake-case`
+pub const IGNORE_DIRECTIVE: &str = "protobuf-lsp:ignore";
+
+/// Comment directive suppressing diagnostics anywhere in the file
+pub const IGNORE_FILE_DIRECTIVE: &str = "protobuf-lsp:ignore-file";
+
+/// A parsed suppression comment
+#[derive(Debug, Clone)]
+pub struct Suppression {
+    /// Codes listed after the directive, with their column ranges. No codes
+    /// suppresses every diagnostic.
+    pub codes: Vec<SuppressedCode>,
+    /// Line the suppression applies to, or `None` for file-level suppressions
+    pub target_line: Option<u32>,
+    /// Line of the comment itself
+    pub line: u32,
+    /// Points to the `//` that starts the comment
+    pub character: u32,
+    /// Column just past the end of the comment
+    pub end_character: u32,
+    /// Whether the comment is the only thing on its line
+    pub own_line: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SuppressedCode {
+    /// Normalized code, see `normalize_code`
+    pub code: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Suppression {
+    fn matches(&self, code: &str) -> bool {
+        self.codes.is_empty() || self.codes.iter().any(|c| c.code == code)
+    }
+}
+
+/// Normalize a diagnostic code so `FIELD_LOWER_SNAKE_CASE` and
+/// `field-lower-snake-case` refer to the same rule.
+pub fn normalize_code(code: &str) -> String {
+    code.trim().to_ascii_lowercase().replace('_', "-")
+}
+
+/// Find every suppression comment in `content`.
+pub fn parse_suppressions(content: &str) -> Vec<Suppression> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut suppressions = Vec::new();
+
+    for (line_num, line) in lines.iter().enumerate() {
+        let Some(comment_start) = find_line_comment(line) else { continue };
+        let comment = &line[comment_start + 2..];
+        let body = comment.trim_start();
+        let body_start = comment_start + 2 + (comment.len() - body.len());
+
+        let (is_file, keyword_len) = if body.starts_with(IGNORE_FILE_DIRECTIVE) {
+            (true, IGNORE_FILE_DIRECTIVE.len())
+        } else if body.starts_with(IGNORE_DIRECTIVE) {
+            (false, IGNORE_DIRECTIVE.len())
+        } else {
+            continue;
+        };
+        // Reject e.g. `protobuf-lsp:ignored`
+        if body[keyword_len..].starts_with(|c: char| !c.is_whitespace() && c != ',') {
+            continue;
+        }
+
+        let directive_end = body_start + keyword_len;
+        let mut codes = Vec::new();
+        let mut token_start: Option<usize> = None;
+        let tail = line[directive_end..].char_indices();
+        for (i, ch) in tail.chain(std::iter::once((line.len() - directive_end, ' '))) {
+            let abs = directive_end + i;
+            if ch.is_whitespace() || ch == ',' {
+                if let Some(start) = token_start.take() {
+                    codes.push(SuppressedCode {
+                        code: normalize_code(&line[start..abs]),
+                        start: start as u32,
+                        end: abs as u32,
+                    });
+                }
+            } else if token_start.is_none() {
+                token_start = Some(abs);
+            }
+        }
+
+        let own_line = line[..comment_start].trim().is_empty();
+        let target_line = if is_file {
+            None
+        } else if own_line {
+            // Applies to the next line of code, skipping other comment lines
+            let next = lines
+                .iter()
+                .enumerate()
+                .skip(line_num + 1)
+                .find(|(_, l)| !l.trim_start().starts_with("//"))
+                .map(|(i, _)| i)
+                .unwrap_or(line_num + 1);
+            Some(next as u32)
+        } else {
+            Some(line_num as u32)
+        };
+
+        suppressions.push(Suppression {
+            codes,
+            target_line,
+            line: line_num as u32,
+            character: comment_start as u32,
+            end_character: line.trim_end().len() as u32,
+            own_line,
+        });
+    }
+
+    suppressions
+}
+
+/// Drop diagnostics covered by a suppression comment and report suppressions
+/// that no longer match anything.
+pub fn apply_suppressions(content: &str, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    let suppressions = parse_suppressions(content);
+    if suppressions.is_empty() {
+        return diagnostics;
+    }
+
+    // used[i][j]: code j of suppression i matched (j = 0 for code-less suppressions)
+    let mut used: Vec<Vec<bool>> = suppressions
+        .iter()
+        .map(|s| vec![false; s.codes.len().max(1)])
+        .collect();
+
+    let mut kept = Vec::with_capacity(diagnostics.len());
+    for diagnostic in diagnostics {
+        let code = match &diagnostic.code {
+            Some(NumberOrString::String(code)) => normalize_code(code),
+            Some(NumberOrString::Number(n)) => n.to_string(),
+            None => String::new(),
+        };
+        let line = diagnostic.range.start.line;
+        let mut suppressed = false;
+        for (i, suppression) in suppressions.iter().enumerate() {
+            let applies = suppression.target_line.is_none_or(|target| target == line);
+            if !applies || !suppression.matches(&code) {
+                continue;
+            }
+            suppressed = true;
+            let index = suppression.codes.iter().position(|c| c.code == code).unwrap_or(0);
+            used[i][index] = true;
+        }
+        if !suppressed {
+            kept.push(diagnostic);
+        }
+    }
+
+    for (suppression, used) in suppressions.iter().zip(&used) {
+        let scope = if suppression.target_line.is_some() { "on this line" } else { "in this file" };
+        if suppression.codes.is_empty() {
+            if !used[0] {
+                kept.push(unused_suppression_diagnostic(
+                    suppression,
+                    suppression.character,
+                    suppression.end_character,
+                    None,
+                    format!("Unused suppression: no diagnostics {}", scope),
+                ));
+            }
+            continue;
+        }
+        for (code, used) in suppression.codes.iter().zip(used) {
+            if !used {
+                kept.push(unused_suppression_diagnostic(
+                    suppression,
+                    code.start,
+                    code.end,
+                    Some(&code.code),
+                    format!("Unused suppression: no '{}' diagnostic {}", code.code, scope),
+                ));
+            }
+        }
+    }
+
+    kept
+}
+
+fn unused_suppression_diagnostic(
+    suppression: &Suppression,
+    start: u32,
+    end: u32,
+    code: Option<&str>,
+    message: String,
+) -> Diagnostic {
+    Diagnostic {
+        range: Range {
+            start: Position {
+                line: suppression.line,
+                character: start,
+            },
+            end: Position {
+                line: suppression.line,
+                character: end,
+            },
+        },
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(NumberOrString::String("unused-suppression".to_string())),
+        source: Some("protobuf-lsp".to_string()),
+        message,
+        related_information: None,
+        tags: None,
+        code_description: None,
+        data: code.map(|c| serde_json::json!({ "code": c })),
+    }
+}
+
+/// Byte offset of a `//` comment on the line, ignoring `//` inside string literals
+fn find_line_comment(line: &str) -> Option<usize> {
+    let bytes = line.as_bytes();
+    let mut quote: Option<u8> = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        match quote {
+            Some(_) if b == b'\\' => i += 1,
+            Some(q) if b == q => quote = None,
+            Some(_) => {}
+            None if b == b'"' || b == b'\'' => quote = Some(b),
+            None if b == b'/' && bytes.get(i + 1) == Some(&b'/') => return Some(i),
+            None => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(line: u32, code: &str) -> Diagnostic {
+        Diagnostic {
+            range: Range {
+                start: Position { line, character: 2 },
+                end: Position { line, character: 6 },
+            },
+            code: Some(NumberOrString::String(code.to_string())),
+            source: Some("protobuf-lsp".to_string()),
+            message: code.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<(u32, String)> {
+        diagnostics
+            .iter()
+            .map(|d| match &d.code {
+                Some(NumberOrString::String(s)) => (d.range.start.line, s.clone()),
+                _ => (d.range.start.line, String::new()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_suppressions() {
+        let content = r#"// protobuf-lsp:ignore-file DUPLICATE_MESSAGE
+message A {
+  // protobuf-lsp:ignore a-rule, b-rule
+  // regular comment
+  string name = 1; // protobuf-lsp:ignore
+  string url = 2 [default = "http://x"];
+  // protobuf-lsp:ignored not-a-directive
+}
+"#;
+        let suppressions = parse_suppressions(content);
+        assert_eq!(suppressions.len(), 3);
+
+        assert_eq!(suppressions[0].target_line, None);
+        assert_eq!(suppressions[0].codes[0].code, "duplicate-message");
+
+        assert_eq!(suppressions[1].target_line, Some(4));
+        let listed: Vec<&str> = suppressions[1].codes.iter().map(|c| c.code.as_str()).collect();
+        assert_eq!(listed, vec!["a-rule", "b-rule"]);
+        assert_eq!(suppressions[1].codes[1].start, 33);
+        assert!(suppressions[1].own_line);
+
+        assert_eq!(suppressions[2].target_line, Some(4));
+        assert!(suppressions[2].codes.is_empty());
+        assert!(!suppressions[2].own_line);
+    }
+
+    #[test]
+    fn test_apply_suppressions() {
+        let content = r#"// protobuf-lsp:ignore-file file-rule
+message A {
+  // protobuf-lsp:ignore line-rule unused-rule
+  string name = 1;
+  string other = 2; // protobuf-lsp:ignore
+}
+"#;
+        let diagnostics = vec![
+            diagnostic(1, "file-rule"),
+            diagnostic(3, "line-rule"),
+            diagnostic(3, "other-rule"),
+            diagnostic(4, "any-rule"),
+            diagnostic(5, "line-rule"),
+        ];
+        let result = apply_suppressions(content, diagnostics);
+        assert_eq!(
+            codes(&result),
+            vec![
+                (3, "other-rule".to_string()),
+                (5, "line-rule".to_string()),
+                (2, "unused-suppression".to_string()),
+            ]
+        );
+        assert!(result[2].message.contains("unused-rule"));
+        assert_eq!(result[2].data, Some(serde_json::json!({ "code": "unused-rule" })));
+    }
+}
@@ -0,0 +1,20 @@
+//! Fixtures shared by the feature tests
+
+use crate::workspace::WorkspaceManager;
+use std::path::Path;
+use tower_lsp::lsp_types::Url;
+
+/// Writes each `(path, content)` under `dir`, creating its directories, opens
+/// it in `workspace` and returns the URIs in the same order
+pub async fn open_files(workspace: &WorkspaceManager, dir: &Path, files: &[(&str, &str)]) -> Vec<Url> {
+    let mut uris = Vec::new();
+    for (path, content) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        let uri = Url::from_file_path(&path).unwrap();
+        workspace.open_file(&uri, content).await.unwrap();
+        uris.push(uri);
+    }
+    uris
+}
@@ -0,0 +1,310 @@
+//! Protobuf text format, the syntax of aggregate option values such as
+//! `option (my.config) = { retries: 3 backoff { base_ms: 100 } };`. Parsing is
+//! tolerant: it stops at the first token that does not fit and keeps what it
+//! has read so far.
+
+/// Kind of a text format token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Identifier,
+    /// A quoted string; the text keeps its quotes
+    String,
+    Number,
+    /// Any other single character
+    Symbol,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub line: u32,
+    /// UTF-16 column, as LSP positions count
+    pub character: u32,
+    /// Byte offset in the line, as the parsed elements count columns
+    pub offset: u32,
+}
+
+impl Token<'_> {
+    pub fn is(&self, symbol: &str) -> bool {
+        self.kind == TokenKind::Symbol && self.text == symbol
+    }
+
+    /// UTF-16 column just past the token
+    pub fn end_character(&self) -> u32 {
+        self.character + self.text.encode_utf16().count() as u32
+    }
+}
+
+/// A `name: value` entry of a message value
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextField<'a> {
+    /// The field name, or the `[` opening an extension or `Any` type name
+    pub name: Token<'a>,
+    pub extension: bool,
+    /// Whether the values were written as a `[a, b]` list
+    pub list: bool,
+    pub values: Vec<TextValue<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextValue<'a> {
+    /// A string, number or identifier; `negative` when preceded by `-`
+    Scalar { token: Token<'a>, negative: bool },
+    /// `{ … }` or `< … >`; `open` is the opening bracket
+    Message { open: Token<'a>, fields: Vec<TextField<'a>> },
+}
+
+/// Aggregate option values in `content`: every `{ … }` directly following an
+/// `=`, with the opening brace and the parsed fields
+pub fn aggregate_values(content: &str) -> Vec<(Token<'_>, Vec<TextField<'_>>)> {
+    let tokens = tokenize(content);
+    let mut values = Vec::new();
+    let mut i = 0;
+    while i + 1 < tokens.len() {
+        if tokens[i].is("=") && tokens[i + 1].is("{") {
+            let open = tokens[i + 1];
+            let mut pos = i + 2;
+            let fields = parse_fields(&tokens, &mut pos, "}");
+            values.push((open, fields));
+            i = pos.max(i + 2);
+        } else {
+            i += 1;
+        }
+    }
+    values
+}
+
+/// Split `content` into tokens, skipping whitespace and comments
+pub fn tokenize(content: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut in_block_comment = false;
+
+    for (line_index, line) in content.lines().enumerate() {
+        let bytes = line.as_bytes();
+        // UTF-16 column of byte `column_offset`, advanced as tokens are found
+        let (mut column_offset, mut column) = (0, 0);
+        let mut i = 0;
+        while i < bytes.len() {
+            if in_block_comment {
+                // Bytes, not `line[i..]`: `i` may be inside a multi-byte character
+                if bytes[i..].starts_with(b"*/") {
+                    in_block_comment = false;
+                    i += 2;
+                } else {
+                    i += 1;
+                }
+                continue;
+            }
+            let b = bytes[i];
+            let start = i;
+            let kind = match b {
+                _ if b.is_ascii_whitespace() => {
+                    i += 1;
+                    continue;
+                }
+                b'/' if line[i..].starts_with("//") => break,
+                b'/' if line[i..].starts_with("/*") => {
+                    in_block_comment = true;
+                    i += 2;
+                    continue;
+                }
+                b'"' | b'\'' => {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != b {
+                        i += if bytes[i] == b'\\' { 2 } else { 1 };
+                    }
+                    i = (i + 1).min(bytes.len());
+                    TokenKind::String
+                }
+                _ if b.is_ascii_digit() || (b == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)) => {
+                    while i < bytes.len() {
+                        let c = bytes[i];
+                        let exponent_sign = (c == b'+' || c == b'-')
+                            && matches!(bytes[i - 1], b'e' | b'E')
+                            && !line[start..].starts_with("0x")
+                            && !line[start..].starts_with("0X");
+                        if c.is_ascii_alphanumeric() || c == b'.' || c == b'_' || exponent_sign {
+                            i += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                    TokenKind::Number
+                }
+                _ if b.is_ascii_alphabetic() || b == b'_' => {
+                    while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                        i += 1;
+                    }
+                    TokenKind::Identifier
+                }
+                _ => {
+                    i += line[i..].chars().next().map_or(1, char::len_utf8);
+                    TokenKind::Symbol
+                }
+            };
+            column += line[column_offset..start].encode_utf16().count();
+            column_offset = start;
+            tokens.push(Token {
+                kind,
+                text: &line[start..i],
+                line: line_index as u32,
+                character: column as u32,
+                offset: start as u32,
+            });
+        }
+    }
+    tokens
+}
+
+/// Fields up to the `close` symbol, which is consumed
+fn parse_fields<'a>(tokens: &[Token<'a>], pos: &mut usize, close: &str) -> Vec<TextField<'a>> {
+    let mut fields = Vec::new();
+    while let Some(token) = tokens.get(*pos) {
+        if token.is(close) {
+            *pos += 1;
+            break;
+        }
+        if token.is(",") || token.is(";") {
+            *pos += 1;
+            continue;
+        }
+
+        let name = *token;
+        let extension = name.is("[");
+        if extension {
+            // `[pkg.ext]` or `[type.googleapis.com/pkg.Type]`
+            while tokens.get(*pos).is_some_and(|t| !t.is("]")) {
+                *pos += 1;
+            }
+        } else if name.kind != TokenKind::Identifier {
+            break;
+        }
+        *pos += 1;
+
+        let colon = tokens.get(*pos).is_some_and(|t| t.is(":"));
+        if colon {
+            *pos += 1;
+        }
+        let list = tokens.get(*pos).is_some_and(|t| t.is("["));
+        let values = if list {
+            *pos += 1;
+            let mut values = Vec::new();
+            while let Some(token) = tokens.get(*pos) {
+                if token.is("]") {
+                    *pos += 1;
+                    break;
+                }
+                if token.is(",") {
+                    *pos += 1;
+                    continue;
+                }
+                match parse_value(tokens, pos) {
+                    Some(value) => values.push(value),
+                    None => break,
+                }
+            }
+            values
+        } else {
+            match parse_value(tokens, pos) {
+                // A scalar needs the colon: `a b` is two field names
+                Some(TextValue::Scalar { .. }) if !colon => return fields,
+                Some(value) => vec![value],
+                None => {
+                    fields.push(TextField { name, extension, list, values: Vec::new() });
+                    break;
+                }
+            }
+        };
+        fields.push(TextField { name, extension, list, values });
+    }
+    fields
+}
+
+fn parse_value<'a>(tokens: &[Token<'a>], pos: &mut usize) -> Option<TextValue<'a>> {
+    let token = *tokens.get(*pos)?;
+    if token.is("{") || token.is("<") {
+        *pos += 1;
+        let close = if token.is("{") { "}" } else { ">" };
+        let fields = parse_fields(tokens, pos, close);
+        return Some(TextValue::Message { open: token, fields });
+    }
+
+    let negative = token.is("-");
+    let token = if negative { *tokens.get(*pos + 1)? } else { token };
+    if token.kind == TokenKind::Symbol {
+        return None;
+    }
+    *pos += if negative { 2 } else { 1 };
+    // Adjacent strings are concatenated
+    if token.kind == TokenKind::String {
+        while tokens.get(*pos).is_some_and(|t| t.kind == TokenKind::String) {
+            *pos += 1;
+        }
+    }
+    Some(TextValue::Scalar { token, negative })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names<'a>(fields: &[TextField<'a>]) -> Vec<&'a str> {
+        fields.iter().map(|f| f.name.text).collect()
+    }
+
+    #[test]
+    fn test_aggregate_values() {
+        let content = r#"option (my.config) = {
+  retries: 3 // comment
+  backoff { base_ms: 100 factor: -1.5e-2 }
+  tags: ["a" "b", 'c']
+  [my.ext]: true
+};
+option java_package = "x";
+"#;
+        let values = aggregate_values(content);
+        assert_eq!(values.len(), 1);
+        let (open, fields) = &values[0];
+        assert_eq!((open.line, open.character), (0, 21));
+        assert_eq!(names(fields), vec!["retries", "backoff", "tags", "["]);
+
+        let TextValue::Message { fields: backoff, .. } = &fields[1].values[0] else {
+            panic!("expected a message value");
+        };
+        assert_eq!(names(backoff), vec!["base_ms", "factor"]);
+        assert_eq!(
+            backoff[1].values[0],
+            TextValue::Scalar {
+                token: Token { kind: TokenKind::Number, text: "1.5e-2", line: 2, character: 34, offset: 34 },
+                negative: true
+            }
+        );
+
+        assert!(fields[2].list);
+        assert_eq!(fields[2].values.len(), 2);
+        assert!(fields[3].extension);
+    }
+
+    #[test]
+    fn test_tokenize_non_ascii() {
+        let content = "/* café 注释 */ message Foo {\n  string s = 1; /* ☕\n é */ int32 n = 2;\n}\n";
+        let tokens = tokenize(content);
+        let texts: Vec<&str> = tokens.iter().map(|t| t.text).collect();
+        assert_eq!(
+            texts,
+            vec!["message", "Foo", "{", "string", "s", "=", "1", ";", "int32", "n", "=", "2", ";", "}"]
+        );
+
+        // Columns count UTF-16 code units; offsets count bytes
+        let message = tokens[0];
+        assert_eq!((message.character, message.offset), (14, 19));
+        let int32 = tokens.iter().find(|t| t.text == "int32").unwrap();
+        assert_eq!((int32.line, int32.character, int32.offset), (2, 6, 7));
+
+        let strings = tokenize("x = \"日本\" y");
+        assert_eq!(strings[2].text, "\"日本\"");
+        assert_eq!((strings[2].character, strings[2].end_character()), (4, 8));
+        assert_eq!((strings[3].character, strings[3].offset), (9, 13));
+    }
+}
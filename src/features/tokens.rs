@@ -0,0 +1,79 @@
+//! Locating statements and names in proto source: the tokens of a statement,
+//! the column of a field's name, and LSP columns of parsed byte offsets.
+
+use crate::features::text_format::{Token, TokenKind};
+
+/// Index of the token starting exactly at `line` and byte column `character`,
+/// as the parsed elements give them
+pub fn token_at(tokens: &[Token], line: u32, character: u32) -> Option<usize> {
+    let i = tokens.partition_point(|t| (t.line, t.offset) < (line, character));
+    tokens.get(i).filter(|t| (t.line, t.offset) == (line, character)).map(|_| i)
+}
+
+/// Last token of the statement starting at `start`: its `;`, or the `}`
+/// closing its body. Brackets are balanced on the way.
+pub fn statement_end(tokens: &[Token], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        if token.kind != TokenKind::Symbol {
+            continue;
+        }
+        match token.text {
+            "{" | "[" | "(" => depth += 1,
+            "}" | "]" | ")" => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 && token.text == "}" {
+                    return Some(i);
+                }
+            }
+            ";" if depth == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Byte column of the field name in a field line: the last whole-word
+/// occurrence before the `=`, so a type spelled like the field is skipped
+pub fn field_name_column(line: &str, name: &str) -> Option<usize> {
+    let before_number = &line[..line.find('=').unwrap_or(line.len())];
+    let is_word_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    before_number.rmatch_indices(name).map(|(start, _)| start).find(|&start| {
+        !before_number[..start].ends_with(is_word_char) && !before_number[start + name.len()..].starts_with(is_word_char)
+    })
+}
+
+/// UTF-16 column of the byte `offset` in `line`
+pub fn utf16_column(line: &str, offset: usize) -> u32 {
+    line.get(..offset).map_or(offset, |before| before.encode_utf16().count()) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::text_format::tokenize;
+
+    #[test]
+    fn test_field_name_column() {
+        assert_eq!(field_name_column("  optional int32 extra = 100;", "extra"), Some(17));
+        assert_eq!(field_name_column("  Foo Foo = 1;", "Foo"), Some(6));
+        assert_eq!(field_name_column("  int32 extras = 1;", "extra"), None);
+    }
+
+    #[test]
+    fn test_statement_end() {
+        let tokens = tokenize("message A {\n  map<string, B> b = 1 [(x) = { y: 1 }];\n}\nenum E { X = 0; }\n");
+        let message = token_at(&tokens, 0, 0).unwrap();
+        assert_eq!(tokens[statement_end(&tokens, message).unwrap()].line, 2);
+        let field = token_at(&tokens, 1, 2).unwrap();
+        let end = &tokens[statement_end(&tokens, field).unwrap()];
+        assert_eq!((end.line, end.text), (1, ";"));
+        assert_eq!(token_at(&tokens, 1, 3), None);
+    }
+
+    #[test]
+    fn test_utf16_column() {
+        assert_eq!(utf16_column("  /* 単価 */ Money", 15), 11);
+        assert_eq!(utf16_column("abc", 10), 10);
+    }
+}
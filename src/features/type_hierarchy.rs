@@ -0,0 +1,299 @@
+//! Type hierarchy over containment and extensions: a message's subtypes are
+//! the messages and enums nested in it and the `extend` blocks targeting it;
+//! supertypes go back up to the enclosing message or, from an `extend` block
+//! or one of its fields, to the extended message.
+
+use crate::features::call_hierarchy::{name_range, type_declaration};
+use crate::features::references::{for_each_type_reference, reference_target, word_at, ReferenceTarget};
+use crate::features::tokens::field_name_column;
+use crate::parser::proto::{ExtendElement, FieldElement, MessageElement, TypeReferenceKind};
+use crate::parser::ParsedProto;
+use crate::workspace::{TypeTable, WorkspaceManager};
+use serde::{Deserialize, Serialize};
+use tower_lsp::lsp_types::*;
+
+/// Identifies the declaration behind a type hierarchy item within its file
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum TypeItemData {
+    /// A message or enum
+    Type { full_name: String },
+    /// A top-level `extend` block, by the line it starts on
+    Extend { line: u32 },
+    /// A field of the `extend` block starting on `line`
+    ExtensionField { line: u32, name: String },
+}
+
+/// Items for the symbol at the cursor: an extension field name, a message or
+/// enum (its declaration or any reference to it), or the `extend` keyword of
+/// an `extend` block
+pub async fn prepare_type_hierarchy(
+    params: TypeHierarchyPrepareParams,
+    workspace: &WorkspaceManager,
+    content: Option<&str>,
+) -> Option<Vec<TypeHierarchyItem>> {
+    let uri = &params.text_document_position_params.text_document.uri;
+    let position = params.text_document_position_params.position;
+    let content = content?;
+    let proto = workspace.get_file(uri)?;
+
+    let line = content.lines().nth(position.line as usize)?;
+    let word = word_at(line, position.character as usize).map(|(_, word)| word);
+    for ext in &proto.extends {
+        if let Some(field) = ext.fields.iter().find(|f| f.line == position.line && Some(f.name.as_str()) == word) {
+            return Some(vec![field_item(&proto, content, ext, field)?]);
+        }
+    }
+
+    if let Some(ReferenceTarget::Type { full_name, uri, .. }) = reference_target(uri, workspace, content, position).await {
+        let file = workspace.get_file(&Url::parse(&uri).ok()?)?;
+        return Some(vec![type_item(&file, &full_name)?]);
+    }
+
+    let ext = proto.extends.iter().find(|ext| ext.line == position.line)?;
+    Some(vec![extend_item(&proto, ext)?])
+}
+
+/// The message enclosing a nested type, or the message extended by an
+/// `extend` block or extension field
+pub async fn supertypes(
+    params: TypeHierarchySupertypesParams,
+    workspace: &WorkspaceManager,
+) -> Option<Vec<TypeHierarchyItem>> {
+    let uri = &params.item.uri;
+    let proto = workspace.get_file(uri)?;
+
+    let item = match item_data(&params.item)? {
+        TypeItemData::Type { full_name } => {
+            let (parent, _) = full_name.rsplit_once('.')?;
+            type_item(&proto, parent).filter(|item| item.kind == SymbolKind::CLASS)
+        }
+        TypeItemData::Extend { line } | TypeItemData::ExtensionField { line, .. } => {
+            let type_ref = proto
+                .type_refs
+                .iter()
+                .find(|r| r.kind == TypeReferenceKind::Extendee && r.line == line)?;
+            let mut files = vec![proto.clone()];
+            files.extend(workspace.collect_all_imports_async(uri).await);
+            let table = TypeTable::from_files(files.iter().map(|f| f.as_ref()));
+            let def = table.resolve(&type_ref.name, &type_ref.scope)?;
+            let file = workspace.get_file(&Url::parse(&def.uri).ok()?)?;
+            type_item(&file, &def.full_name)
+        }
+    };
+    Some(item.into_iter().collect())
+}
+
+/// The messages and enums nested in a message followed by the `extend` blocks
+/// targeting it across the workspace, or the fields of an `extend` block
+pub async fn subtypes(
+    params: TypeHierarchySubtypesParams,
+    workspace: &WorkspaceManager,
+) -> Option<Vec<TypeHierarchyItem>> {
+    let uri = &params.item.uri;
+    let proto = workspace.get_file(uri)?;
+
+    match item_data(&params.item)? {
+        TypeItemData::Type { full_name } => {
+            let Some(message) = find_message(&proto.messages, &full_name) else { return Some(Vec::new()) };
+            let mut items: Vec<TypeHierarchyItem> = message
+                .nested_messages
+                .iter()
+                .map(|m| &m.full_name)
+                .chain(message.nested_enums.iter().map(|e| &e.full_name))
+                .filter_map(|nested| type_item(&proto, nested))
+                .collect();
+
+            let mut extendees = Vec::new();
+            for_each_type_reference(&full_name, workspace, |location, _, type_ref, _| {
+                if type_ref.kind == TypeReferenceKind::Extendee {
+                    extendees.push((location.uri, type_ref.line));
+                }
+            })
+            .await;
+            for (uri, line) in extendees {
+                let Some(file) = workspace.get_file(&uri) else { continue };
+                // Only top-level `extend` blocks are kept by the parser
+                if let Some(ext) = file.extends.iter().find(|ext| ext.line == line) {
+                    items.extend(extend_item(&file, ext));
+                }
+            }
+            Some(items)
+        }
+        TypeItemData::Extend { line } => {
+            let content = workspace.get_content(uri)?;
+            let ext = proto.extends.iter().find(|ext| ext.line == line)?;
+            Some(ext.fields.iter().filter_map(|field| field_item(&proto, &content, ext, field)).collect())
+        }
+        TypeItemData::ExtensionField { .. } => Some(Vec::new()),
+    }
+}
+
+fn find_message<'a>(messages: &'a [MessageElement], full_name: &str) -> Option<&'a MessageElement> {
+    messages.iter().find_map(|msg| {
+        if msg.full_name == full_name {
+            Some(msg)
+        } else {
+            find_message(&msg.nested_messages, full_name)
+        }
+    })
+}
+
+fn type_item(proto: &ParsedProto, full_name: &str) -> Option<TypeHierarchyItem> {
+    let (name, kind, range, selection_range) = type_declaration(proto, full_name)?;
+    Some(TypeHierarchyItem {
+        name: name.to_string(),
+        kind,
+        tags: None,
+        detail: Some(full_name.to_string()),
+        uri: Url::parse(&proto.uri).ok()?,
+        range,
+        selection_range,
+        data: to_data(TypeItemData::Type { full_name: full_name.to_string() }),
+    })
+}
+
+fn extend_item(proto: &ParsedProto, ext: &ExtendElement) -> Option<TypeHierarchyItem> {
+    Some(TypeHierarchyItem {
+        name: format!("extend {}", ext.name),
+        kind: SymbolKind::OBJECT,
+        tags: None,
+        detail: Some(format!("{} field{}", ext.fields.len(), if ext.fields.len() == 1 { "" } else { "s" })),
+        uri: Url::parse(&proto.uri).ok()?,
+        range: Range {
+            start: Position {
+                line: ext.line,
+                character: ext.character.saturating_sub("extend ".len() as u32),
+            },
+            end: Position { line: ext.end_line, character: 0 },
+        },
+        selection_range: name_range(ext.line, ext.character, ext.name.len()),
+        data: to_data(TypeItemData::Extend { line: ext.line }),
+    })
+}
+
+fn field_item(proto: &ParsedProto, content: &str, ext: &ExtendElement, field: &FieldElement) -> Option<TypeHierarchyItem> {
+    let line = content.lines().nth(field.line as usize)?;
+    let name_start = field_name_column(line, &field.name)?;
+    Some(TypeHierarchyItem {
+        name: field.name.clone(),
+        kind: SymbolKind::FIELD,
+        tags: None,
+        detail: Some(format!("{} = {}", field.field_type, field.number)),
+        uri: Url::parse(&proto.uri).ok()?,
+        range: Range {
+            start: Position { line: field.line, character: field.character.min(name_start as u32) },
+            end: Position { line: field.line, character: line.trim_end().len() as u32 },
+        },
+        selection_range: name_range(field.line, name_start as u32, field.name.len()),
+        data: to_data(TypeItemData::ExtensionField { line: ext.line, name: field.name.clone() }),
+    })
+}
+
+fn to_data(data: TypeItemData) -> Option<serde_json::Value> {
+    serde_json::to_value(data).ok()
+}
+
+fn item_data(item: &TypeHierarchyItem) -> Option<TypeItemData> {
+    serde_json::from_value(item.data.clone()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::testing::open_files;
+
+    fn names(items: &[TypeHierarchyItem]) -> Vec<&str> {
+        items.iter().map(|item| item.name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_type_hierarchy() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = r#"syntax = "proto2";
+package demo;
+
+message Outer {
+  message Inner {
+    enum Kind {
+      A = 0;
+    }
+  }
+}
+
+message Base {
+  extensions 100 to 200;
+}
+
+extend Base {
+  optional int32 extra = 100;
+}
+"#;
+        let ext = r#"syntax = "proto2";
+package other;
+import "base.proto";
+
+extend demo.Base {
+  optional string note = 101;
+}
+"#;
+        let workspace = WorkspaceManager::new();
+        let uris = open_files(&workspace, dir.path(), &[("base.proto", base), ("ext.proto", ext)]).await;
+        let (base_uri, ext_uri) = (&uris[0], &uris[1]);
+
+        let prepare = |uri: &Url, content: &'static str, line: u32, character: u32| {
+            let params = TypeHierarchyPrepareParams {
+                text_document_position_params: TextDocumentPositionParams {
+                    text_document: TextDocumentIdentifier { uri: uri.clone() },
+                    position: Position { line, character },
+                },
+                work_done_progress_params: Default::default(),
+            };
+            let workspace = &workspace;
+            async move { prepare_type_hierarchy(params, workspace, Some(content)).await.unwrap().remove(0) }
+        };
+        let up = |item: TypeHierarchyItem| async {
+            let params = TypeHierarchySupertypesParams {
+                item,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            };
+            supertypes(params, &workspace).await.unwrap()
+        };
+        let down = |item: TypeHierarchyItem| async {
+            let params = TypeHierarchySubtypesParams {
+                item,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            };
+            subtypes(params, &workspace).await.unwrap()
+        };
+
+        // Containment
+        let inner = prepare(base_uri, base, 4, 11).await;
+        assert_eq!(inner.detail.as_deref(), Some("demo.Outer.Inner"));
+        assert_eq!(names(&down(inner.clone()).await), vec!["Kind"]);
+        let outer = up(inner).await.remove(0);
+        assert_eq!(outer.name, "Outer");
+        assert!(up(outer.clone()).await.is_empty());
+        assert_eq!(names(&down(outer).await), vec!["Inner"]);
+
+        // Extensions of a message, across files
+        let base_message = prepare(base_uri, base, 11, 9).await;
+        let extensions = down(base_message).await;
+        assert_eq!(names(&extensions), vec!["extend Base", "extend demo.Base"]);
+        assert_eq!(&extensions[1].uri, ext_uri);
+        assert_eq!(extensions[1].selection_range.start, Position { line: 4, character: 7 });
+        assert_eq!(names(&down(extensions[1].clone()).await), vec!["note"]);
+        assert_eq!(names(&up(extensions[1].clone()).await), vec!["Base"]);
+
+        // From an extension field or the `extend` keyword
+        let extra = prepare(base_uri, base, 16, 20).await;
+        assert_eq!((extra.name.as_str(), extra.kind), ("extra", SymbolKind::FIELD));
+        assert_eq!(extra.selection_range.start, Position { line: 16, character: 17 });
+        let extended = up(extra).await;
+        assert_eq!((extended[0].name.as_str(), &extended[0].uri), ("Base", base_uri));
+        let block = prepare(ext_uri, ext, 4, 2).await;
+        assert_eq!(block.name, "extend demo.Base");
+    }
+}
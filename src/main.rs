@@ -23,7 +23,7 @@ async fn main() {
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
 
-    let (service, socket) = LspService::new(|client| ProtobufLanguageServer::new(client));
+    let (service, socket) = LspService::new(ProtobufLanguageServer::new);
 
     Server::new(stdin, stdout, socket).serve(service).await;
 
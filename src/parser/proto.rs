@@ -64,6 +64,9 @@ pub struct ParsedProto {
     pub enums: Vec<EnumElement>,
     pub services: Vec<ServiceElement>,
     pub extends: Vec<ExtendElement>,
+    /// Every place a message or enum type is referenced (field types, RPC
+    /// parameters, extend targets), in source order
+    pub type_refs: Vec<TypeReference>,
     pub line_to_element: HashMap<u32, ProtoElement>,
     /// Parse errors collected during parsing.
     ///
@@ -84,6 +87,14 @@ pub struct MessageElement {
     pub fields: Vec<FieldElement>,
     pub nested_messages: Vec<MessageElement>,
     pub nested_enums: Vec<EnumElement>,
+    /// Oneofs of the message; their fields are also listed in `fields`
+    pub oneofs: Vec<OneofElement>,
+    /// Numbers taken by `reserved` and `extensions` statements
+    pub reserved_ranges: Vec<NumberRange>,
+    /// Set by `option deprecated = true;`
+    pub deprecated: bool,
+    /// Leading comment, without the comment markers
+    pub comment: Option<String>,
     pub line: u32,
     pub end_line: u32,
     pub character: u32,
@@ -98,30 +109,84 @@ pub struct FieldElement {
     pub type_name: Option<String>,
     pub number: i32,
     pub label: Option<FieldLabelProto>,
+    /// Compact options, e.g. `[deprecated = true, json_name = "id"]`
+    pub options: Vec<OptionElement>,
+    pub deprecated: bool,
     pub line: u32,
     pub character: u32,
 }
 
+/// Oneof definition element
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct OneofElement {
+    pub name: String,
+    pub line: u32,
+    pub end_line: u32,
+    pub character: u32,
+}
+
 /// Enum definition element
 #[derive(Debug, Clone)]
 pub struct EnumElement {
     pub name: String,
     pub full_name: String,
     pub values: Vec<EnumValueElement>,
+    pub options: Vec<OptionElement>,
+    /// Numbers taken by `reserved` statements
+    pub reserved_ranges: Vec<NumberRange>,
+    pub deprecated: bool,
+    /// Leading comment, without the comment markers
+    pub comment: Option<String>,
     pub line: u32,
     pub end_line: u32,
     pub character: u32,
 }
 
+/// Inclusive range of field or enum value numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberRange {
+    pub start: i32,
+    pub end: i32,
+}
+
+impl NumberRange {
+    pub fn contains(&self, number: i32) -> bool {
+        self.start <= number && number <= self.end
+    }
+}
+
 /// Enum value element
 #[derive(Debug, Clone)]
 pub struct EnumValueElement {
     pub name: String,
     pub number: i32,
+    /// Set by `[deprecated = true]`
+    pub deprecated: bool,
     pub line: u32,
     pub character: u32,
 }
 
+/// Option element - represents `option name = value;` or an entry of `[name = value]`
+#[derive(Debug, Clone)]
+pub struct OptionElement {
+    pub name: String,
+    /// Source representation of the value (strings keep their quotes)
+    pub value: String,
+    pub line: u32,
+    /// Points to the `option` keyword, or to `[` for compact options
+    pub character: u32,
+    pub value_line: u32,
+    pub value_character: u32,
+}
+
+impl OptionElement {
+    /// Whether this is a boolean option set to `true`, e.g. `deprecated = true`
+    pub fn is_true(&self) -> bool {
+        self.value == "true"
+    }
+}
+
 /// Extend definition element - represents `extend SomeMessage { ... }`
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -153,10 +218,40 @@ pub struct MethodElement {
     pub output_type: String,
     pub client_streaming: bool,
     pub server_streaming: bool,
+    /// Set by `option deprecated = true;` in the RPC body
+    pub deprecated: bool,
     pub line: u32,
     pub character: u32,
 }
 
+/// A reference to a message or enum type, as written in the source
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct TypeReference {
+    /// Type name as written, e.g. `Foo`, `pkg.Foo` or `.pkg.Foo`
+    pub name: String,
+    /// Fully-qualified name of the scope the reference is resolved from: the
+    /// enclosing message, or the package at top level (empty without a package)
+    pub scope: String,
+    pub kind: TypeReferenceKind,
+    pub line: u32,
+    /// Points to the first character of the type name
+    pub character: u32,
+}
+
+/// Where a type reference appears
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeReferenceKind {
+    /// Type of a field, oneof field or extension field
+    Field,
+    /// Value type of a map field
+    MapValue,
+    RpcInput,
+    RpcOutput,
+    /// Target of an `extend` block
+    Extendee,
+}
+
 /// Field label (optional, required, repeated)
 #[derive(Debug, Clone)]
 pub enum FieldLabelProto {
@@ -204,7 +299,7 @@ impl ProtoParser {
         }
 
         let parse_result = match proto_parser::Parser::new(content).parse() {
-            Ok(proto) => Ok(self.convert_proto(&uri, &proto)),
+            Ok(proto) => Ok(self.convert_proto(&uri, &proto, content)),
             Err(e) => {
                 // Parse failed — surface the error to the caller. We do NOT fabricate an
                 // empty ParsedProto here; the workspace layer will reuse the last good
@@ -239,7 +334,7 @@ impl ProtoParser {
     }
 
     /// Convert proto-rs AST to our ParsedProto representation
-    fn convert_proto(&self, uri: &str, proto: &proto_parser::Proto) -> ParsedProto {
+    fn convert_proto(&self, uri: &str, proto: &proto_parser::Proto, content: &str) -> ParsedProto {
         let mut package: Option<String> = None;
         let mut imports = Vec::new();
         let mut messages = Vec::new();
@@ -285,6 +380,11 @@ impl ProtoParser {
             }
         }
 
+        let lines: Vec<&str> = content.lines().collect();
+        let mut type_refs = Vec::new();
+        collect_type_refs(&proto.elements, &lines, package.as_deref().unwrap_or(""), &mut type_refs);
+        type_refs.sort_by_key(|r| (r.line, r.character));
+
         ParsedProto {
             uri: uri.to_string(),
             package,
@@ -293,6 +393,7 @@ impl ProtoParser {
             enums,
             services,
             extends,
+            type_refs,
             line_to_element,
             parse_errors: Vec::new(),
         }
@@ -315,15 +416,12 @@ impl ProtoParser {
         let mut last_line = pos_line(m.position.line);
 
         for elem in &m.elements {
-            match elem {
-                proto_parser::Element::NormalField(f) => {
-                    let fe = self.convert_normal_field(f);
-                    if fe.line > last_line {
-                        last_line = fe.line;
-                    }
-                    fields.push(fe);
+            if let proto_parser::Element::NormalField(f) = elem {
+                let fe = self.convert_normal_field(f);
+                if fe.line > last_line {
+                    last_line = fe.line;
                 }
-                _ => {}
+                fields.push(fe);
             }
         }
 
@@ -360,6 +458,9 @@ impl ProtoParser {
         let mut fields = Vec::new();
         let mut nested_messages = Vec::new();
         let mut nested_enums = Vec::new();
+        let mut oneofs = Vec::new();
+        let mut options = Vec::new();
+        let mut reserved_ranges = Vec::new();
         let mut last_line = pos_line(m.position.line);
 
         for elem in &m.elements {
@@ -380,25 +481,32 @@ impl ProtoParser {
                 }
                 proto_parser::Element::Oneof(o) => {
                     // Flatten oneof fields into the message fields list
+                    let mut oneof_last_line = pos_line(o.position.line);
                     for oe in &o.elements {
                         if let proto_parser::Element::OneofField(of) = oe {
                             let fe = self.convert_oneof_field(of);
-                            if fe.line > last_line {
-                                last_line = fe.line;
-                            }
+                            oneof_last_line = oneof_last_line.max(fe.line);
                             fields.push(fe);
                         }
                     }
+                    // Estimate end_line as for messages: the closing brace follows the last field
+                    let end_line = oneof_last_line + 1;
+                    last_line = last_line.max(end_line);
+                    oneofs.push(OneofElement {
+                        name: o.name.clone(),
+                        line: pos_line(o.position.line),
+                        end_line,
+                        // position.column points to the keyword "oneof", not the name
+                        character: pos_col(o.position.column + "oneof".len() + 1),
+                    });
                 }
-                proto_parser::Element::Message(nested_m) => {
-                    // Skip nested extend blocks — they are references, not definitions
-                    if !nested_m.is_extend {
-                        let nested = self.convert_message(nested_m, package, &full_name);
-                        if nested.end_line > last_line {
-                            last_line = nested.end_line;
-                        }
-                        nested_messages.push(nested);
+                // Skip nested extend blocks — they are references, not definitions
+                proto_parser::Element::Message(nested_m) if !nested_m.is_extend => {
+                    let nested = self.convert_message(nested_m, package, &full_name);
+                    if nested.end_line > last_line {
+                        last_line = nested.end_line;
                     }
+                    nested_messages.push(nested);
                 }
                 proto_parser::Element::Enum(nested_e) => {
                     let nested = self.convert_enum(nested_e, package, &full_name);
@@ -407,6 +515,15 @@ impl ProtoParser {
                     }
                     nested_enums.push(nested);
                 }
+                proto_parser::Element::Option(o) => {
+                    options.push(convert_option(o));
+                }
+                proto_parser::Element::Reserved(r) => {
+                    reserved_ranges.extend(r.ranges.iter().map(convert_range));
+                }
+                proto_parser::Element::Extensions(e) => {
+                    reserved_ranges.extend(e.ranges.iter().map(convert_range));
+                }
                 _ => {}
             }
         }
@@ -428,6 +545,10 @@ impl ProtoParser {
             fields,
             nested_messages,
             nested_enums,
+            oneofs,
+            reserved_ranges,
+            deprecated: is_deprecated(&options),
+            comment: comment_text(&m.comment),
             line: pos_line(m.position.line),
             end_line,
             character: pos_col(name_column),
@@ -452,12 +573,15 @@ impl ProtoParser {
             Some(f.field.type_name.clone())
         };
 
+        let options: Vec<OptionElement> = f.field.options.iter().map(convert_option).collect();
         FieldElement {
             name: f.field.name.clone(),
             field_type: f.field.type_name.clone(),
             type_name,
             number: f.field.sequence as i32,
             label,
+            deprecated: is_deprecated(&options),
+            options,
             line: pos_line(f.field.position.line),
             character: pos_col(f.field.position.column),
         }
@@ -466,12 +590,15 @@ impl ProtoParser {
     /// Convert a proto-rs MapField to FieldElement
     fn convert_map_field(&self, f: &proto_parser::MapField) -> FieldElement {
         let map_type = format!("map<{}, {}>", f.key_type, f.field.type_name);
+        let options: Vec<OptionElement> = f.field.options.iter().map(convert_option).collect();
         FieldElement {
             name: f.field.name.clone(),
             field_type: map_type,
             type_name: None,
             number: f.field.sequence as i32,
             label: Some(FieldLabelProto::Repeated),
+            deprecated: is_deprecated(&options),
+            options,
             line: pos_line(f.field.position.line),
             character: pos_col(f.field.position.column),
         }
@@ -485,12 +612,15 @@ impl ProtoParser {
             Some(f.field.type_name.clone())
         };
 
+        let options: Vec<OptionElement> = f.field.options.iter().map(convert_option).collect();
         FieldElement {
             name: f.field.name.clone(),
             field_type: f.field.type_name.clone(),
             type_name,
             number: f.field.sequence as i32,
             label: None,
+            deprecated: is_deprecated(&options),
+            options,
             line: pos_line(f.field.position.line),
             character: pos_col(f.field.position.column),
         }
@@ -507,20 +637,44 @@ impl ProtoParser {
         let full_name = make_full_name(package, parent_name, &name);
 
         let mut values = Vec::new();
+        let mut options = Vec::new();
+        let mut reserved_ranges = Vec::new();
         let mut last_line = pos_line(e.position.line);
 
         for elem in &e.elements {
-            if let proto_parser::Element::EnumField(ef) = elem {
-                let line = pos_line(ef.position.line);
-                if line > last_line {
-                    last_line = line;
+            match elem {
+                proto_parser::Element::EnumField(ef) => {
+                    let line = pos_line(ef.position.line);
+                    if line > last_line {
+                        last_line = line;
+                    }
+                    let value_options: Vec<OptionElement> = ef
+                        .elements
+                        .iter()
+                        .filter_map(|el| match el {
+                            proto_parser::Element::Option(o) => Some(convert_option(o)),
+                            _ => None,
+                        })
+                        .collect();
+                    values.push(EnumValueElement {
+                        name: ef.name.clone(),
+                        number: ef.integer as i32,
+                        deprecated: is_deprecated(&value_options),
+                        line,
+                        character: pos_col(ef.position.column),
+                    });
                 }
-                values.push(EnumValueElement {
-                    name: ef.name.clone(),
-                    number: ef.integer as i32,
-                    line,
-                    character: pos_col(ef.position.column),
-                });
+                proto_parser::Element::Option(o) => {
+                    let opt = convert_option(o);
+                    if opt.line > last_line {
+                        last_line = opt.line;
+                    }
+                    options.push(opt);
+                }
+                proto_parser::Element::Reserved(r) => {
+                    reserved_ranges.extend(r.ranges.iter().map(convert_range));
+                }
+                _ => {}
             }
         }
 
@@ -538,6 +692,10 @@ impl ProtoParser {
             name,
             full_name,
             values,
+            deprecated: is_deprecated(&options),
+            options,
+            reserved_ranges,
+            comment: comment_text(&e.comment),
             line: pos_line(e.position.line),
             end_line,
             character: pos_col(name_column),
@@ -581,6 +739,10 @@ impl ProtoParser {
                     output_type,
                     client_streaming: rpc.streams_request,
                     server_streaming: rpc.streams_returns,
+                    deprecated: rpc.elements.iter().any(|el| {
+                        matches!(el, proto_parser::Element::Option(o) if o.name == "deprecated"
+                            && o.constant.source_representation() == "true")
+                    }),
                     line,
                     character: pos_col(method_name_column),
                 });
@@ -635,18 +797,156 @@ fn pos_col(col: usize) -> u32 {
     if col > 0 { col as u32 - 1 } else { 0 }
 }
 
-/// Build a fully-qualified name like "package.Parent.Name"
-fn make_full_name(package: &Option<String>, parent_name: &str, name: &str) -> String {
-    if let Some(pkg) = package {
-        if parent_name.is_empty() {
-            format!("{}.{}", pkg, name)
-        } else {
-            format!("{}.{}.{}", pkg, parent_name, name)
+/// Convert a proto-rs range; `to max` extends to the largest representable number
+fn convert_range(r: &proto_parser::Range) -> NumberRange {
+    NumberRange {
+        start: r.from as i32,
+        end: if r.max { i32::MAX } else { r.to as i32 },
+    }
+}
+
+/// Convert a proto-rs option to OptionElement
+fn convert_option(o: &proto_parser::ProtoOption) -> OptionElement {
+    OptionElement {
+        name: o.name.clone(),
+        value: o.constant.source_representation(),
+        line: pos_line(o.position.line),
+        character: pos_col(o.position.column),
+        value_line: pos_line(o.constant.position.line),
+        value_character: pos_col(o.constant.position.column),
+    }
+}
+
+/// Walk the AST collecting type references. `scope` is the fully-qualified name of
+/// the enclosing message (or the package at top level).
+fn collect_type_refs(
+    elements: &[proto_parser::Element],
+    lines: &[&str],
+    scope: &str,
+    refs: &mut Vec<TypeReference>,
+) {
+    let mut push = |name: &str, kind: TypeReferenceKind, line: u32, character: u32| {
+        if is_builtin_type(name) || name.is_empty() {
+            return None;
         }
-    } else if parent_name.is_empty() {
-        name.to_string()
-    } else {
+        let (line, character) = locate_name(lines, line, character, name);
+        refs.push(TypeReference {
+            name: name.to_string(),
+            scope: scope.to_string(),
+            kind,
+            line,
+            character,
+        });
+        Some((line, character + name.len() as u32))
+    };
+
+    let mut nested: Vec<(&[proto_parser::Element], String)> = Vec::new();
+    for element in elements {
+        match element {
+            proto_parser::Element::NormalField(f) => {
+                let p = &f.field.position;
+                push(&f.field.type_name, TypeReferenceKind::Field, pos_line(p.line), pos_col(p.column));
+            }
+            proto_parser::Element::OneofField(f) => {
+                let p = &f.field.position;
+                push(&f.field.type_name, TypeReferenceKind::Field, pos_line(p.line), pos_col(p.column));
+            }
+            proto_parser::Element::MapField(f) => {
+                let p = &f.field.position;
+                push(&f.field.type_name, TypeReferenceKind::MapValue, pos_line(p.line), pos_col(p.column));
+            }
+            proto_parser::Element::Oneof(o) => {
+                nested.push((&o.elements, scope.to_string()));
+            }
+            proto_parser::Element::Message(m) if m.is_extend => {
+                let p = &m.position;
+                let column = p.column + "extend".len() + 1;
+                push(&m.name, TypeReferenceKind::Extendee, pos_line(p.line), pos_col(column));
+                // Extension field types are resolved from the scope the block appears in
+                nested.push((&m.elements, scope.to_string()));
+            }
+            proto_parser::Element::Message(m) => {
+                let child = if scope.is_empty() {
+                    m.name.clone()
+                } else {
+                    format!("{}.{}", scope, m.name)
+                };
+                nested.push((&m.elements, child));
+            }
+            proto_parser::Element::Service(s) => {
+                for rpc in &s.elements {
+                    if let proto_parser::Element::Rpc(rpc) = rpc {
+                        let (mut line, mut column) =
+                            (pos_line(rpc.position.line), pos_col(rpc.position.column));
+                        if let Some(end) = push(&rpc.request_type, TypeReferenceKind::RpcInput, line, column) {
+                            (line, column) = end;
+                        }
+                        push(&rpc.returns_type, TypeReferenceKind::RpcOutput, line, column);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for (elements, child_scope) in nested {
+        collect_type_refs(elements, lines, &child_scope, refs);
+    }
+}
+
+/// Find the first whole-word occurrence of `name` at or after (`line`, `character`),
+/// looking at most a few lines ahead for statements that span lines. Falls back to
+/// the start position if the name cannot be found.
+fn locate_name(lines: &[&str], line: u32, character: u32, name: &str) -> (u32, u32) {
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_' || b == b'.';
+    for (offset, text) in lines.iter().skip(line as usize).take(4).enumerate() {
+        let start = if offset == 0 { (character as usize).min(text.len()) } else { 0 };
+        let bytes = text.as_bytes();
+        let mut from = start;
+        while let Some(found) = text.get(from..).and_then(|rest| rest.find(name)) {
+            let begin = from + found;
+            let end = begin + name.len();
+            let before_ok = begin == 0 || !is_word(bytes[begin - 1]);
+            let after_ok = end >= bytes.len() || !is_word(bytes[end]);
+            if before_ok && after_ok {
+                return (line + offset as u32, begin as u32);
+            }
+            from = begin + 1;
+        }
+    }
+    (line, character)
+}
+
+/// Text of a comment with the markers and the space after them removed
+fn comment_text(comment: &Option<proto_parser::Comment>) -> Option<String> {
+    let comment = comment.as_ref()?;
+    let lines: Vec<&str> = comment
+        .lines
+        .iter()
+        .map(|line| {
+            let line = line.trim_end();
+            let line = if comment.c_style { line.trim_start().trim_start_matches("* ") } else { line };
+            line.strip_prefix(' ').unwrap_or(line)
+        })
+        .collect();
+    let text = lines.join("\n").trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Whether an option list contains `deprecated = true`
+fn is_deprecated(options: &[OptionElement]) -> bool {
+    options.iter().any(|o| o.name == "deprecated" && o.is_true())
+}
+
+/// Build a fully-qualified name like "package.Parent.Name". `parent_name` is the
+/// parent's full name (already package-qualified), or empty at top level.
+fn make_full_name(package: &Option<String>, parent_name: &str, name: &str) -> String {
+    if !parent_name.is_empty() {
         format!("{}.{}", parent_name, name)
+    } else if let Some(pkg) = package {
+        format!("{}.{}", pkg, name)
+    } else {
+        name.to_string()
     }
 }
 
@@ -889,6 +1189,99 @@ message Outer {
 
         let deepest = &inner.nested_messages[0];
         assert_eq!(deepest.name, "Deepest");
+        assert_eq!(deepest.full_name, "test.Outer.Inner.Deepest");
+    }
+
+    #[tokio::test]
+    async fn test_parse_oneofs() {
+        let content = r#"syntax = "proto3";
+message Payment {
+  string id = 1;
+  oneof method {
+    string card = 2;
+    string iban = 3;
+  }
+}
+"#;
+        let proto = ParsedProto::parse("test.proto".to_string(), content).await.unwrap();
+        let payment = &proto.messages[0];
+        // Oneof fields stay in the message's field list
+        assert_eq!(payment.fields.len(), 3);
+        assert_eq!(payment.oneofs.len(), 1);
+        let oneof = &payment.oneofs[0];
+        assert_eq!(oneof.name, "method");
+        assert_eq!((oneof.line, oneof.character, oneof.end_line), (3, 8, 6));
+    }
+
+    #[tokio::test]
+    async fn test_deprecated_flags() {
+        let content = r#"syntax = "proto3";
+message M {
+  option deprecated = true;
+  string a = 1 [deprecated = true];
+  string b = 2;
+}
+enum E {
+  E_OLD = 0 [deprecated = true];
+  E_NEW = 1;
+}
+service S {
+  rpc Old(M) returns (M) { option deprecated = true; }
+  rpc New(M) returns (M);
+}
+"#;
+        let proto = ParsedProto::parse("test.proto".to_string(), content).await.unwrap();
+        let m = &proto.messages[0];
+        assert!(m.deprecated);
+        assert!(m.fields[0].deprecated);
+        assert!(!m.fields[1].deprecated);
+        assert!(!proto.enums[0].deprecated);
+        assert!(proto.enums[0].values[0].deprecated);
+        assert!(!proto.enums[0].values[1].deprecated);
+        assert!(proto.services[0].methods[0].deprecated);
+        assert!(!proto.services[0].methods[1].deprecated);
+    }
+
+    #[tokio::test]
+    async fn test_type_refs() {
+        let content = r#"syntax = "proto3";
+package a.b;
+
+message M {
+  repeated   Foo foo = 1;
+  map<string, Bar> m = 2;
+  message N {
+    oneof o { .x.Baz z = 3; }
+  }
+}
+
+extend google.protobuf.FieldOptions {
+  M opt = 50000;
+}
+
+service S {
+  rpc Get(stream Req)
+      returns (x.Resp);
+}
+"#;
+        let proto = ParsedProto::parse("test.proto".to_string(), content).await.unwrap();
+        let refs: Vec<(&str, &str, TypeReferenceKind, u32, u32)> = proto
+            .type_refs
+            .iter()
+            .map(|r| (r.name.as_str(), r.scope.as_str(), r.kind, r.line, r.character))
+            .collect();
+        assert_eq!(
+            refs,
+            vec![
+                ("Foo", "a.b.M", TypeReferenceKind::Field, 4, 13),
+                ("Bar", "a.b.M", TypeReferenceKind::MapValue, 5, 14),
+                (".x.Baz", "a.b.M.N", TypeReferenceKind::Field, 7, 14),
+                ("google.protobuf.FieldOptions", "a.b", TypeReferenceKind::Extendee, 11, 7),
+                ("M", "a.b", TypeReferenceKind::Field, 12, 2),
+                ("Req", "a.b", TypeReferenceKind::RpcInput, 16, 17),
+                ("x.Resp", "a.b", TypeReferenceKind::RpcOutput, 17, 15),
+            ]
+        );
     }
 
     #[tokio::test]
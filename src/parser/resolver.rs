@@ -11,63 +11,43 @@ impl ImportResolver {
 
     /// Resolves an import path to an absolute file path
     pub fn resolve_import(&self, current_file: &Path, import_path: &str) -> Option<PathBuf> {
+        self.resolve_with(current_file, import_path, Path::exists)
+    }
+
+    /// Resolves an import path as `resolve_import` does, deciding which files
+    /// exist with `exists`. Lets callers resolve against a planned layout,
+    /// such as the tree after files are moved.
+    pub fn resolve_with(&self, current_file: &Path, import_path: &str, exists: impl Fn(&Path) -> bool) -> Option<PathBuf> {
         tracing::debug!("ImportResolver: resolving '{}' from file: {}", import_path, current_file.display());
         tracing::debug!("Additional directories: {:?}", self.additional_dirs);
 
-        // First try additional directories (if any were configured) - highest priority
-        for (i, dir) in self.additional_dirs.iter().enumerate() {
-            let resolved = dir.join(import_path);
-            tracing::debug!("Trying additional dir[{}] {}: {}", i, dir.display(), resolved.display());
-            if resolved.exists() {
-                tracing::debug!("Found in additional directory: {}", resolved.display());
+        for root in self.search_roots(current_file) {
+            let resolved = root.join(import_path);
+            tracing::debug!("Trying {}", resolved.display());
+            if exists(&resolved) {
+                tracing::debug!("Found at {}", resolved.display());
                 return Some(resolved);
             }
         }
 
-        // Then try relative to current file's directory
-        if let Some(parent) = current_file.parent() {
-            let resolved = parent.join(import_path);
-            tracing::debug!("Trying relative to parent: {}", resolved.display());
-            if resolved.exists() {
-                tracing::debug!("Found at relative path: {}", resolved.display());
-                return Some(resolved);
-            }
-        }
+        tracing::debug!("Failed to resolve import: {}", import_path);
+        None
+    }
 
-        // Finally try walking up the directory tree from current file
-        // This handles cases where imports are relative to a project root
-        if let Some(mut current) = current_file.parent() {
-            // Start from the parent directory and walk up
-            let mut level = 0;
-            loop {
-                let resolved = current.join(import_path);
-                tracing::debug!("Trying from level {}: {}", level, resolved.display());
-                if resolved.exists() {
-                    tracing::debug!("Found at level {}: {}", level, resolved.display());
-                    return Some(resolved);
-                }
-
-                // Move up one directory
-                match current.parent() {
-                    Some(parent) => {
-                        // Avoid infinite loops on root directory
-                        if parent == current {
-                            tracing::debug!("Reached root directory, stopping upward search");
-                            break;
-                        }
-                        current = parent;
-                        level += 1;
-                    }
-                    None => {
-                        tracing::debug!("No more parent directories");
-                        break;
-                    }
-                }
-            }
+    /// Directories imports are resolved against, in priority order: additional
+    /// directories (highest priority), the current file's directory, then each
+    /// of its ancestors up to the filesystem root (imports relative to a
+    /// project root).
+    pub fn search_roots(&self, current_file: &Path) -> Vec<PathBuf> {
+        let mut roots = self.additional_dirs.clone();
+        if let Some(parent) = current_file.parent() {
+            roots.extend(parent.ancestors().map(Path::to_path_buf));
         }
+        roots
+    }
 
-        tracing::debug!("Failed to resolve import: {}", import_path);
-        None
+    pub fn additional_dirs(&self) -> &[PathBuf] {
+        &self.additional_dirs
     }
 
     pub fn add_directory(&mut self, dir: PathBuf) {
@@ -217,4 +197,15 @@ mod tests {
         assert_eq!(resolved.as_ref().unwrap(), &additional_import);
         assert_ne!(resolved.as_ref().unwrap(), &relative_import);
     }
+
+    #[test]
+    fn test_search_roots_order() {
+        let resolver = ImportResolver::new(vec![PathBuf::from("/extra")]);
+        let roots = resolver.search_roots(Path::new("/project/api/v1/user.proto"));
+        let expected: Vec<PathBuf> = ["/extra", "/project/api/v1", "/project/api", "/project", "/"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(roots, expected);
+    }
 }
@@ -1,12 +1,19 @@
 use crate::features::{
     format_document, provide_completion, provide_definition_async, provide_document_symbols,
     provide_hover_async, validate_proto_file, find_references,
-    prepare_rename, rename, workspace_symbol, provide_signature_help, provide_code_actions,
+    prepare_rename, rename, will_rename_files, provide_document_highlights,
+    prepare_call_hierarchy, incoming_calls, outgoing_calls, prepare_type_hierarchy, subtypes,
+    supertypes, provide_selection_ranges, provide_code_lenses, provide_inlay_hints, workspace_symbol, provide_signature_help, provide_code_actions,
     provide_semantic_tokens_full, provide_folding_ranges, provide_document_links,
+    provide_document_diagnostics, provide_workspace_diagnostics, validate_dependents,
+    provide_on_type_formatting, resolve_completion_item, DiagnosticsCache, EditSupport,
 };
 use crate::workspace::WorkspaceManager;
 use dashmap::DashMap;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer};
@@ -15,8 +22,27 @@ pub struct ProtobufLanguageServer {
     client: Client,
     workspace: Arc<WorkspaceManager>,
     document_contents: Arc<DashMap<Url, String>>,
+    /// Version of each open document, as sent by the client
+    document_versions: Arc<DashMap<Url, i32>>,
+    diagnostics_cache: Arc<DiagnosticsCache>,
+    /// Set when the client pulls diagnostics (LSP 3.17), in which case they are
+    /// not pushed on open/change.
+    pull_diagnostics: Arc<AtomicBool>,
+    /// Set when the client registers type hierarchy dynamically; lsp-types has
+    /// no static `typeHierarchyProvider` capability to announce it with
+    register_type_hierarchy: Arc<AtomicBool>,
+    /// Set when the client takes `documentChanges` with change annotations in
+    /// workspace edits
+    annotated_edits: Arc<AtomicBool>,
+    /// Files edited since dependents were last revalidated
+    pending_changes: Arc<parking_lot::Mutex<HashSet<Url>>>,
+    /// Bumped on every edit; a debounced revalidation only runs if it is still current
+    change_ticket: Arc<AtomicU64>,
 }
 
+/// Quiet period after the last edit before dependent files are revalidated
+const DEPENDENTS_DEBOUNCE: Duration = Duration::from_millis(300);
+
 impl ProtobufLanguageServer {
     pub fn new(client: Client) -> Self {
         tracing::info!("Creating new ProtobufLanguageServer instance");
@@ -26,6 +52,66 @@ impl ProtobufLanguageServer {
             client,
             workspace,
             document_contents: Arc::new(DashMap::new()),
+            document_versions: Arc::new(DashMap::new()),
+            diagnostics_cache: Arc::new(DiagnosticsCache::new()),
+            pull_diagnostics: Arc::new(AtomicBool::new(false)),
+            register_type_hierarchy: Arc::new(AtomicBool::new(false)),
+            annotated_edits: Arc::new(AtomicBool::new(false)),
+            pending_changes: Arc::new(parking_lot::Mutex::new(HashSet::new())),
+            change_ticket: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Schedules revalidation of open files that import `uri`. Bursts of edits are
+    /// coalesced: only the task scheduled by the last edit runs the pass.
+    fn schedule_dependents_validation(&self, uri: &Url) {
+        self.pending_changes.lock().insert(uri.clone());
+        let ticket = self.change_ticket.fetch_add(1, Ordering::SeqCst) + 1;
+
+        let client = self.client.clone();
+        let workspace = self.workspace.clone();
+        let document_contents = self.document_contents.clone();
+        let pending_changes = self.pending_changes.clone();
+        let change_ticket = self.change_ticket.clone();
+        let pull_diagnostics = self.pull_diagnostics.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(DEPENDENTS_DEBOUNCE).await;
+            if change_ticket.load(Ordering::SeqCst) != ticket {
+                return;
+            }
+            let changed: Vec<Url> = pending_changes.lock().drain().collect();
+            let open: Vec<Url> = document_contents.iter().map(|e| e.key().clone()).collect();
+
+            if pull_diagnostics.load(Ordering::Relaxed) {
+                // Result IDs cover imports, so a refresh makes the client re-pull
+                // only the reports that actually changed.
+                if !workspace.find_dependents(&open, &changed).await.is_empty() {
+                    if let Err(e) = client.workspace_diagnostic_refresh().await {
+                        tracing::debug!("Diagnostic refresh not supported: {}", e);
+                    }
+                }
+            } else {
+                let revalidated = validate_dependents(&changed, &open, &workspace, &client).await;
+                tracing::debug!("Revalidated {} dependent files", revalidated.len());
+            }
+        });
+    }
+
+    /// Pushes diagnostics for a file, unless the client pulls them itself.
+    async fn validate(&self, uri: &Url) {
+        if self.pull_diagnostics.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Err(e) = validate_proto_file(uri, &self.workspace, &self.client).await {
+            tracing::error!("Failed to validate {}: {}", uri, e);
+        }
+    }
+
+    /// How workspace edits are sent to the client, with its open documents' versions
+    fn edit_support(&self) -> EditSupport {
+        EditSupport {
+            annotated: self.annotated_edits.load(Ordering::Relaxed),
+            versions: self.document_versions.iter().map(|e| (e.key().clone(), *e.value())).collect(),
         }
     }
 }
@@ -39,6 +125,7 @@ impl LanguageServer for ProtobufLanguageServer {
         tracing::info!("Checking for additional proto directories in initialization options");
         if let Some(options) = params.initialization_options {
             tracing::debug!("Initialization options: {:?}", options);
+            self.workspace.update_settings(&options);
             if let Some(dirs) = options.get("additionalProtoDirs") {
                 tracing::info!("Found additionalProtoDirs: {:?}", dirs);
                 if let Some(dirs_array) = dirs.as_array() {
@@ -57,14 +144,89 @@ impl LanguageServer for ProtobufLanguageServer {
             tracing::info!("No initialization options provided");
         }
 
+        // Workspace folders are indexed after initialization so that workspace
+        // diagnostics cover files that are never opened.
+        let mut roots: Vec<Url> = params
+            .workspace_folders
+            .unwrap_or_default()
+            .into_iter()
+            .map(|folder| folder.uri)
+            .collect();
+        #[allow(deprecated)]
+        if roots.is_empty() {
+            roots.extend(params.root_uri);
+        }
+        for root in roots {
+            if let Ok(path) = root.to_file_path() {
+                tracing::info!("Adding workspace folder: {}", path.display());
+                self.workspace.add_workspace_root(path);
+            }
+        }
+
+        let supports_pull = params
+            .capabilities
+            .text_document
+            .as_ref()
+            .and_then(|td| td.diagnostic.as_ref())
+            .is_some();
+        self.pull_diagnostics.store(supports_pull, Ordering::Relaxed);
+        tracing::info!("Client pulls diagnostics: {}", supports_pull);
+
+        let dynamic_type_hierarchy = params
+            .capabilities
+            .text_document
+            .as_ref()
+            .and_then(|td| td.type_hierarchy.as_ref())
+            .and_then(|th| th.dynamic_registration)
+            .unwrap_or(false);
+        self.register_type_hierarchy.store(dynamic_type_hierarchy, Ordering::Relaxed);
+
+        let annotated_edits = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|ws| ws.workspace_edit.as_ref())
+            .is_some_and(|edit| edit.document_changes == Some(true) && edit.change_annotation_support.is_some());
+        self.annotated_edits.store(annotated_edits, Ordering::Relaxed);
+        tracing::info!("Client takes annotated document changes: {}", annotated_edits);
+
+        // `.proto` files and the folders that may hold them
+        let proto_file_operations = FileOperationRegistrationOptions {
+            filters: vec![
+                FileOperationFilter {
+                    scheme: Some("file".to_string()),
+                    pattern: FileOperationPattern {
+                        glob: "**/*.proto".to_string(),
+                        matches: Some(FileOperationPatternKind::File),
+                        options: None,
+                    },
+                },
+                FileOperationFilter {
+                    scheme: Some("file".to_string()),
+                    pattern: FileOperationPattern {
+                        glob: "**".to_string(),
+                        matches: Some(FileOperationPatternKind::Folder),
+                        options: None,
+                    },
+                },
+            ],
+        };
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
                     TextDocumentSyncKind::FULL,
                 )),
                 completion_provider: Some(CompletionOptions {
-                    trigger_characters: Some(vec![".".to_string(), ":".to_string()]),
-                    resolve_provider: Some(false),
+                    trigger_characters: Some(vec![
+                        ".".to_string(),
+                        ":".to_string(),
+                        "=".to_string(),
+                        "[".to_string(),
+                        "\"".to_string(),
+                        "/".to_string(),
+                    ]),
+                    resolve_provider: Some(true),
                     completion_item: None,
                     work_done_progress_options: Default::default(),
                     all_commit_characters: None,
@@ -74,12 +236,26 @@ impl LanguageServer for ProtobufLanguageServer {
                 document_symbol_provider: Some(OneOf::Left(true)),
                 document_formatting_provider: Some(OneOf::Left(true)),
                 document_range_formatting_provider: Some(OneOf::Left(true)),
+                document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+                    first_trigger_character: "=".to_string(),
+                    more_trigger_character: Some(vec![";".to_string()]),
+                }),
                 references_provider: Some(OneOf::Left(true)),
+                document_highlight_provider: Some(OneOf::Left(true)),
+                call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
                 rename_provider: Some(OneOf::Right(RenameOptions {
                     prepare_provider: Some(true),
                     work_done_progress_options: Default::default(),
                 })),
                 workspace_symbol_provider: Some(OneOf::Left(true)),
+                workspace: Some(WorkspaceServerCapabilities {
+                    workspace_folders: None,
+                    file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+                        will_rename: Some(proto_file_operations.clone()),
+                        did_rename: Some(proto_file_operations),
+                        ..Default::default()
+                    }),
+                }),
                 signature_help_provider: Some(SignatureHelpOptions {
                     trigger_characters: Some(vec!["(".to_string()]),
                     retrigger_characters: Some(vec![",".to_string()]),
@@ -106,10 +282,25 @@ impl LanguageServer for ProtobufLanguageServer {
                     ),
                 ),
                 folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+                selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+                code_lens_provider: Some(CodeLensOptions { resolve_provider: Some(false) }),
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: crate::features::code_lens::COMMANDS.iter().map(|c| c.to_string()).collect(),
+                    work_done_progress_options: Default::default(),
+                }),
+                inlay_hint_provider: Some(OneOf::Left(true)),
                 document_link_provider: Some(DocumentLinkOptions {
                     resolve_provider: Some(false),
                     work_done_progress_options: Default::default(),
                 }),
+                diagnostic_provider: Some(DiagnosticServerCapabilities::Options(
+                    DiagnosticOptions {
+                        identifier: Some("protobuf-lsp".to_string()),
+                        inter_file_dependencies: true,
+                        workspace_diagnostics: true,
+                        work_done_progress_options: Default::default(),
+                    },
+                )),
                 ..Default::default()
             },
             server_info: Some(ServerInfo {
@@ -124,6 +315,39 @@ impl LanguageServer for ProtobufLanguageServer {
         self.client
             .log_message(MessageType::INFO, "Protobuf LSP server initialized")
             .await;
+
+        if self.register_type_hierarchy.load(Ordering::Relaxed) {
+            let options = TypeHierarchyRegistrationOptions {
+                text_document_registration_options: TextDocumentRegistrationOptions {
+                    document_selector: Some(vec![DocumentFilter {
+                        language: None,
+                        scheme: Some("file".to_string()),
+                        pattern: Some("**/*.proto".to_string()),
+                    }]),
+                },
+                ..Default::default()
+            };
+            let registration = Registration {
+                id: "protobuf-lsp-type-hierarchy".to_string(),
+                method: "textDocument/prepareTypeHierarchy".to_string(),
+                register_options: serde_json::to_value(options).ok(),
+            };
+            if let Err(e) = self.client.register_capability(vec![registration]).await {
+                tracing::warn!("Failed to register type hierarchy: {}", e);
+            }
+        }
+
+        let workspace = self.workspace.clone();
+        let client = self.client.clone();
+        let pull_diagnostics = self.pull_diagnostics.load(Ordering::Relaxed);
+        tokio::spawn(async move {
+            if workspace.index_workspace().await > 0 && pull_diagnostics {
+                // Ask the client to pull again now that more files are known
+                if let Err(e) = client.workspace_diagnostic_refresh().await {
+                    tracing::debug!("Diagnostic refresh not supported: {}", e);
+                }
+            }
+        });
     }
 
     async fn shutdown(&self) -> Result<()> {
@@ -139,6 +363,7 @@ impl LanguageServer for ProtobufLanguageServer {
 
         // Store the document content
         self.document_contents.insert(uri.clone(), content.clone());
+        self.document_versions.insert(uri.clone(), params.text_document.version);
 
         // Parse the file. On a successful parse, the workspace updates its live
         // cache. On a parse failure, the workspace records the error (for
@@ -150,9 +375,7 @@ impl LanguageServer for ProtobufLanguageServer {
                     .await;
 
                 // Validate the file and publish diagnostics
-                if let Err(e) = validate_proto_file(&uri, &self.workspace, &self.client).await {
-                    tracing::error!("Failed to validate {}: {}", uri, e);
-                }
+                self.validate(&uri).await;
             }
             Err(e) => {
                 tracing::error!("Failed to parse {}: {}", uri, e);
@@ -161,9 +384,7 @@ impl LanguageServer for ProtobufLanguageServer {
                     .await;
 
                 // Publish diagnostics derived from the recorded parse error.
-                if let Err(e) = validate_proto_file(&uri, &self.workspace, &self.client).await {
-                    tracing::error!("Failed to validate {}: {}", uri, e);
-                }
+                self.validate(&uri).await;
             }
         }
     }
@@ -176,25 +397,25 @@ impl LanguageServer for ProtobufLanguageServer {
 
             // Update stored content
             self.document_contents.insert(uri.clone(), content.clone());
+            self.document_versions.insert(uri.clone(), params.text_document.version);
 
             // Re-parse the file. Same semantics as did_open: on failure the workspace
             // keeps the last good result and records the error for diagnostics.
             match self.workspace.open_file(&uri, content).await {
                 Ok(_) => {
                     // Validate the file and publish diagnostics
-                    if let Err(e) = validate_proto_file(&uri, &self.workspace, &self.client).await {
-                        tracing::error!("Failed to validate {}: {}", uri, e);
-                    }
+                    self.validate(&uri).await;
                 }
                 Err(e) => {
                     tracing::error!("Failed to parse {}: {}", uri, e);
 
                     // Publish diagnostics derived from the recorded parse error.
-                    if let Err(e) = validate_proto_file(&uri, &self.workspace, &self.client).await {
-                        tracing::error!("Failed to validate {}: {}", uri, e);
-                    }
+                    self.validate(&uri).await;
                 }
             }
+
+            // Files importing this one may have gained or lost errors
+            self.schedule_dependents_validation(&uri);
         }
     }
 
@@ -203,7 +424,36 @@ impl LanguageServer for ProtobufLanguageServer {
         tracing::info!("Closing document: {}", uri);
 
         self.document_contents.remove(&uri);
+        self.document_versions.remove(&uri);
         self.workspace.close_file(&uri);
+        self.diagnostics_cache.invalidate(&uri);
+
+        // Files inside the workspace stay indexed with their on-disk content so
+        // workspace diagnostics keep reporting on them.
+        if self.workspace.is_in_workspace(&uri) {
+            if let Some(content) = uri.to_file_path().ok().and_then(|p| std::fs::read_to_string(p).ok()) {
+                let _ = self.workspace.open_file(&uri, &content).await;
+            }
+        }
+
+        // Unsaved edits were discarded, so dependents see the on-disk content again
+        self.schedule_dependents_validation(&uri);
+    }
+
+    async fn diagnostic(
+        &self,
+        params: DocumentDiagnosticParams,
+    ) -> Result<DocumentDiagnosticReportResult> {
+        tracing::debug!("Document diagnostic request: {:?}", params);
+        Ok(provide_document_diagnostics(params, &self.workspace, &self.diagnostics_cache).await)
+    }
+
+    async fn workspace_diagnostic(
+        &self,
+        params: WorkspaceDiagnosticParams,
+    ) -> Result<WorkspaceDiagnosticReportResult> {
+        tracing::debug!("Workspace diagnostic request: {:?}", params);
+        Ok(provide_workspace_diagnostics(params, &self.workspace, &self.diagnostics_cache).await)
     }
 
     async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
@@ -213,6 +463,11 @@ impl LanguageServer for ProtobufLanguageServer {
         Ok(provide_completion(params, &self.workspace, content.as_deref()).await)
     }
 
+    async fn completion_resolve(&self, item: CompletionItem) -> Result<CompletionItem> {
+        tracing::debug!("Completion resolve request: {}", item.label);
+        Ok(resolve_completion_item(item, &self.workspace))
+    }
+
     async fn goto_definition(
         &self,
         params: GotoDefinitionParams,
@@ -287,9 +542,31 @@ impl LanguageServer for ProtobufLanguageServer {
         }
     }
 
+    async fn on_type_formatting(
+        &self,
+        params: DocumentOnTypeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        tracing::debug!("On type formatting request: {:?}", params);
+        let uri = &params.text_document_position.text_document.uri;
+        let content: Option<String> = self.document_contents.get(uri).map(|s| s.clone());
+        Ok(provide_on_type_formatting(params, &self.workspace, content.as_deref()))
+    }
+
     async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
         tracing::info!("Configuration changed: {:?}", params);
 
+        self.workspace.update_settings(&params.settings);
+        if params.settings.get("inlayHints").is_some() {
+            if let Err(e) = self.client.inlay_hint_refresh().await {
+                tracing::debug!("Inlay hint refresh not supported: {}", e);
+            }
+        }
+        if params.settings.get("codeLens").is_some() {
+            if let Err(e) = self.client.code_lens_refresh().await {
+                tracing::debug!("Code lens refresh not supported: {}", e);
+            }
+        }
+
         // Handle configuration changes (e.g., additional proto directories)
         if let Some(settings) = params.settings.as_object() {
             if let Some(dirs) = settings.get("additionalProtoDirs") {
@@ -305,6 +582,68 @@ impl LanguageServer for ProtobufLanguageServer {
         }
     }
 
+    async fn document_highlight(
+        &self,
+        params: DocumentHighlightParams,
+    ) -> Result<Option<Vec<DocumentHighlight>>> {
+        tracing::debug!("Document highlight request: {:?}", params);
+        let uri = &params.text_document_position_params.text_document.uri;
+        let content: Option<String> = self.document_contents.get(uri).map(|s| s.clone());
+        Ok(provide_document_highlights(params, &self.workspace, content.as_deref()).await)
+    }
+
+    async fn prepare_call_hierarchy(
+        &self,
+        params: CallHierarchyPrepareParams,
+    ) -> Result<Option<Vec<CallHierarchyItem>>> {
+        tracing::debug!("Prepare call hierarchy request: {:?}", params);
+        let uri = &params.text_document_position_params.text_document.uri;
+        let content: Option<String> = self.document_contents.get(uri).map(|s| s.clone());
+        Ok(prepare_call_hierarchy(params, &self.workspace, content.as_deref()).await)
+    }
+
+    async fn incoming_calls(
+        &self,
+        params: CallHierarchyIncomingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyIncomingCall>>> {
+        tracing::debug!("Incoming calls request: {:?}", params);
+        Ok(incoming_calls(params, &self.workspace).await)
+    }
+
+    async fn outgoing_calls(
+        &self,
+        params: CallHierarchyOutgoingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+        tracing::debug!("Outgoing calls request: {:?}", params);
+        Ok(outgoing_calls(params, &self.workspace).await)
+    }
+
+    async fn prepare_type_hierarchy(
+        &self,
+        params: TypeHierarchyPrepareParams,
+    ) -> Result<Option<Vec<TypeHierarchyItem>>> {
+        tracing::debug!("Prepare type hierarchy request: {:?}", params);
+        let uri = &params.text_document_position_params.text_document.uri;
+        let content: Option<String> = self.document_contents.get(uri).map(|s| s.clone());
+        Ok(prepare_type_hierarchy(params, &self.workspace, content.as_deref()).await)
+    }
+
+    async fn supertypes(
+        &self,
+        params: TypeHierarchySupertypesParams,
+    ) -> Result<Option<Vec<TypeHierarchyItem>>> {
+        tracing::debug!("Supertypes request: {:?}", params);
+        Ok(supertypes(params, &self.workspace).await)
+    }
+
+    async fn subtypes(
+        &self,
+        params: TypeHierarchySubtypesParams,
+    ) -> Result<Option<Vec<TypeHierarchyItem>>> {
+        tracing::debug!("Subtypes request: {:?}", params);
+        Ok(subtypes(params, &self.workspace).await)
+    }
+
     async fn prepare_rename(
         &self,
         params: TextDocumentPositionParams,
@@ -312,14 +651,29 @@ impl LanguageServer for ProtobufLanguageServer {
         tracing::debug!("Prepare rename request: {:?}", params);
         let uri = &params.text_document.uri;
         let content: Option<String> = self.document_contents.get(uri).map(|s| s.clone());
-        Ok(prepare_rename(params, &self.workspace, content.as_deref()))
+        Ok(prepare_rename(params, &self.workspace, content.as_deref()).await)
     }
 
     async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
         tracing::debug!("Rename request: {:?}", params);
         let uri = &params.text_document_position.text_document.uri;
         let content: Option<String> = self.document_contents.get(uri).map(|s| s.clone());
-        Ok(rename(params, &self.workspace, content.as_deref()).await)
+        rename(params, &self.workspace, content.as_deref(), &self.edit_support())
+            .await
+            .map_err(tower_lsp::jsonrpc::Error::invalid_params)
+    }
+
+    async fn will_rename_files(&self, params: RenameFilesParams) -> Result<Option<WorkspaceEdit>> {
+        tracing::debug!("Will rename files request: {:?}", params);
+        Ok(will_rename_files(params, &self.workspace, &self.edit_support()).await)
+    }
+
+    async fn did_rename_files(&self, params: RenameFilesParams) {
+        tracing::debug!("Did rename files: {:?}", params);
+        for file in &params.files {
+            let (Ok(old), Ok(new)) = (Url::parse(&file.old_uri), Url::parse(&file.new_uri)) else { continue };
+            self.workspace.rename_path(&old, &new).await;
+        }
     }
 
     async fn symbol(
@@ -354,6 +708,32 @@ impl LanguageServer for ProtobufLanguageServer {
         Ok(provide_semantic_tokens_full(params, &self.workspace, content.as_deref()))
     }
 
+    async fn selection_range(&self, params: SelectionRangeParams) -> Result<Option<Vec<SelectionRange>>> {
+        tracing::debug!("Selection range request: {:?}", params);
+        let uri = &params.text_document.uri;
+        let content: Option<String> = self.document_contents.get(uri).map(|s| s.clone());
+        Ok(provide_selection_ranges(params, &self.workspace, content.as_deref()))
+    }
+
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+        tracing::debug!("Code lens request: {:?}", params);
+        Ok(provide_code_lenses(params, &self.workspace).await)
+    }
+
+    async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<serde_json::Value>> {
+        tracing::debug!("Execute command request: {:?}", params);
+        // Lenses that only display information run `NO_OP_COMMAND`; showing
+        // references is left to the client's own command
+        Ok(None)
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        tracing::debug!("Inlay hint request: {:?}", params);
+        let uri = &params.text_document.uri;
+        let content: Option<String> = self.document_contents.get(uri).map(|s| s.clone());
+        Ok(provide_inlay_hints(params, &self.workspace, content.as_deref()).await)
+    }
+
     async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
         tracing::debug!("Folding range request: {:?}", params);
         let uri = &params.text_document.uri;
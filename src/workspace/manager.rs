@@ -1,7 +1,9 @@
 use crate::parser::{ParsedProto, ImportResolver, ProtoParser, ParseError};
+use crate::workspace::{Settings, TypeTable};
 use anyhow::Result;
 use dashmap::DashMap;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tower_lsp::lsp_types::Url;
 
@@ -23,8 +25,20 @@ pub struct PackageSymbol {
     pub kind: SymbolKind,
     #[allow(dead_code)]
     pub package: String,
+    pub deprecated: bool,
 }
 
+/// A file or directory that can complete an import path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportCandidate {
+    /// Path as written in the import, relative to a search root; directories end with `/`
+    pub path: String,
+    pub is_dir: bool,
+}
+
+/// URI and generation of each file a cached `TypeTable` was built from
+type TableStamps = Vec<(String, u64)>;
+
 /// Thread-safe workspace manager for caching parsed proto files
 #[derive(Clone)]
 pub struct WorkspaceManager {
@@ -36,7 +50,19 @@ pub struct WorkspaceManager {
     /// Most recent parse errors per URI (for diagnostics). Empty/absent means the last
     /// parse was successful.
     last_errors: Arc<DashMap<String, Vec<ParseError>>>,
+    /// Source text of the most recent parse attempt per URI
+    contents: Arc<DashMap<String, String>>,
+    /// Generation stamp per URI, bumped on every parse attempt. Used to derive
+    /// diagnostic result IDs so unchanged files are not recomputed.
+    generations: Arc<DashMap<String, u64>>,
+    next_generation: Arc<AtomicU64>,
+    /// Table of the types visible from each file, with the generations of the
+    /// files it was built from. Stale once any of them is parsed again.
+    type_tables: Arc<DashMap<String, (TableStamps, Arc<TypeTable>)>>,
+    /// Workspace folders whose `.proto` files are indexed up front
+    roots: Arc<parking_lot::RwLock<Vec<PathBuf>>>,
     resolver: Arc<parking_lot::RwLock<ImportResolver>>,
+    settings: Arc<parking_lot::RwLock<Settings>>,
 }
 
 impl WorkspaceManager {
@@ -45,7 +71,13 @@ impl WorkspaceManager {
             files: Arc::new(DashMap::new()),
             last_good: Arc::new(DashMap::new()),
             last_errors: Arc::new(DashMap::new()),
+            contents: Arc::new(DashMap::new()),
+            generations: Arc::new(DashMap::new()),
+            next_generation: Arc::new(AtomicU64::new(1)),
+            type_tables: Arc::new(DashMap::new()),
+            roots: Arc::new(parking_lot::RwLock::new(Vec::new())),
             resolver: Arc::new(parking_lot::RwLock::new(ImportResolver::new(vec![]))),
+            settings: Arc::new(parking_lot::RwLock::new(Settings::default())),
         }
     }
 
@@ -55,7 +87,13 @@ impl WorkspaceManager {
             files: Arc::new(DashMap::new()),
             last_good: Arc::new(DashMap::new()),
             last_errors: Arc::new(DashMap::new()),
+            contents: Arc::new(DashMap::new()),
+            generations: Arc::new(DashMap::new()),
+            next_generation: Arc::new(AtomicU64::new(1)),
+            type_tables: Arc::new(DashMap::new()),
+            roots: Arc::new(parking_lot::RwLock::new(Vec::new())),
             resolver: Arc::new(parking_lot::RwLock::new(ImportResolver::new(dirs))),
+            settings: Arc::new(parking_lot::RwLock::new(Settings::default())),
         }
     }
 
@@ -70,6 +108,10 @@ impl WorkspaceManager {
         let uri_str = uri.to_string();
         let parser = ProtoParser::new();
 
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        self.generations.insert(uri_str.clone(), generation);
+        self.contents.insert(uri_str.clone(), content.to_string());
+
         match parser.parse(uri_str.clone(), content).await {
             Ok(parsed) => {
                 let parsed_arc = Arc::new(parsed);
@@ -117,6 +159,89 @@ impl WorkspaceManager {
         self.files.remove(&uri_str);
         self.last_good.remove(&uri_str);
         self.last_errors.remove(&uri_str);
+        self.generations.remove(&uri_str);
+        self.contents.remove(&uri_str);
+        self.type_tables.remove(&uri_str);
+    }
+
+    /// Moves the cached files at `old`, a file or a directory, to `new`. Each
+    /// file is parsed again under its new URI and dropped under the old one, so
+    /// stale paths do not linger as duplicate definitions.
+    pub async fn rename_path(&self, old: &Url, new: &Url) {
+        let (Some(old_path), Some(new_path)) = (url_to_path(old), url_to_path(new)) else { return };
+        for uri in self.get_tracked_uris() {
+            let Some(path) = url_to_path(&uri) else { continue };
+            let Ok(rest) = path.strip_prefix(&old_path) else { continue };
+            let moved = if rest.as_os_str().is_empty() { new_path.clone() } else { new_path.join(rest) };
+            let Some(moved_uri) = path_to_url(&moved) else { continue };
+            if let Some(content) = self.get_content(&uri) {
+                let _ = self.open_file(&moved_uri, &content).await;
+            }
+            self.close_file(&uri);
+        }
+    }
+
+    /// Returns the source text the file was last parsed from (even if that parse
+    /// failed)
+    pub fn get_content(&self, uri: &Url) -> Option<String> {
+        self.contents.get(&uri.to_string()).map(|c| c.clone())
+    }
+
+    /// Returns the generation stamp of the most recent parse attempt for a file,
+    /// or `None` if the file is not tracked.
+    pub fn get_generation(&self, uri: &Url) -> Option<u64> {
+        self.generations.get(&uri.to_string()).map(|g| *g)
+    }
+
+    /// Returns every URI with a recorded parse attempt, including files that have
+    /// never parsed successfully.
+    pub fn get_tracked_uris(&self) -> Vec<Url> {
+        self.generations
+            .iter()
+            .filter_map(|entry| Url::parse(entry.key()).ok())
+            .collect()
+    }
+
+    /// Registers a workspace folder to be indexed by `index_workspace`
+    pub fn add_workspace_root(&self, dir: PathBuf) {
+        let mut roots = self.roots.write();
+        if !roots.contains(&dir) {
+            roots.push(dir);
+        }
+    }
+
+    /// Returns true if the file lives under one of the workspace folders
+    pub fn is_in_workspace(&self, uri: &Url) -> bool {
+        match url_to_path(uri) {
+            Some(path) => self.roots.read().iter().any(|root| path.starts_with(root)),
+            None => false,
+        }
+    }
+
+    /// Parses every `.proto` file under the workspace folders that is not cached
+    /// yet. Returns the number of newly indexed files.
+    pub async fn index_workspace(&self) -> usize {
+        let roots: Vec<PathBuf> = self.roots.read().clone();
+        let mut paths = Vec::new();
+        for root in &roots {
+            collect_proto_files(root, &mut paths);
+        }
+
+        let mut indexed = 0;
+        for path in paths {
+            let Some(uri) = path_to_url(&path) else { continue };
+            if self.get_generation(&uri).is_some() {
+                continue;
+            }
+            let Ok(content) = std::fs::read_to_string(&path) else { continue };
+            // Files that fail to parse are still tracked (with their errors) so
+            // workspace diagnostics can report them.
+            let _ = self.open_file(&uri, &content).await;
+            indexed += 1;
+        }
+
+        tracing::info!("Indexed {} proto files from {} workspace folders", indexed, roots.len());
+        indexed
     }
 
     /// Resolves an import from a given file
@@ -133,6 +258,103 @@ impl WorkspaceManager {
         resolved
     }
 
+    /// Lists `.proto` files and directories that can complete the import path
+    /// `typed`, searching every root the resolver would. Directories are only
+    /// listed if they contain `.proto` files. Ancestors above the workspace
+    /// folder holding the file are not searched; outside any workspace folder,
+    /// the search stops at the nearest project root (see `PROJECT_MARKERS`), or
+    /// at the file's own directory without one.
+    pub fn list_import_candidates(&self, current_uri: &Url, typed: &str) -> Vec<ImportCandidate> {
+        let Some(current_path) = url_to_path(current_uri) else { return Vec::new() };
+        let mut roots = self.resolver.read().search_roots(&current_path);
+        let workspace_root = self
+            .roots
+            .read()
+            .iter()
+            .filter(|root| current_path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .cloned();
+        let boundary = workspace_root
+            .or_else(|| project_root(&current_path))
+            .or_else(|| current_path.parent().map(Path::to_path_buf));
+        if let Some(boundary) = boundary {
+            roots.retain(|dir| !(boundary.starts_with(dir) && *dir != boundary));
+        }
+
+        let (dir_part, name_prefix) = match typed.rfind('/') {
+            Some(i) => typed.split_at(i + 1),
+            None => ("", typed),
+        };
+
+        let mut candidates = std::collections::BTreeMap::new();
+        for root in roots {
+            let Ok(entries) = std::fs::read_dir(root.join(dir_part)) else { continue };
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if name.starts_with('.') || !name.starts_with(name_prefix) {
+                    continue;
+                }
+                let path = entry.path();
+                if path.is_dir() {
+                    if !SKIPPED_DIRS.contains(&name.as_str()) && contains_proto_files(&path, 4) {
+                        candidates.insert(format!("{}{}/", dir_part, name), true);
+                    }
+                } else if path.extension().is_some_and(|ext| ext == "proto") && path != current_path {
+                    candidates.insert(format!("{}{}", dir_part, name), false);
+                }
+            }
+        }
+
+        candidates
+            .into_iter()
+            .map(|(path, is_dir)| ImportCandidate { path, is_dir })
+            .collect()
+    }
+
+    /// Path to write in an `import` of `target_uri` from `current_uri`, such that
+    /// it resolves back to the target. Paths relative to additional proto
+    /// directories are preferred, then relative to the workspace folder, then
+    /// the other search roots in resolver order.
+    pub fn import_path_for(&self, current_uri: &Url, target_uri: &Url) -> Option<String> {
+        let current_path = url_to_path(current_uri)?;
+        let target_path = url_to_path(target_uri)?;
+        self.planned_import_path(&current_path, &target_path, Path::exists)
+    }
+
+    /// Like `import_path_for`, for files that may not be where they will be:
+    /// `exists` decides which paths hold a file, so imports can be computed
+    /// for a layout before files are moved there.
+    pub fn planned_import_path(&self, current_path: &Path, target_path: &Path, exists: impl Fn(&Path) -> bool) -> Option<String> {
+        let resolver = self.resolver.read();
+
+        let mut roots = resolver.additional_dirs().to_vec();
+        roots.extend(
+            self.roots
+                .read()
+                .iter()
+                .filter(|root| current_path.starts_with(root))
+                .cloned(),
+        );
+        roots.extend(resolver.search_roots(current_path));
+
+        roots.iter().find_map(|root| {
+            let relative = target_path.strip_prefix(root).ok()?;
+            let import_path = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            (resolver.resolve_with(current_path, &import_path, &exists).as_deref() == Some(target_path))
+                .then_some(import_path)
+        })
+    }
+
+    /// Resolves an import as `resolve_import` does, deciding which files exist
+    /// with `exists`
+    pub fn resolve_planned_import(&self, current_path: &Path, import_path: &str, exists: impl Fn(&Path) -> bool) -> Option<PathBuf> {
+        self.resolver.read().resolve_with(current_path, import_path, exists)
+    }
+
     /// Gets or loads an imported file (async version)
     pub async fn get_imported_file(&self, current_uri: &Url, import_path: &str) -> Option<Arc<ParsedProto>> {
         let resolved_path = self.resolve_import(current_uri, import_path)?;
@@ -157,6 +379,26 @@ impl WorkspaceManager {
         self.get_file(&import_uri)
     }
 
+    /// Table of the types visible from a file: the file itself and its
+    /// transitive imports. Built once and reused until the file or one of its
+    /// imports is parsed again.
+    pub async fn visible_types(&self, uri: &Url) -> Option<Arc<TypeTable>> {
+        let mut files = vec![self.get_file(uri)?];
+        files.extend(self.collect_all_imports_async(uri).await);
+        let stamps: TableStamps = files
+            .iter()
+            .map(|f| (f.uri.clone(), self.generations.get(&f.uri).map(|g| *g).unwrap_or(0)))
+            .collect();
+
+        let key = uri.to_string();
+        if let Some(cached) = self.type_tables.get(&key).filter(|cached| cached.0 == stamps) {
+            return Some(cached.1.clone());
+        }
+        let table = Arc::new(TypeTable::from_files(files.iter().map(|f| f.as_ref())));
+        self.type_tables.insert(key, (stamps, table.clone()));
+        Some(table)
+    }
+
     /// Recursively collects all imported files (including transitive imports)
     pub async fn collect_all_imports_async(&self, current_uri: &Url) -> Vec<Arc<ParsedProto>> {
         tracing::debug!("Collecting all imports for: {}", current_uri);
@@ -177,8 +419,18 @@ impl WorkspaceManager {
         all_imports
     }
 
+    /// Recursively collects imported files that are already cached, without
+    /// touching the filesystem (for synchronous request handlers)
+    pub fn collect_all_imports_cached(&self, current_uri: &Url) -> Vec<Arc<ParsedProto>> {
+        let mut all_imports = Vec::new();
+        let mut visited = std::collections::HashSet::new();
+        if let Some(proto) = self.get_file(current_uri) {
+            self.collect_imports_recursive(&proto, current_uri, &mut all_imports, &mut visited);
+        }
+        all_imports
+    }
+
     /// Helper function for recursive import collection
-    #[allow(dead_code)]
     fn collect_imports_recursive(
         &self,
         proto: &ParsedProto,
@@ -250,6 +502,24 @@ impl WorkspaceManager {
         }
     }
 
+    /// Returns the files among `candidates` that import any of `changed`, directly
+    /// or transitively. Files in `changed` are only included if they depend on
+    /// another changed file.
+    pub async fn find_dependents(&self, candidates: &[Url], changed: &[Url]) -> Vec<Url> {
+        let changed: std::collections::HashSet<&str> = changed.iter().map(|u| u.as_str()).collect();
+        let mut dependents = Vec::new();
+        for candidate in candidates {
+            let imports = self.collect_all_imports_async(candidate).await;
+            if imports
+                .iter()
+                .any(|imported| imported.uri != candidate.as_str() && changed.contains(imported.uri.as_str()))
+            {
+                dependents.push(candidate.clone());
+            }
+        }
+        dependents
+    }
+
     /// Gets all symbols grouped by package name
     #[allow(dead_code)]
     pub async fn get_symbols_by_package(&self, current_uri: &Url) -> std::collections::HashMap<String, Vec<PackageSymbol>> {
@@ -269,7 +539,7 @@ impl WorkspaceManager {
 
         for imported in &all_imports {
             tracing::debug!("Imported file: {} (package: {:?})", imported.uri, imported.package);
-            self.add_symbols_from_proto(&imported, &mut symbols_by_package);
+            self.add_symbols_from_proto(imported, &mut symbols_by_package);
         }
 
         // Log all packages and their symbol counts
@@ -298,7 +568,7 @@ impl WorkspaceManager {
 
         for imported in &all_imports {
             tracing::debug!("Imported file: {} (package: {:?})", imported.uri, imported.package);
-            self.add_symbols_from_proto(&imported, &mut symbols_by_package);
+            self.add_symbols_from_proto(imported, &mut symbols_by_package);
         }
 
         // Log all packages and their symbol counts
@@ -319,7 +589,7 @@ impl WorkspaceManager {
         tracing::debug!("Processing file with package: '{}', messages: {}, enums: {}, services: {}",
             package_name, proto.messages.len(), proto.enums.len(), proto.services.len());
 
-        let symbols = symbols_by_package.entry(package_name.clone()).or_insert_with(Vec::new);
+        let symbols = symbols_by_package.entry(package_name.clone()).or_default();
 
         // Add messages
         for msg in &proto.messages {
@@ -329,6 +599,7 @@ impl WorkspaceManager {
                 full_name: msg.full_name.clone(),
                 kind: SymbolKind::Message,
                 package: package_name.clone(),
+                deprecated: msg.deprecated,
             });
         }
 
@@ -340,6 +611,7 @@ impl WorkspaceManager {
                 full_name: enum_.full_name.clone(),
                 kind: SymbolKind::Enum,
                 package: package_name.clone(),
+                deprecated: enum_.deprecated,
             });
 
             // Add enum values
@@ -349,6 +621,7 @@ impl WorkspaceManager {
                     full_name: format!("{}.{}", enum_.full_name, value.name),
                     kind: SymbolKind::EnumValue,
                     package: package_name.clone(),
+                    deprecated: value.deprecated,
                 });
             }
         }
@@ -361,6 +634,7 @@ impl WorkspaceManager {
                 full_name: svc.full_name.clone(),
                 kind: SymbolKind::Service,
                 package: package_name.clone(),
+                deprecated: false,
             });
 
             // Add methods
@@ -370,6 +644,7 @@ impl WorkspaceManager {
                     full_name: format!("{}.{}", svc.full_name, method.name),
                     kind: SymbolKind::Method,
                     package: package_name.clone(),
+                    deprecated: method.deprecated,
                 });
             }
         }
@@ -381,31 +656,14 @@ impl WorkspaceManager {
         resolver.add_directory(dir);
     }
 
-    /// Finds a symbol across all open files
-    pub fn find_symbol(&self, symbol_name: &str) -> Vec<(String, String)> {
-        let mut results = Vec::new();
-
-        for entry in self.files.iter() {
-            let uri = entry.key();
-            let proto = entry.value();
-
-            // Search messages
-            if let Some(_msg) = proto.find_message_by_name(symbol_name) {
-                results.push((uri.clone(), "message".to_string()));
-            }
-
-            // Search enums
-            if let Some(_enum) = proto.find_enum_by_name(symbol_name) {
-                results.push((uri.clone(), "enum".to_string()));
-            }
-
-            // Search services
-            if let Some(_svc) = proto.find_service_by_name(symbol_name) {
-                results.push((uri.clone(), "service".to_string()));
-            }
-        }
+    /// Current user settings
+    pub fn settings(&self) -> Settings {
+        self.settings.read().clone()
+    }
 
-        results
+    /// Applies a settings object from the client, see `Settings::update`
+    pub fn update_settings(&self, value: &serde_json::Value) {
+        self.settings.write().update(value);
     }
 
     /// Gets all files in the workspace
@@ -431,6 +689,56 @@ fn path_to_url(path: &Path) -> Option<Url> {
     Url::from_file_path(path).ok()
 }
 
+/// Directories that never contain sources worth indexing
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "bazel-out"];
+
+/// Entries marking the root of a project checked out outside any workspace folder
+const PROJECT_MARKERS: &[&str] = &[".git", "buf.yaml", "buf.work.yaml"];
+
+/// Nearest ancestor directory of `file` holding one of `PROJECT_MARKERS`
+fn project_root(file: &Path) -> Option<PathBuf> {
+    file.ancestors()
+        .skip(1)
+        .find(|dir| PROJECT_MARKERS.iter().any(|marker| dir.join(marker).exists()))
+        .map(Path::to_path_buf)
+}
+
+/// Whether `dir` holds a `.proto` file within `depth` levels
+fn contains_proto_files(dir: &Path, depth: usize) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else { return false };
+    let mut subdirs = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref()) {
+                subdirs.push(path);
+            }
+        } else if path.extension().is_some_and(|ext| ext == "proto") {
+            return true;
+        }
+    }
+    depth > 1 && subdirs.iter().any(|sub| contains_proto_files(sub, depth - 1))
+}
+
+/// Recursively collects `.proto` files under `dir`, skipping hidden and build directories
+fn collect_proto_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if path.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref()) {
+                collect_proto_files(&path, out);
+            }
+        } else if path.extension().is_some_and(|ext| ext == "proto") {
+            out.push(path);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -458,4 +766,197 @@ message Person {
         manager.close_file(&url);
         assert!(manager.get_file(&url).is_none());
     }
+
+    #[tokio::test]
+    async fn test_generation_bumped_on_each_parse() {
+        let manager = WorkspaceManager::new();
+        let url = Url::parse("file:///test/gen.proto").unwrap();
+        assert!(manager.get_generation(&url).is_none());
+
+        manager.open_file(&url, "syntax = \"proto3\";").await.unwrap();
+        let first = manager.get_generation(&url).unwrap();
+        manager.open_file(&url, "syntax = \"proto3\";").await.unwrap();
+        assert!(manager.get_generation(&url).unwrap() > first);
+
+        manager.close_file(&url);
+        assert!(manager.get_generation(&url).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rename_path() {
+        let manager = WorkspaceManager::new();
+        let base = Url::parse("file:///test/common/base.proto").unwrap();
+        let types = Url::parse("file:///test/common/types.proto").unwrap();
+        let other = Url::parse("file:///test/commons/other.proto").unwrap();
+        for uri in [&base, &types, &other] {
+            manager.open_file(uri, "syntax = \"proto3\";\npackage common;\n").await.unwrap();
+        }
+
+        manager
+            .rename_path(&types, &Url::parse("file:///test/shared/types.proto").unwrap())
+            .await;
+        manager
+            .rename_path(&Url::parse("file:///test/common").unwrap(), &Url::parse("file:///test/core").unwrap())
+            .await;
+
+        let mut uris: Vec<String> = manager.get_tracked_uris().iter().map(|uri| uri.path().to_string()).collect();
+        uris.sort();
+        assert_eq!(uris, ["/test/commons/other.proto", "/test/core/base.proto", "/test/shared/types.proto"]);
+        let moved = manager.get_file(&Url::parse("file:///test/core/base.proto").unwrap()).unwrap();
+        assert_eq!(moved.uri, "file:///test/core/base.proto");
+        assert!(manager.get_file(&base).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("api/v1")).unwrap();
+        std::fs::create_dir_all(dir.path().join(".git")).unwrap();
+        std::fs::write(dir.path().join("api/v1/a.proto"), "syntax = \"proto3\";\nmessage A {}\n").unwrap();
+        std::fs::write(dir.path().join("b.proto"), "message {").unwrap();
+        std::fs::write(dir.path().join(".git/c.proto"), "syntax = \"proto3\";").unwrap();
+        std::fs::write(dir.path().join("README.md"), "").unwrap();
+
+        let manager = WorkspaceManager::new();
+        manager.add_workspace_root(dir.path().to_path_buf());
+        assert_eq!(manager.index_workspace().await, 2);
+
+        // Broken files are tracked even though they have no parse result
+        let broken = Url::from_file_path(dir.path().join("b.proto")).unwrap();
+        assert!(manager.get_file(&broken).is_none());
+        assert!(manager.get_tracked_uris().contains(&broken));
+        assert!(manager.is_in_workspace(&broken));
+
+        // A second pass only picks up new files
+        assert_eq!(manager.index_workspace().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_find_dependents() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, content: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            Url::from_file_path(path).unwrap()
+        };
+        let common = write("common.proto", "syntax = \"proto3\";\nmessage Id {}\n");
+        let user = write("user.proto", "syntax = \"proto3\";\nimport \"common.proto\";\nmessage User { Id id = 1; }\n");
+        let api = write("api.proto", "syntax = \"proto3\";\nimport \"user.proto\";\nmessage Req { User user = 1; }\n");
+        let other = write("other.proto", "syntax = \"proto3\";\nmessage Other {}\n");
+
+        let manager = WorkspaceManager::new();
+        for uri in [&common, &user, &api, &other] {
+            let content = std::fs::read_to_string(uri.to_file_path().unwrap()).unwrap();
+            manager.open_file(uri, &content).await.unwrap();
+        }
+
+        let candidates = vec![common.clone(), user.clone(), api.clone(), other.clone()];
+        let dependents = manager.find_dependents(&candidates, std::slice::from_ref(&common)).await;
+        assert_eq!(dependents, vec![user, api]);
+    }
+
+    #[tokio::test]
+    async fn test_visible_types() {
+        let dir = tempfile::tempdir().unwrap();
+        let common_path = dir.path().join("common.proto");
+        let user_path = dir.path().join("user.proto");
+        std::fs::write(&common_path, "syntax = \"proto3\";\nmessage Id {}\n").unwrap();
+        std::fs::write(&user_path, "syntax = \"proto3\";\nimport \"common.proto\";\nmessage User {}\n").unwrap();
+        let common = Url::from_file_path(&common_path).unwrap();
+        let user = Url::from_file_path(&user_path).unwrap();
+
+        let manager = WorkspaceManager::new();
+        manager.open_file(&common, "syntax = \"proto3\";\nmessage Id {}\n").await.unwrap();
+        manager
+            .open_file(&user, "syntax = \"proto3\";\nimport \"common.proto\";\nmessage User {}\n")
+            .await
+            .unwrap();
+
+        let table = manager.visible_types(&user).await.unwrap();
+        assert!(table.resolve("Id", "").is_some());
+        assert!(Arc::ptr_eq(&table, &manager.visible_types(&user).await.unwrap()));
+
+        // Parsing an import again rebuilds the table
+        manager.open_file(&common, "syntax = \"proto3\";\nmessage Key {}\n").await.unwrap();
+        let table = manager.visible_types(&user).await.unwrap();
+        assert!(table.resolve("Id", "").is_none());
+        assert!(table.resolve("Key", "").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_list_import_candidates() {
+        let additional = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(additional.path().join("google/protobuf")).unwrap();
+        std::fs::write(additional.path().join("google/protobuf/timestamp.proto"), "").unwrap();
+        std::fs::create_dir_all(additional.path().join("docs")).unwrap();
+        std::fs::write(additional.path().join("docs/readme.md"), "").unwrap();
+
+        let workspace_dir = tempfile::tempdir().unwrap();
+        let api_dir = workspace_dir.path().join("api");
+        std::fs::create_dir_all(api_dir.join("v1")).unwrap();
+        std::fs::write(api_dir.join("v1/user.proto"), "").unwrap();
+        std::fs::write(api_dir.join("common.proto"), "").unwrap();
+        std::fs::write(api_dir.join("service.proto"), "").unwrap();
+
+        let manager = WorkspaceManager::with_additional_dirs(vec![additional.path().to_path_buf()]);
+        manager.add_workspace_root(workspace_dir.path().to_path_buf());
+        let uri = Url::from_file_path(api_dir.join("service.proto")).unwrap();
+
+        let paths = |typed: &str| -> Vec<String> {
+            manager.list_import_candidates(&uri, typed).into_iter().map(|c| c.path).collect()
+        };
+
+        // Directories without .proto files and the current file are left out;
+        // the workspace root is searched as an ancestor
+        assert_eq!(paths(""), vec!["api/", "common.proto", "google/", "v1/"]);
+        assert_eq!(paths("google/pro"), vec!["google/protobuf/"]);
+        assert_eq!(paths("google/protobuf/"), vec!["google/protobuf/timestamp.proto"]);
+        assert_eq!(paths("api/v1/u"), vec!["api/v1/user.proto"]);
+        assert!(manager.list_import_candidates(&uri, "")[0].is_dir);
+    }
+
+    #[tokio::test]
+    async fn test_list_import_candidates_outside_workspace() {
+        let project = tempfile::tempdir().unwrap();
+        let api_dir = project.path().join("api");
+        std::fs::create_dir_all(&api_dir).unwrap();
+        std::fs::write(project.path().join("common.proto"), "").unwrap();
+        std::fs::write(api_dir.join("user.proto"), "").unwrap();
+        std::fs::write(api_dir.join("service.proto"), "").unwrap();
+
+        let manager = WorkspaceManager::new();
+        let uri = Url::from_file_path(api_dir.join("service.proto")).unwrap();
+        let paths = |typed: &str| -> Vec<String> {
+            manager.list_import_candidates(&uri, typed).into_iter().map(|c| c.path).collect()
+        };
+
+        // Without a project root only the file's directory is searched
+        assert_eq!(paths(""), vec!["user.proto"]);
+
+        // Ancestors are searched up to the nearest project root, not beyond
+        std::fs::write(project.path().join("buf.yaml"), "version: v1\n").unwrap();
+        assert_eq!(paths(""), vec!["api/", "common.proto", "user.proto"]);
+    }
+
+    #[tokio::test]
+    async fn test_import_path_for() {
+        let additional = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(additional.path().join("google/type")).unwrap();
+        std::fs::write(additional.path().join("google/type/date.proto"), "").unwrap();
+
+        let workspace_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(workspace_dir.path().join("api/v1")).unwrap();
+        std::fs::write(workspace_dir.path().join("api/v1/user.proto"), "").unwrap();
+        std::fs::write(workspace_dir.path().join("api/service.proto"), "").unwrap();
+
+        let manager = WorkspaceManager::with_additional_dirs(vec![additional.path().to_path_buf()]);
+        manager.add_workspace_root(workspace_dir.path().to_path_buf());
+        let uri = Url::from_file_path(workspace_dir.path().join("api/service.proto")).unwrap();
+        let path_of = |target: PathBuf| manager.import_path_for(&uri, &Url::from_file_path(target).unwrap());
+
+        assert_eq!(path_of(additional.path().join("google/type/date.proto")).as_deref(), Some("google/type/date.proto"));
+        // Relative to the workspace folder rather than the importing file
+        assert_eq!(path_of(workspace_dir.path().join("api/v1/user.proto")).as_deref(), Some("api/v1/user.proto"));
+        assert_eq!(path_of(workspace_dir.path().join("missing.proto")), None);
+    }
 }
@@ -1,3 +1,7 @@
 pub mod manager;
+pub mod settings;
+pub mod symbol_table;
 
 pub use manager::{WorkspaceManager, SymbolKind};
+pub use settings::Settings;
+pub use symbol_table::{TypeDefinition, TypeKind, TypeTable};
@@ -0,0 +1,127 @@
+use serde_json::Value;
+
+/// Standard methods of the AIP service template, in template order
+pub const STANDARD_METHODS: &[&str] = &["Get", "List", "Create", "Update", "Delete"];
+
+/// Client command code lenses show their locations with by default: VS Code's
+/// references peek view
+pub const SHOW_REFERENCES_COMMAND: &str = "editor.action.showReferences";
+
+/// User settings, read from the initialization options and
+/// `workspace/didChangeConfiguration`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    /// Methods generated by the "standard methods" service snippet. Empty
+    /// disables the snippet.
+    pub standard_methods: Vec<String>,
+    pub inlay_hints: InlayHintSettings,
+    /// Client-side command counting code lenses run with the document URI, the
+    /// lens position and the counted locations
+    pub show_references_command: String,
+}
+
+/// Which inlay hints are shown; all are on by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InlayHintSettings {
+    /// Fully-qualified type after short or relative type names
+    pub resolved_types: bool,
+    /// Effective JSON name after field names
+    pub json_names: bool,
+    /// Next free number at the closing brace of messages and enums
+    pub next_number: bool,
+}
+
+impl Default for InlayHintSettings {
+    fn default() -> Self {
+        Self { resolved_types: true, json_names: true, next_number: true }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            standard_methods: STANDARD_METHODS.iter().map(|m| m.to_string()).collect(),
+            inlay_hints: InlayHintSettings::default(),
+            show_references_command: SHOW_REFERENCES_COMMAND.to_string(),
+        }
+    }
+}
+
+impl Settings {
+    /// Applies the recognized keys of a settings object, leaving the others unchanged:
+    ///
+    /// ```json
+    /// {
+    ///   "snippets": { "standardMethods": ["Get", "List"] },
+    ///   "inlayHints": { "resolvedTypes": true, "jsonNames": false, "nextNumber": true },
+    ///   "codeLens": { "showReferencesCommand": "editor.action.showReferences" }
+    /// }
+    /// ```
+    ///
+    /// `"standardMethods": false` disables the standard methods snippet.
+    pub fn update(&mut self, value: &Value) {
+        if let Some(hints) = value.get("inlayHints") {
+            let flags = [
+                ("resolvedTypes", &mut self.inlay_hints.resolved_types),
+                ("jsonNames", &mut self.inlay_hints.json_names),
+                ("nextNumber", &mut self.inlay_hints.next_number),
+            ];
+            for (key, flag) in flags {
+                if let Some(enabled) = hints.get(key).and_then(Value::as_bool) {
+                    *flag = enabled;
+                }
+            }
+        }
+
+        if let Some(command) = value.pointer("/codeLens/showReferencesCommand").and_then(Value::as_str) {
+            self.show_references_command = command.to_string();
+        }
+
+        let Some(snippets) = value.get("snippets") else {
+            return;
+        };
+        match snippets.get("standardMethods") {
+            Some(Value::Array(methods)) => {
+                self.standard_methods = methods
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .filter_map(|m| STANDARD_METHODS.iter().find(|s| s.eq_ignore_ascii_case(m)))
+                    .map(|m| m.to_string())
+                    .collect();
+            }
+            Some(Value::Bool(false)) => self.standard_methods.clear(),
+            Some(Value::Bool(true)) => self.standard_methods = Settings::default().standard_methods,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_settings() {
+        let mut settings = Settings::default();
+        settings.update(&serde_json::json!({ "additionalProtoDirs": [] }));
+        assert_eq!(settings, Settings::default());
+
+        // Unknown methods are dropped, names are normalized
+        settings.update(&serde_json::json!({ "snippets": { "standardMethods": ["get", "List", "Watch"] } }));
+        assert_eq!(settings.standard_methods, vec!["Get", "List"]);
+
+        settings.update(&serde_json::json!({ "snippets": { "standardMethods": false } }));
+        assert!(settings.standard_methods.is_empty());
+
+        // Inlay hint kinds are switched individually
+        settings.update(&serde_json::json!({ "inlayHints": { "jsonNames": false, "nextNumber": "no" } }));
+        assert_eq!(
+            settings.inlay_hints,
+            InlayHintSettings { resolved_types: true, json_names: false, next_number: true }
+        );
+        assert!(settings.standard_methods.is_empty());
+
+        settings.update(&serde_json::json!({ "codeLens": { "showReferencesCommand": "myEditor.peekLocations" } }));
+        assert_eq!(settings.show_references_command, "myEditor.peekLocations");
+    }
+}
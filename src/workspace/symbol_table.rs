@@ -0,0 +1,242 @@
+use crate::parser::proto::{EnumElement, MessageElement};
+use crate::parser::ParsedProto;
+use std::collections::{HashMap, HashSet};
+
+/// Kind of a type that can be referenced from a field or RPC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeKind {
+    Message,
+    Enum,
+}
+
+/// A message or enum definition, keyed by its fully-qualified name
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct TypeDefinition {
+    pub name: String,
+    /// Fully-qualified name without a leading dot, e.g. "pkg.Outer.Inner"
+    pub full_name: String,
+    pub kind: TypeKind,
+    pub uri: String,
+    /// Set by `option deprecated = true;`
+    pub deprecated: bool,
+    /// Names of enum values marked `[deprecated = true]` (enums only)
+    pub deprecated_values: Vec<String>,
+    pub line: u32,
+    pub character: u32,
+}
+
+/// Index of the types visible from a file (the file itself plus its imports),
+/// resolving names with protoc's scoping rules.
+#[derive(Debug, Default)]
+pub struct TypeTable {
+    types: HashMap<String, TypeDefinition>,
+    /// Every package and package prefix, e.g. "a" and "a.b" for `package a.b;`
+    packages: HashSet<String>,
+}
+
+impl TypeTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_files<'a>(files: impl IntoIterator<Item = &'a ParsedProto>) -> Self {
+        let mut table = Self::new();
+        for proto in files {
+            table.add_file(proto);
+        }
+        table
+    }
+
+    /// Adds every message and enum of a file, including nested ones
+    pub fn add_file(&mut self, proto: &ParsedProto) {
+        if let Some(pkg) = &proto.package {
+            let mut prefix = String::new();
+            for part in pkg.split('.') {
+                if !prefix.is_empty() {
+                    prefix.push('.');
+                }
+                prefix.push_str(part);
+                self.packages.insert(prefix.clone());
+            }
+        }
+        for msg in &proto.messages {
+            self.add_message(msg, &proto.uri);
+        }
+        for e in &proto.enums {
+            self.add_enum(e, &proto.uri);
+        }
+    }
+
+    fn add_message(&mut self, msg: &MessageElement, uri: &str) {
+        self.types.insert(
+            msg.full_name.clone(),
+            TypeDefinition {
+                name: msg.name.clone(),
+                full_name: msg.full_name.clone(),
+                kind: TypeKind::Message,
+                uri: uri.to_string(),
+                deprecated: msg.deprecated,
+                deprecated_values: Vec::new(),
+                line: msg.line,
+                character: msg.character,
+            },
+        );
+        for nested in &msg.nested_messages {
+            self.add_message(nested, uri);
+        }
+        for e in &msg.nested_enums {
+            self.add_enum(e, uri);
+        }
+    }
+
+    fn add_enum(&mut self, e: &EnumElement, uri: &str) {
+        self.types.insert(
+            e.full_name.clone(),
+            TypeDefinition {
+                name: e.name.clone(),
+                full_name: e.full_name.clone(),
+                kind: TypeKind::Enum,
+                uri: uri.to_string(),
+                deprecated: e.deprecated,
+                deprecated_values: e
+                    .values
+                    .iter()
+                    .filter(|v| v.deprecated)
+                    .map(|v| v.name.clone())
+                    .collect(),
+                line: e.line,
+                character: e.character,
+            },
+        );
+    }
+
+    /// Resolves a type name as written in `scope` (the fully-qualified name of the
+    /// enclosing message, or the package).
+    ///
+    /// Like protoc, the first component of a relative name is looked up from the
+    /// innermost scope outwards; the rest of the name must then exist under the
+    /// first match. `Outer.Inner` therefore does not fall back to an outer scope
+    /// once some `Outer` has been found.
+    pub fn resolve(&self, name: &str, scope: &str) -> Option<&TypeDefinition> {
+        if let Some(absolute) = name.strip_prefix('.') {
+            return self.types.get(absolute);
+        }
+
+        let first = name.split('.').next().unwrap_or(name);
+        let mut scope = scope;
+        loop {
+            let candidate_first = join(scope, first);
+            if self.types.contains_key(&candidate_first) || self.packages.contains(&candidate_first) {
+                let candidate = join(scope, name);
+                if let Some(def) = self.types.get(&candidate) {
+                    return Some(def);
+                }
+                // A package prefix may be shared by several packages ("a.b" and
+                // "a.c"), so only a type match stops the search.
+                if self.types.contains_key(&candidate_first) {
+                    return None;
+                }
+            }
+            if scope.is_empty() {
+                return None;
+            }
+            scope = scope.rfind('.').map(|i| &scope[..i]).unwrap_or("");
+        }
+    }
+
+    /// Shortest name that resolves to `full_name` when written in `scope`: `Inner`
+    /// inside `Outer`, `Outer.Inner` elsewhere in the package, and so on. Falls
+    /// back to the absolute `.full_name` when every relative name is shadowed.
+    pub fn shortest_name(&self, full_name: &str, scope: &str) -> String {
+        let parts: Vec<&str> = full_name.split('.').collect();
+        (0..parts.len())
+            .rev()
+            .map(|start| parts[start..].join("."))
+            .find(|name| self.resolve(name, scope).is_some_and(|def| def.full_name == full_name))
+            .unwrap_or_else(|| format!(".{}", full_name))
+    }
+}
+
+fn join(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn table(sources: &[&str]) -> TypeTable {
+        let mut files = Vec::new();
+        for (i, source) in sources.iter().enumerate() {
+            let uri = format!("file:///test/{}.proto", i);
+            files.push(ParsedProto::parse(uri, source).await.unwrap());
+        }
+        TypeTable::from_files(files.iter())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_scoping() {
+        let table = table(&[
+            r#"syntax = "proto3";
+package a.b;
+message Outer {
+  message Inner {}
+  enum Status { STATUS_UNSPECIFIED = 0; }
+}
+message Inner {}
+"#,
+            r#"syntax = "proto3";
+package a.c;
+message Other {}
+"#,
+        ])
+        .await;
+
+        // Innermost scope wins
+        assert_eq!(table.resolve("Inner", "a.b.Outer").unwrap().full_name, "a.b.Outer.Inner");
+        assert_eq!(table.resolve("Inner", "a.b").unwrap().full_name, "a.b.Inner");
+        assert_eq!(table.resolve("Outer.Status", "a.b").unwrap().kind, TypeKind::Enum);
+
+        // Package-relative and absolute names
+        assert_eq!(table.resolve("c.Other", "a.b").unwrap().full_name, "a.c.Other");
+        assert_eq!(table.resolve("a.c.Other", "a.b.Outer").unwrap().full_name, "a.c.Other");
+        assert_eq!(table.resolve(".a.b.Inner", "a.b.Outer").unwrap().full_name, "a.b.Inner");
+
+        // `Outer` is found first, so `Outer.Missing` does not fall back further
+        assert!(table.resolve("Outer.Missing", "a.b").is_none());
+        assert!(table.resolve("Nope", "a.b").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_shortest_name() {
+        let table = table(&[
+            r#"syntax = "proto3";
+package a.b;
+message Outer {
+  message Inner {}
+  message Other { message Inner {} }
+}
+message Inner {}
+"#,
+            r#"syntax = "proto3";
+package a.c;
+message Other {}
+"#,
+        ])
+        .await;
+
+        assert_eq!(table.shortest_name("a.b.Outer.Inner", "a.b.Outer"), "Inner");
+        assert_eq!(table.shortest_name("a.b.Outer.Inner", "a.b"), "Outer.Inner");
+        assert_eq!(table.shortest_name("a.b.Inner", "a.b"), "Inner");
+        // `Inner` means Outer.Inner inside Outer
+        assert_eq!(table.shortest_name("a.b.Inner", "a.b.Outer"), "b.Inner");
+        assert_eq!(table.shortest_name("a.c.Other", "a.b"), "c.Other");
+        // `Other` is Outer.Other there
+        assert_eq!(table.shortest_name("a.c.Other", "a.b.Outer"), "c.Other");
+    }
+}
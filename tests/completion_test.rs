@@ -1,7 +1,8 @@
-use protobuf_lsp::features::provide_completion;
+use protobuf_lsp::features::{provide_completion, resolve_completion_item};
 use protobuf_lsp::workspace::WorkspaceManager;
 use tower_lsp::lsp_types::{
-    CompletionParams, CompletionResponse, PartialResultParams, Position, TextDocumentIdentifier,
+    CompletionItemTag, CompletionParams, CompletionResponse, Documentation, PartialResultParams, Position,
+    TextDocumentIdentifier,
     TextDocumentPositionParams, Url, WorkDoneProgressParams,
 };
 
@@ -565,3 +566,379 @@ message MainMessage {
         labels
     );
 }
+
+#[tokio::test]
+async fn test_completion_marks_deprecated_types() {
+    let workspace = WorkspaceManager::new();
+    let uri = Url::parse("file:///test/deprecated.proto").unwrap();
+    let content = r#"syntax = "proto3";
+package test;
+
+message Legacy {
+    option deprecated = true;
+}
+
+message Current {
+    string name = 1;
+}
+
+message User {
+    
+}
+"#;
+    workspace.open_file(&uri, content).await.unwrap();
+
+    let params = CompletionParams {
+        text_document_position: TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            position: Position { line: 12, character: 4 },
+        },
+        context: None,
+        work_done_progress_params: WorkDoneProgressParams::default(),
+        partial_result_params: PartialResultParams::default(),
+    };
+    let Some(CompletionResponse::Array(items)) =
+        provide_completion(params, &workspace, Some(content)).await
+    else {
+        panic!("expected CompletionResponse::Array");
+    };
+
+    let tags_of = |label: &str| {
+        items
+            .iter()
+            .find(|i| i.label == label)
+            .unwrap_or_else(|| panic!("missing completion item {}", label))
+            .tags
+            .clone()
+    };
+    assert_eq!(tags_of("Legacy"), Some(vec![CompletionItemTag::DEPRECATED]));
+    assert_eq!(tags_of("Current"), None);
+}
+
+#[tokio::test]
+async fn test_completion_suggests_next_field_number() {
+    let workspace = WorkspaceManager::new();
+    let url = Url::parse("file:///test/numbers.proto").unwrap();
+    let complete = r#"syntax = "proto3";
+package test;
+
+message User {
+    reserved 4;
+    string name = 1;
+    oneof contact {
+        string phone = 3;
+    }
+}
+
+enum Color {
+    COLOR_UNSPECIFIED = 0;
+}
+"#;
+    workspace.open_file(&url, complete).await.unwrap();
+
+    let in_progress = complete.replace(
+        "    string name = 1;\n",
+        "    string name = 1;\n    string email = \n",
+    );
+    workspace.open_file(&url, &in_progress).await.unwrap();
+    let labels = run_completion(&workspace, &url, &in_progress, Position { line: 6, character: 19 }).await;
+    // Next after the oneof's 3 skips reserved 4; 2 is the gap below it
+    assert_eq!(labels, vec!["5", "2"]);
+
+    let in_progress = complete.replace(
+        "    COLOR_UNSPECIFIED = 0;\n",
+        "    COLOR_UNSPECIFIED = 0;\n    COLOR_RED = \n",
+    );
+    workspace.open_file(&url, &in_progress).await.unwrap();
+    let labels = run_completion(&workspace, &url, &in_progress, Position { line: 13, character: 16 }).await;
+    assert_eq!(labels, vec!["1"]);
+}
+
+#[tokio::test]
+async fn test_completion_suggests_import_paths() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("shared")).unwrap();
+    std::fs::write(dir.path().join("shared/a.proto"), "syntax = \"proto3\";\npackage shared;\n").unwrap();
+    std::fs::write(dir.path().join("shared/b.proto"), "syntax = \"proto3\";\npackage shared;\n").unwrap();
+
+    let workspace = WorkspaceManager::new();
+    let url = Url::from_file_path(dir.path().join("main.proto")).unwrap();
+    let valid = "syntax = \"proto3\";\nimport \"shared/a.proto\";\n";
+    workspace.open_file(&url, valid).await.unwrap();
+
+    let typing = "syntax = \"proto3\";\nimport \"shared/a.proto\";\nimport \"sh\n";
+    let labels = run_completion(&workspace, &url, typing, Position { line: 2, character: 10 }).await;
+    assert_eq!(labels, vec!["shared/"]);
+
+    let typing = "syntax = \"proto3\";\nimport \"shared/a.proto\";\nimport \"shared/\n";
+    let labels = run_completion(&workspace, &url, typing, Position { line: 2, character: 15 }).await;
+    // a.proto is already imported
+    assert_eq!(labels, vec!["shared/b.proto"]);
+}
+
+#[tokio::test]
+async fn test_completion_auto_imports_types_from_unimported_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("shared")).unwrap();
+    let money = "syntax = \"proto3\";\npackage shared;\n\nmessage Money {\n    int64 units = 1;\n}\n";
+    let order = "syntax = \"proto3\";\npackage api;\n\nenum Status {\n    STATUS_UNSPECIFIED = 0;\n}\n";
+    let common = "syntax = \"proto3\";\npackage api;\n\nmessage Empty {}\n";
+    std::fs::write(dir.path().join("shared/money.proto"), money).unwrap();
+    std::fs::write(dir.path().join("status.proto"), order).unwrap();
+    std::fs::write(dir.path().join("common.proto"), common).unwrap();
+
+    let workspace = WorkspaceManager::new();
+    for (name, content) in [("shared/money.proto", money), ("status.proto", order), ("common.proto", common)] {
+        let uri = Url::from_file_path(dir.path().join(name)).unwrap();
+        workspace.open_file(&uri, content).await.unwrap();
+    }
+
+    let uri = Url::from_file_path(dir.path().join("main.proto")).unwrap();
+    let content = "syntax = \"proto3\";\npackage api;\n\nimport \"common.proto\";\n\nmessage Order {\n    \n}\n";
+    workspace.open_file(&uri, content).await.unwrap();
+
+    let params = CompletionParams {
+        text_document_position: TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            position: Position { line: 6, character: 4 },
+        },
+        context: None,
+        work_done_progress_params: WorkDoneProgressParams::default(),
+        partial_result_params: PartialResultParams::default(),
+    };
+    let Some(CompletionResponse::Array(items)) =
+        provide_completion(params, &workspace, Some(content)).await
+    else {
+        panic!("expected CompletionResponse::Array");
+    };
+    let item = |label: &str| {
+        items
+            .iter()
+            .find(|i| i.label == label && i.additional_text_edits.is_some())
+            .unwrap_or_else(|| panic!("missing auto-import item {}", label))
+    };
+
+    // Other packages are fully qualified; the import sorts after common.proto
+    let money_item = item("Money");
+    assert_eq!(money_item.insert_text.as_deref(), Some("shared.Money"));
+    let edit = &money_item.additional_text_edits.as_ref().unwrap()[0];
+    assert_eq!(edit.new_text, "import \"shared/money.proto\";\n");
+    assert_eq!(edit.range.start, Position { line: 4, character: 0 });
+
+    // Same package needs no qualifier
+    let status_item = item("Status");
+    assert_eq!(status_item.insert_text.as_deref(), Some("Status"));
+    let edit = &status_item.additional_text_edits.as_ref().unwrap()[0];
+    assert_eq!(edit.new_text, "import \"status.proto\";\n");
+
+    // Already imported types are not offered again
+    assert!(!items.iter().any(|i| i.label == "Empty" && i.additional_text_edits.is_some()));
+}
+
+#[tokio::test]
+async fn test_completion_names_types_from_cursor_scope() {
+    let workspace = WorkspaceManager::new();
+    let uri = Url::parse("file:///test/scope.proto").unwrap();
+    let content = r#"syntax = "proto3";
+package test;
+
+message Outer {
+    message Inner {}
+    enum Kind {
+        KIND_UNSPECIFIED = 0;
+    }
+    
+}
+
+message Other {
+    
+}
+"#;
+    workspace.open_file(&uri, content).await.unwrap();
+
+    // Inside Outer its nested types are siblings: short names, ranked first
+    let labels = run_completion(&workspace, &uri, content, Position { line: 8, character: 4 }).await;
+    let inner = labels.iter().position(|l| l == "Inner").expect("Inner in Outer");
+    let kind = labels.iter().position(|l| l == "Kind").expect("Kind in Outer");
+    let other = labels.iter().position(|l| l == "Other").expect("Other in Outer");
+    assert!(inner < other && kind < other, "siblings should rank first: {:?}", labels);
+    assert!(!labels.iter().any(|l| l == "Outer.Inner"));
+
+    // Elsewhere they need the enclosing message's name
+    let labels = run_completion(&workspace, &uri, content, Position { line: 12, character: 4 }).await;
+    assert!(labels.iter().any(|l| l == "Outer.Inner"), "got {:?}", labels);
+    assert!(labels.iter().any(|l| l == "Outer.Kind"), "got {:?}", labels);
+    assert!(!labels.iter().any(|l| l == "Inner"));
+}
+
+#[tokio::test]
+async fn test_completion_resolve_adds_documentation() {
+    let dir = tempfile::tempdir().unwrap();
+    let money = r#"syntax = "proto3";
+package shared;
+
+// An amount of money.
+// Units are whole currency units.
+message Money {
+    string currency_code = 1;
+    int64 units = 2;
+}
+"#;
+    std::fs::write(dir.path().join("money.proto"), money).unwrap();
+    let workspace = WorkspaceManager::new();
+    let money_uri = Url::from_file_path(dir.path().join("money.proto")).unwrap();
+    workspace.open_file(&money_uri, money).await.unwrap();
+
+    let uri = Url::from_file_path(dir.path().join("main.proto")).unwrap();
+    let content = r#"syntax = "proto3";
+package api;
+
+/* Lifecycle of an order */
+enum State {
+    STATE_UNSPECIFIED = 0;
+}
+
+message Order {
+    
+}
+"#;
+    workspace.open_file(&uri, content).await.unwrap();
+
+    let params = CompletionParams {
+        text_document_position: TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            position: Position { line: 9, character: 4 },
+        },
+        context: None,
+        work_done_progress_params: WorkDoneProgressParams::default(),
+        partial_result_params: PartialResultParams::default(),
+    };
+    let Some(CompletionResponse::Array(items)) =
+        provide_completion(params, &workspace, Some(content)).await
+    else {
+        panic!("expected CompletionResponse::Array");
+    };
+    let documentation = |label: &str| {
+        let item = items.iter().find(|i| i.label == label).unwrap().clone();
+        // The list itself stays lightweight
+        assert!(item.documentation.is_none());
+        match resolve_completion_item(item, &workspace).documentation {
+            Some(Documentation::MarkupContent(markup)) => markup.value,
+            other => panic!("expected markdown documentation, got {:?}", other),
+        }
+    };
+
+    let money_doc = documentation("Money");
+    assert!(money_doc.starts_with("An amount of money.\nUnits are whole currency units."), "{}", money_doc);
+    assert!(money_doc.contains("int64 units = 2;"), "{}", money_doc);
+    assert!(money_doc.contains("Defined in `money.proto`"), "{}", money_doc);
+    assert!(money_doc.contains("Adds `import \"money.proto\";`"), "{}", money_doc);
+
+    let state_doc = documentation("State");
+    assert!(state_doc.starts_with("Lifecycle of an order"), "{}", state_doc);
+    assert!(state_doc.contains("STATE_UNSPECIFIED = 0;"), "{}", state_doc);
+    assert!(state_doc.contains("Defined in `main.proto`"), "{}", state_doc);
+    assert!(!state_doc.contains("Adds"), "{}", state_doc);
+}
+
+#[tokio::test]
+async fn test_completion_suggests_field_and_enum_value_names() {
+    let workspace = WorkspaceManager::new();
+    let uri = Url::parse("file:///test/naming.proto").unwrap();
+    let content = r#"syntax = "proto3";
+package test;
+
+message UserProfile {}
+
+message Account {
+    string name = 1;
+    UserProfile user_profile = 2;
+}
+
+enum OrderState {
+}
+
+enum Color {
+    COLOR_UNSPECIFIED = 0;
+}
+"#;
+    workspace.open_file(&uri, content).await.unwrap();
+
+    let typing = content.replace("    UserProfile user_profile = 2;\n", "    UserProfile user_profile = 2;\n    repeated UserProfile \n");
+    let labels = run_completion(&workspace, &uri, &typing, Position { line: 8, character: 25 }).await;
+    assert_eq!(labels, vec!["user_profiles", "profiles"]);
+
+    // Names already used in the message are skipped
+    let typing = content.replace("    UserProfile user_profile = 2;\n", "    UserProfile user_profile = 2;\n    UserProfile \n");
+    let labels = run_completion(&workspace, &uri, &typing, Position { line: 8, character: 16 }).await;
+    assert_eq!(labels, vec!["profile"]);
+
+    let typing = content.replace("enum OrderState {\n", "enum OrderState {\n    \n");
+    let labels = run_completion(&workspace, &uri, &typing, Position { line: 11, character: 4 }).await;
+    assert_eq!(labels[0], "ORDER_STATE_UNSPECIFIED");
+
+    let typing = content.replace("    COLOR_UNSPECIFIED = 0;\n", "    COLOR_UNSPECIFIED = 0;\n    \n");
+    let labels = run_completion(&workspace, &uri, &typing, Position { line: 15, character: 4 }).await;
+    assert_eq!(labels[0], "COLOR_…");
+
+    // A line inserted above a member sits where the last good parse has it;
+    // the member keeps its name and number
+    let typing = content.replace("    UserProfile user_profile = 2;\n", "    UserProfile \n    UserProfile user_profile = 2;\n");
+    let labels = run_completion(&workspace, &uri, &typing, Position { line: 7, character: 16 }).await;
+    assert_eq!(labels, vec!["profile"]);
+
+    let typing = content.replace("    COLOR_UNSPECIFIED = 0;\n", "    \n    COLOR_UNSPECIFIED = 0;\n");
+    let labels = run_completion(&workspace, &uri, &typing, Position { line: 15, character: 4 }).await;
+    assert_eq!(labels[0], "COLOR_…");
+}
+
+#[tokio::test]
+async fn test_completion_fuzzy_matches_and_ranks_by_usage() {
+    let workspace = WorkspaceManager::new();
+    let uri = Url::parse("file:///test/ranking.proto").unwrap();
+    let content = r#"syntax = "proto3";
+package test;
+
+message UserProfile {}
+message UserPreference {}
+message Upload {}
+
+message Account {
+    UserPreference preference = 1;
+    repeated UserPreference history = 2;
+    UsrPr
+}
+"#;
+    workspace.open_file(&uri, &content.replace("    UsrPr\n", "")).await.unwrap();
+
+    // Camel humps match; the type already used in the file ranks first
+    let labels = run_completion(&workspace, &uri, content, Position { line: 10, character: 9 }).await;
+    assert_eq!(labels[..2], ["UserPreference", "UserProfile"]);
+    // Items that do not match stay in the list, after the matches, for the
+    // client to filter as typing continues
+    let upload = labels.iter().position(|l| l == "Upload").expect("non-matching items are kept");
+    assert!(upload > 1);
+
+    // Long lists are cut and marked incomplete
+    let mut many = String::from("syntax = \"proto3\";\npackage test;\n\nmessage Holder {\n    \n}\n");
+    for i in 0..300 {
+        many.push_str(&format!("message Generated{} {{}}\n", i));
+    }
+    workspace.open_file(&uri, &many).await.unwrap();
+    let params = CompletionParams {
+        text_document_position: TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            position: Position { line: 4, character: 4 },
+        },
+        context: None,
+        work_done_progress_params: WorkDoneProgressParams::default(),
+        partial_result_params: PartialResultParams::default(),
+    };
+    match provide_completion(params, &workspace, Some(&many)).await {
+        Some(CompletionResponse::List(list)) => {
+            assert!(list.is_incomplete);
+            assert_eq!(list.items.len(), 200);
+        }
+        other => panic!("expected an incomplete list, got {:?}", other.map(|_| ())),
+    }
+}